@@ -1,7 +1,52 @@
+use crate::core::architecture;
+use crate::core::cleanup::{self, CleanupPlan, StaleReason};
+use crate::core::coverage;
+use crate::core::doctest;
+use crate::core::links;
+use crate::core::patch;
+use crate::core::plan::{ActionPlan, PlannedCoverage, PlannedFile, PlannedPullRequest};
+use crate::core::readme;
+use crate::core::report::{self, RunSummary};
+use crate::core::review;
+use crate::core::splice::{self, DocEdit};
+use crate::core::summaries;
+use crate::core::symbols;
 use crate::github_client::client as github_client;
-use crate::ollama_client::client as ollama_client;
-use crate::utils::errors::DredgerError;
+use crate::github_client::data::{FileChange, RepoNode};
+use crate::languages::registry;
+use crate::local_client::client as local_client;
+use crate::ollama_client::client::{self as ollama_client, DredgerDoc};
+use crate::utils::config::{CoverageConfig, CoverageReport, OutputMode, RepoConfig};
+use crate::utils::errors::{github_error, DredgerError};
+use crate::utils::tokens::load_tokenizer;
 use colored::*;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+const PR_TITLE: &str = "Dredger: Add documentation";
+
+/// Where the docs of a `dredge_repo` run ended up
+#[derive(Debug)]
+pub enum DredgeOutcome {
+    /// No file needed new docs
+    NoChanges,
+    /// URL of the opened or refreshed pull request, and the labels,
+    /// assignees or milestone that couldn't be applied to it
+    PullRequest {
+        url: String,
+        metadata_failures: Vec<String>,
+    },
+    /// Patch files written to disk, in the order to apply them
+    Patches(Vec<PathBuf>),
+    /// Files of a local checkout rewritten in place, and the SHA of the
+    /// commit holding them if one was made
+    Applied {
+        files: Vec<PathBuf>,
+        commit: Option<String>,
+    },
+}
 
 /// This is the most important function of dredger
 ///
@@ -10,12 +55,19 @@ use colored::*;
 ///   estimated # of language model tokens required to parse the content
 /// - Passes parsed repo content to the ollama client, which will
 ///   chunk up the content into LLM-digestible sizes
+/// - Splices the generated docs into their files and, depending on the
+///   repo's output mode, opens a PR or writes patch files
+/// - Posts the doc coverage of the dredged commit as a commit status or
+///   check run, when configured (never in patch mode)
 pub async fn dredge_repo(
     quiet: bool,
     repo_owner: String,
     repo_name: String,
     tokenizer_path: String,
-) -> Result<(), Box<DredgerError>> {
+    repo_config: &RepoConfig,
+) -> Result<DredgeOutcome, Box<DredgerError>> {
+    let started_at = Instant::now();
+
     // First, read the repo into dredger RepoNode structure
     // - root node (dir node)
     //   - dir node
@@ -28,7 +80,767 @@ pub async fn dredge_repo(
     //
     // FIXME: Define the tokenizer here, then pass it around instead of re-creating it each
     //        call to parse_repo_recursive
-    let root_node = github_client::read_repo(repo_owner, repo_name, tokenizer_path).await?;
+    //
+    // Coverage is posted to a specific commit, so the repo is pinned to
+    // the default branch's head before reading it
+    let coverage_sha = match repo_config.coverage.report {
+        Some(_) if repo_config.output != OutputMode::Patch => {
+            Some(default_branch_sha(&repo_owner, &repo_name).await?)
+        }
+        _ => None,
+    };
+    let mut root_node = github_client::read_repo(
+        repo_owner.clone(),
+        repo_name.clone(),
+        tokenizer_path.clone(),
+        coverage_sha.clone(),
+    )
+    .await?;
+
+    if let Some(sha) = &coverage_sha {
+        // Coverage is informational, so failing to post it doesn't stop the run
+        match report_coverage(
+            &repo_owner,
+            &repo_name,
+            sha,
+            &root_node,
+            &repo_config.coverage,
+        )
+        .await
+        {
+            Ok(summary) if !quiet => println!("{} {}", "Doc coverage:".bold(), summary),
+            Ok(_) => {}
+            Err(e) => eprintln!("Could not report doc coverage: {}", e),
+        }
+    }
+
+    let edits = generate_edits(
+        &mut root_node,
+        &repo_name,
+        Some(&tokenizer_path),
+        repo_config,
+    )
+    .await?;
+
+    if edits.is_empty() {
+        if !quiet {
+            println!("{}", "\nNo documentation changes to propose.\n".yellow());
+        }
+        return Ok(DredgeOutcome::NoChanges);
+    }
+
+    // Patch mode must never write to GitHub, so it returns before any
+    // of the pull request plumbing runs
+    if repo_config.output == OutputMode::Patch {
+        let written = patch::write_patches(&edits, &repo_config.patch).map_err(Box::new)?;
+        return Ok(DredgeOutcome::Patches(written));
+    }
+
+    let changes: Vec<FileChange> = edits.iter().map(|edit| edit.change.clone()).collect();
+    let pr_body = report::render_pr_body(&RunSummary {
+        model: ollama_client::MODEL,
+        prompt_version: ollama_client::PROMPT_VERSION,
+        duration: started_at.elapsed(),
+        edits: &edits,
+    });
+
+    let pr = github_client::publish_docs_pr(
+        &repo_owner,
+        &repo_name,
+        &changes,
+        &docs_commit_message(&changes),
+        PR_TITLE,
+        &pr_body,
+        repo_config,
+    )
+    .await
+    .map_err(|e| {
+        if !quiet {
+            println!("{}", "\n❌ Could not open pull request.\n".bold().red());
+        }
+        Box::new(DredgerError::GithubClientError(e.to_string()))
+    })?;
+
+    Ok(DredgeOutcome::PullRequest {
+        url: pr.url,
+        metadata_failures: pr.metadata_failures,
+    })
+}
+
+/// Same as `dredge_repo`, but for a checkout on disk: the docs are
+/// written straight into the files under `repo_root`, or into patch
+/// files when the repo's output mode says so.
+///
+/// With `local.commit` configured, a branch is created and the docs are
+/// committed to it as the configured author.
+pub async fn dredge_local_repo(
+    quiet: bool,
+    repo_root: PathBuf,
+    tokenizer_path: String,
+    repo_config: &RepoConfig,
+) -> Result<DredgeOutcome, Box<DredgerError>> {
+    let mut root_node = local_client::read_repo(&repo_root, &tokenizer_path)?;
+    let edits = generate_edits(
+        &mut root_node,
+        &local_repo_name(&repo_root),
+        Some(&tokenizer_path),
+        repo_config,
+    )
+    .await?;
+
+    if edits.is_empty() {
+        if !quiet {
+            println!("{}", "\nNo documentation changes to propose.\n".yellow());
+        }
+        return Ok(DredgeOutcome::NoChanges);
+    }
+
+    if repo_config.output == OutputMode::Patch {
+        let written = patch::write_patches(&edits, &repo_config.patch).map_err(Box::new)?;
+        return Ok(DredgeOutcome::Patches(written));
+    }
+
+    let changes: Vec<FileChange> = edits.iter().map(|edit| edit.change.clone()).collect();
+    let local_config = &repo_config.local;
+
+    // Branch off before touching any file, so a failure leaves the
+    // working tree as it was
+    if local_config.commit {
+        let branch = match &local_config.branch {
+            Some(branch) => branch.clone(),
+            None => {
+                let head_sha = local_client::head_sha(&repo_root).map_err(Box::new)?;
+                github_client::run_branch_name(&head_sha, &changes)
+            }
+        };
+        local_client::create_branch(&repo_root, &branch).map_err(Box::new)?;
+    }
+
+    let files = local_client::write_changes(&repo_root, &changes).map_err(Box::new)?;
+
+    let commit = if local_config.commit {
+        Some(
+            local_client::commit_changes(
+                &repo_root,
+                &changes,
+                &docs_commit_message(&changes),
+                &local_config.author_name,
+                &local_config.author_email,
+            )
+            .map_err(Box::new)?,
+        )
+    } else {
+        None
+    };
+
+    Ok(DredgeOutcome::Applied { files, commit })
+}
+
+/// Dry run of `dredge_repo`: reads the repo, counts tokens and, unless
+/// `skip_llm` is set, generates the docs, then works out exactly what
+/// the run would do. Only read requests are sent to GitHub.
+pub async fn plan_repo(
+    repo_owner: String,
+    repo_name: String,
+    tokenizer_path: String,
+    repo_config: &RepoConfig,
+    skip_llm: bool,
+) -> Result<ActionPlan, Box<DredgerError>> {
+    let started_at = Instant::now();
+    let mut root_node = github_client::read_repo(
+        repo_owner.clone(),
+        repo_name.clone(),
+        tokenizer_path.clone(),
+        None,
+    )
+    .await?;
+    let edits = if skip_llm {
+        Vec::new()
+    } else {
+        generate_edits(
+            &mut root_node,
+            &repo_name,
+            Some(&tokenizer_path),
+            repo_config,
+        )
+        .await?
+    };
+
+    let mut plan = base_plan(
+        format!("{}/{}", repo_owner, repo_name),
+        &root_node,
+        &edits,
+        repo_config,
+        skip_llm,
+    );
+
+    if repo_config.output == OutputMode::Patch {
+        plan.written_files = written_patch_paths(&edits, repo_config);
+        return Ok(plan);
+    }
+
+    let token = std::env::var("GITHUB_PAT").map_err(|e| Box::new(DredgerError::VarError(e)))?;
+    let client = reqwest::Client::new();
+
+    let upstream = github_client::get_repository(&client, &repo_owner, &repo_name, &token)
+        .await
+        .map_err(github_error)?;
+    let base_sha = github_client::get_branch_sha(
+        &client,
+        &repo_owner,
+        &repo_name,
+        &upstream.default_branch,
+        &token,
+    )
+    .await
+    .map_err(github_error)?;
+
+    // Decided the way the real run decides it, without creating the fork
+    let push_decision = github_client::push_decision(&upstream, repo_config);
+    let head_owner = push_decision
+        .owner(&client, &upstream, &token)
+        .await
+        .map_err(github_error)?;
+
+    let existing_pr =
+        github_client::find_dredger_pr(&client, &repo_owner, &repo_name, &head_owner, &token)
+            .await
+            .map_err(github_error)?;
+
+    let changes: Vec<FileChange> = edits.iter().map(|edit| edit.change.clone()).collect();
+    plan.branch = Some(match &existing_pr {
+        Some(pr) => pr.head.branch.clone(),
+        None => github_client::run_branch_name(&base_sha, &changes),
+    });
+
+    let pr_config = &repo_config.pull_request;
+    plan.pull_request = Some(PlannedPullRequest {
+        existing_number: existing_pr.map(|pr| pr.number),
+        fork: (push_decision != github_client::PushDecision::Upstream)
+            .then(|| format!("{}/{}", head_owner, repo_name)),
+        base: upstream.default_branch,
+        title: PR_TITLE.to_string(),
+        body: report::render_pr_body(&RunSummary {
+            model: ollama_client::MODEL,
+            prompt_version: ollama_client::PROMPT_VERSION,
+            duration: started_at.elapsed(),
+            edits: &edits,
+        }),
+        draft: pr_config.draft,
+        labels: pr_config.labels.clone(),
+        assignees: pr_config.assignees.clone(),
+        milestone: pr_config.milestone.clone(),
+    });
+
+    if let Some(report) = repo_config.coverage.report {
+        let coverage = coverage::compute_coverage(&root_node);
+        plan.coverage = Some(PlannedCoverage {
+            sha: base_sha,
+            report: match report {
+                CoverageReport::Status => "status".to_string(),
+                CoverageReport::CheckRun => "check_run".to_string(),
+            },
+            passes: coverage.passes(repo_config.coverage.min_percent),
+            summary: coverage.summary(),
+            undocumented: coverage.undocumented.len(),
+        });
+    }
+
+    Ok(plan)
+}
+
+/// Dry run of `dredge_local_repo`: works out which files would be written
+/// and which branch would be created, without touching the checkout
+pub async fn plan_local_repo(
+    repo_root: PathBuf,
+    tokenizer_path: String,
+    repo_config: &RepoConfig,
+    skip_llm: bool,
+) -> Result<ActionPlan, Box<DredgerError>> {
+    let mut root_node = local_client::read_repo(&repo_root, &tokenizer_path)?;
+    let edits = if skip_llm {
+        Vec::new()
+    } else {
+        generate_edits(
+            &mut root_node,
+            &local_repo_name(&repo_root),
+            Some(&tokenizer_path),
+            repo_config,
+        )
+        .await?
+    };
+
+    let mut plan = base_plan(
+        repo_root.display().to_string(),
+        &root_node,
+        &edits,
+        repo_config,
+        skip_llm,
+    );
+
+    if repo_config.output == OutputMode::Patch {
+        plan.written_files = written_patch_paths(&edits, repo_config);
+        return Ok(plan);
+    }
+
+    plan.output = "local".to_string();
+    plan.written_files = edits
+        .iter()
+        .map(|edit| repo_root.join(&edit.change.path).display().to_string())
+        .collect();
+
+    if repo_config.local.commit {
+        let changes: Vec<FileChange> = edits.iter().map(|edit| edit.change.clone()).collect();
+        plan.branch = Some(match &repo_config.local.branch {
+            Some(branch) => branch.clone(),
+            None => {
+                let head_sha = local_client::head_sha(&repo_root).map_err(Box::new)?;
+                github_client::run_branch_name(&head_sha, &changes)
+            }
+        });
+    }
+
+    Ok(plan)
+}
+
+/// The parts of a plan that don't depend on where the docs end up
+fn base_plan(
+    repo: String,
+    root_node: &RepoNode,
+    edits: &[DocEdit],
+    repo_config: &RepoConfig,
+    skip_llm: bool,
+) -> ActionPlan {
+    let scanned: Vec<&RepoNode> = root_node
+        .iter()
+        .filter(|node| matches!(node, RepoNode::File { .. }))
+        .collect();
+
+    let files = if skip_llm {
+        scanned
+            .iter()
+            .filter_map(|node| match node {
+                RepoNode::File {
+                    path, token_count, ..
+                } if registry::for_path(path).is_some() => Some(PlannedFile {
+                    path: path.clone(),
+                    token_count: *token_count,
+                    lines_added: 0,
+                    lines_replaced: 0,
+                }),
+                _ => None,
+            })
+            .collect()
+    } else {
+        edits
+            .iter()
+            .map(|edit| PlannedFile {
+                path: edit.change.path.clone(),
+                token_count: edit.token_count,
+                lines_added: edit.lines_added,
+                lines_replaced: edit.lines_replaced,
+            })
+            .collect()
+    };
+
+    ActionPlan {
+        repo,
+        output: match repo_config.output {
+            OutputMode::PullRequest => "pull_request".to_string(),
+            OutputMode::Patch => "patch".to_string(),
+        },
+        files_scanned: scanned.len(),
+        total_tokens: root_node.token_count(),
+        llm_skipped: skip_llm,
+        files,
+        branch: None,
+        pull_request: None,
+        coverage: None,
+        written_files: Vec::new(),
+    }
+}
+
+fn written_patch_paths(edits: &[DocEdit], repo_config: &RepoConfig) -> Vec<String> {
+    patch::render_patches(edits, &repo_config.patch)
+        .into_iter()
+        .map(|(path, _)| path.display().to_string())
+        .collect()
+}
+
+/// Review-comment mode: instead of opening a separate PR, generate docs
+/// only for the files pull request `pr_number` changes, and post them on
+/// that PR as a review made of `suggestion` blocks.
+///
+/// With `dry_run` set, the review is printed instead of posted.
+///
+/// Returns the URL of the review, or `None` if there was nothing to
+/// suggest or it wasn't posted.
+pub async fn review_pr(
+    quiet: bool,
+    repo_owner: String,
+    repo_name: String,
+    pr_number: u64,
+    tokenizer_path: String,
+    repo_config: &RepoConfig,
+    dry_run: bool,
+) -> Result<Option<String>, Box<DredgerError>> {
+    let (pr, pr_files, mut pr_node) =
+        github_client::read_pull_request(&repo_owner, &repo_name, pr_number, &tokenizer_path)
+            .await?;
+
+    // The PR's tree only holds the files it changes, so there's no
+    // README to go on, nor enough of the repo to summarize
+    let mut repo_config = repo_config.clone();
+    repo_config.readme.generate = false;
+    let edits = generate_edits(&mut pr_node, &repo_name, None, &repo_config).await?;
+
+    let mut comments = Vec::new();
+    let mut unanchored = 0;
+    let mut files_commented = 0;
+    for edit in &edits {
+        let Some(pr_file) = pr_files.iter().find(|f| f.filename == edit.change.path) else {
+            continue;
+        };
+        let (file_comments, file_unanchored) =
+            review::suggestions_for_file(pr_file, &edit.original_content, &edit.change.content);
+        if !file_comments.is_empty() {
+            files_commented += 1;
+        }
+        comments.extend(file_comments);
+        unanchored += file_unanchored;
+    }
+
+    if comments.is_empty() {
+        if !quiet {
+            println!("{}", "\nNo documentation suggestions to post.\n".yellow());
+        }
+        return Ok(None);
+    }
+
+    let mut body = format!(
+        "🦀 Dredger suggests documentation for {} file(s) in this PR.",
+        files_commented
+    );
+    if unanchored > 0 {
+        body.push_str(&format!(
+            "\n\n{} suggestion(s) were left out because they fall outside the lines this PR changes.",
+            unanchored
+        ));
+    }
+
+    if dry_run {
+        println!(
+            "Dry run: would post this review on PR #{}\n\n{}",
+            pr_number, body
+        );
+        for comment in &comments {
+            println!("\n{}:{}\n{}", comment.path, comment.line, comment.body);
+        }
+        return Ok(None);
+    }
+
+    let token = std::env::var("GITHUB_PAT").map_err(|e| Box::new(DredgerError::VarError(e)))?;
+    let review_url = github_client::create_review(
+        &reqwest::Client::new(),
+        &repo_owner,
+        &repo_name,
+        pr_number,
+        &pr.head.sha,
+        &body,
+        &comments,
+        &token,
+    )
+    .await
+    .map_err(|e| {
+        if !quiet {
+            println!("{}", "\n❌ Could not post review.\n".bold().red());
+        }
+        Box::new(DredgerError::GithubClientError(e.to_string()))
+    })?;
+
+    Ok(Some(review_url))
+}
+
+/// Housekeeping for the Dredger PRs and branches of a repo: closes open
+/// Dredger PRs superseded by a newer one, and deletes Dredger branches
+/// that were merged or abandoned more than `max_age_days` ago.
+///
+/// The branches are looked for where Dredger pushes them: the repo
+/// itself, or its fork when the token can't push to it (see
+/// `github_client::push_target`). No fork is created for this.
+///
+/// With `dry_run` set, only prints what it would do.
+pub async fn cleanup_repo(
+    quiet: bool,
+    repo_owner: String,
+    repo_name: String,
+    repo_config: &RepoConfig,
+    max_age_days: i64,
+    dry_run: bool,
+) -> Result<CleanupPlan, Box<DredgerError>> {
+    let token = std::env::var("GITHUB_PAT").map_err(|e| Box::new(DredgerError::VarError(e)))?;
+    let client = reqwest::Client::new();
+
+    let upstream = github_client::get_repository(&client, &repo_owner, &repo_name, &token)
+        .await
+        .map_err(github_error)?;
+    let pulls = github_client::list_pull_requests(&client, &repo_owner, &repo_name, "all", &token)
+        .await
+        .map_err(github_error)?;
+    let target = github_client::existing_push_target(&client, &upstream, repo_config, &token)
+        .await
+        .map_err(github_error)?
+        .unwrap_or(upstream);
+    let (push_owner, push_repo) = (target.owner.login.as_str(), target.name.as_str());
+    let branches = github_client::list_dredger_branches(&client, push_owner, push_repo, &token)
+        .await
+        .map_err(github_error)?;
+
+    let plan = cleanup::plan_cleanup(
+        push_owner,
+        &pulls,
+        &branches,
+        chrono::Utc::now(),
+        chrono::Duration::days(max_age_days),
+    );
+
+    if !quiet {
+        let verb = if dry_run { "Would close" } else { "Closing" };
+        for pr in &plan.superseded_prs {
+            println!("{} superseded PR #{}: {}", verb, pr.number, pr.html_url);
+        }
+
+        let verb = if dry_run { "Would delete" } else { "Deleting" };
+        for (branch, reason) in &plan.stale_branches {
+            let reason = match reason {
+                StaleReason::Merged => "merged",
+                StaleReason::Abandoned => "abandoned",
+            };
+            println!("{} {} branch {}", verb, reason, branch.name);
+        }
+
+        if plan.superseded_prs.is_empty() && plan.stale_branches.is_empty() {
+            println!("{}", "\nNothing to clean up.\n".yellow());
+        }
+    }
+
+    if dry_run {
+        return Ok(plan);
+    }
+
+    for pr in &plan.superseded_prs {
+        if let Some(latest_pr) = &plan.latest_pr {
+            github_client::add_issue_comment(
+                &client,
+                &repo_owner,
+                &repo_name,
+                pr.number,
+                &format!("Superseded by #{}.", latest_pr.number),
+                &token,
+            )
+            .await
+            .map_err(github_error)?;
+        }
+        github_client::close_pull_request(&client, &repo_owner, &repo_name, pr.number, &token)
+            .await
+            .map_err(github_error)?;
+    }
+
+    for (branch, _) in &plan.stale_branches {
+        github_client::delete_branch(&client, push_owner, push_repo, &branch.name, &token)
+            .await
+            .map_err(github_error)?;
+    }
+
+    Ok(plan)
+}
+
+/// Writes an ARCHITECTURE.md describing a GitHub repo's layout to
+/// `output` (see `architecture::architecture_doc`). Nothing is sent to
+/// GitHub besides the requests reading the repo.
+///
+/// With `dry_run` set, the document is printed instead. Returns the
+/// path written to, if any.
+pub async fn architecture_repo(
+    quiet: bool,
+    repo_owner: String,
+    repo_name: String,
+    tokenizer_path: String,
+    output: PathBuf,
+    dry_run: bool,
+) -> Result<Option<PathBuf>, Box<DredgerError>> {
+    let mut root_node =
+        github_client::read_repo(repo_owner, repo_name, tokenizer_path.clone(), None).await?;
+    write_architecture(quiet, &mut root_node, &tokenizer_path, output, dry_run).await
+}
+
+/// Same as `architecture_repo`, for a checkout on disk. The document
+/// goes to the checkout's root unless `output` says otherwise.
+pub async fn architecture_local_repo(
+    quiet: bool,
+    repo_root: PathBuf,
+    tokenizer_path: String,
+    output: Option<PathBuf>,
+    dry_run: bool,
+) -> Result<Option<PathBuf>, Box<DredgerError>> {
+    let mut root_node = local_client::read_repo(&repo_root, &tokenizer_path)?;
+    let output = output.unwrap_or_else(|| repo_root.join("ARCHITECTURE.md"));
+    write_architecture(quiet, &mut root_node, &tokenizer_path, output, dry_run).await
+}
+
+/// An ARCHITECTURE.md a human wrote is never overwritten, so that's
+/// checked before any of the (slow) summarizing starts
+async fn write_architecture(
+    quiet: bool,
+    root_node: &mut RepoNode,
+    tokenizer_path: &str,
+    output: PathBuf,
+    dry_run: bool,
+) -> Result<Option<PathBuf>, Box<DredgerError>> {
+    if let Ok(existing) = fs::read_to_string(&output) {
+        if !architecture::is_generated(&existing) {
+            return Err(Box::new(DredgerError::OtherError(format!(
+                "{} wasn't written by Dredger, so it's left alone",
+                output.display()
+            ))));
+        }
+    }
+
+    if !quiet {
+        println!("{}", "\nSummarizing the repo's directories...\n".cyan());
+    }
+    let tokenizer =
+        load_tokenizer(tokenizer_path).map_err(|e| Box::new(DredgerError::TokenizerError(e)))?;
+    let document = architecture::architecture_doc(root_node, &tokenizer).await?;
+
+    if dry_run {
+        println!("Dry run: would write {}\n\n{}", output.display(), document);
+        return Ok(None);
+    }
+
+    fs::write(&output, document).map_err(|e| Box::new(DredgerError::IoError(e)))?;
+    Ok(Some(output))
+}
+
+async fn default_branch_sha(
+    repo_owner: &str,
+    repo_name: &str,
+) -> Result<String, Box<DredgerError>> {
+    let token = std::env::var("GITHUB_PAT").map_err(|e| Box::new(DredgerError::VarError(e)))?;
+    let client = reqwest::Client::new();
+
+    let upstream = github_client::get_repository(&client, repo_owner, repo_name, &token)
+        .await
+        .map_err(github_error)?;
+    github_client::get_branch_sha(
+        &client,
+        repo_owner,
+        repo_name,
+        &upstream.default_branch,
+        &token,
+    )
+    .await
+    .map_err(github_error)
+}
+
+/// Computes the doc coverage of `root_node` and posts it to commit `sha`
+/// the way `coverage_config` says. A check run falls back to a commit
+/// status when it can't be created, e.g. because the token isn't a
+/// GitHub App's.
+///
+/// Returns the coverage summary that was posted.
+async fn report_coverage(
+    repo_owner: &str,
+    repo_name: &str,
+    sha: &str,
+    root_node: &RepoNode,
+    coverage_config: &CoverageConfig,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let token = std::env::var("GITHUB_PAT")?;
+    let client = reqwest::Client::new();
+
+    let coverage = coverage::compute_coverage(root_node);
+    let passes = coverage.passes(coverage_config.min_percent);
+    let summary = coverage.summary();
+
+    if coverage_config.report == Some(CoverageReport::CheckRun) {
+        let mut details = summary.clone();
+        if let Some(min_percent) = coverage_config.min_percent {
+            details.push_str(&format!("\n\nRequired: {:.0}%", min_percent));
+        }
+        if !coverage.undocumented.is_empty() {
+            details.push_str(&format!(
+                "\n\n{} public item(s) have no doc comment; the first 50 are annotated.",
+                coverage.undocumented.len()
+            ));
+        }
+
+        let check_run = github_client::create_check_run(
+            &client,
+            repo_owner,
+            repo_name,
+            sha,
+            if passes { "success" } else { "failure" },
+            &summary,
+            &details,
+            &coverage.annotations(),
+            &token,
+        )
+        .await;
+        match check_run {
+            Ok(_) => return Ok(summary),
+            Err(e) => eprintln!(
+                "Could not create check run, posting a commit status instead: {}",
+                e
+            ),
+        }
+    }
+
+    github_client::create_commit_status(
+        &client,
+        repo_owner,
+        repo_name,
+        sha,
+        if passes { "success" } else { "failure" },
+        &summary,
+        &token,
+    )
+    .await?;
+
+    Ok(summary)
+}
+
+/// Summarizes `root_node` (see `summaries::summarize_repo`) with the
+/// tokenizer at `tokenizer_path`, then runs the language model over
+/// every file of it and splices the resulting
+/// docs into their files. Intra-doc links in Rust docs that don't
+/// resolve within the crate are turned into code spans first, and files
+/// the docs would break (see `LanguageStrategy::verify`) are logged and
+/// left out.
+///
+/// With `readme.generate` on, the README's Dredger sections are written
+/// last, from what the other edits say about the repo (see
+/// `readme::readme_edit`).
+///
+/// Without a `tokenizer_path`, nothing is summarized: a tree that's only
+/// part of the repo, like a PR's files, would make for a wrong summary.
+async fn generate_edits(
+    root_node: &mut RepoNode,
+    repo_name: &str,
+    tokenizer_path: Option<&str>,
+    repo_config: &RepoConfig,
+) -> Result<Vec<DocEdit>, Box<DredgerError>> {
+    // The summaries give each file's prompt its directory and repo as
+    // context; without them the docs are only written from the file
+    match tokenizer_path.map(load_tokenizer) {
+        Some(Ok(tokenizer)) => summaries::summarize_repo(root_node, &tokenizer).await,
+        Some(Err(e)) => eprintln!(
+            "Could not load the tokenizer, so the repo isn't summarized: {}",
+            e
+        ),
+        None => {}
+    }
+    let root_node = &*root_node;
 
     // TODO: run Ollama, based on the root node
     // ollama_client::process_root_node();
@@ -37,38 +849,78 @@ pub async fn dredge_repo(
     // ... try and get self-improvement loop, self-rating/self-judging on the docs...
     // ... branching LLM calls in, like 10 equal prompts, and choosing best response...
     // ... if it thinks the docs are good enough, then we can open PR.
-    ollama_client::process_repo(&root_node.clone())
+    let docs = ollama_client::process_repo(root_node, repo_config)
         .await
-        .unwrap();
-
-    // TODO: If ollama generated good docs that are different enough
-    //       from current docs, open PR.
-    let open_new_pr_flag = false;
-
-    if open_new_pr_flag {
-        // TODO: If there's already a Dredger PR open, edit that PR!
-        if let Err(e) = github_client::open_test_pr().await {
-            if quiet {
-                eprintln!("Could not open pull request");
-                // TODO: How to handle this...?
-                //       - Return partial success...?
-                //       - Don't return root_node, but instead just
-                //         a DredgerError?
-            } else {
-                println!(
-                    "{} {}",
-                    "\n❌ Could not open pull request.\n".bold().red(),
-                    e
-                );
-                // TODO: How to handle this...?
-                //       - Return partial success...?
-                //       - Don't return root_node, but instead just
-                //         a DredgerError?
+        .map_err(|e| Box::new(DredgerError::OllamaClientError(e.to_string())))?;
+    // Examples and intra-doc links are checked against the crate
+    let (mut rust_docs, mut docs): (Vec<DredgerDoc>, Vec<DredgerDoc>) =
+        docs.into_iter().partition(|doc| {
+            registry::for_path(&doc.file_path)
+                .is_some_and(|language| language.checks_intra_doc_links())
+        });
+    if repo_config.examples.generate {
+        doctest::check_examples(&mut rust_docs, root_node, &repo_config.examples);
+    }
+
+    let symbols = symbols::crate_symbols(root_node);
+    let mut links_fixed: HashMap<String, usize> = HashMap::new();
+    for doc in rust_docs.iter_mut() {
+        let (comments, fixed) = links::fix_links(&doc.comments, &symbols);
+        doc.comments = comments;
+        *links_fixed.entry(doc.file_path.clone()).or_default() += fixed;
+    }
+    docs.append(&mut rust_docs);
+
+    // TODO: Only open a PR if ollama generated good docs that are
+    //       different enough from current docs.
+    let mut edits: Vec<DocEdit> = splice::apply_docs(root_node, &docs)
+        .into_iter()
+        .map(|mut edit| {
+            edit.links_fixed = links_fixed.get(&edit.change.path).copied().unwrap_or(0);
+            edit
+        })
+        .filter(|edit| {
+            let path = &edit.change.path;
+            let Some(language) = registry::for_path(path) else {
+                return true;
+            };
+            match language.verify(&edit.original_content, &edit.change.content) {
+                Ok(()) => true,
+                Err(reason) => {
+                    eprintln!("Rejected the docs for {}: {}", path, reason);
+                    false
+                }
             }
-        } else {
-            println!("Success! Opened PR!")
+        })
+        .collect();
+
+    if repo_config.readme.generate {
+        if let Some(edit) =
+            readme::readme_edit(root_node, repo_name, &edits, &repo_config.readme).await
+        {
+            edits.push(edit);
         }
     }
 
-    Ok(())
+    Ok(edits)
+}
+
+/// Name of a local checkout: its directory's name
+fn local_repo_name(repo_root: &Path) -> String {
+    repo_root
+        .canonicalize()
+        .ok()
+        .and_then(|path| {
+            path.file_name()
+                .map(|name| name.to_string_lossy().to_string())
+        })
+        .unwrap_or_else(|| "Project".to_string())
+}
+
+fn docs_commit_message(changes: &[FileChange]) -> String {
+    format!(
+        "Add Dredger documentation to {} file(s)\n\n{}",
+        changes.len(),
+        FileChange::describe(changes)
+    )
 }
@@ -0,0 +1,304 @@
+use crate::core::summaries::{self, clean_output};
+use crate::github_client::data::RepoNode;
+use crate::languages::registry;
+use crate::languages::strategy::Prompt;
+use crate::ollama_client::client as ollama_client;
+use crate::utils::errors::DredgerError;
+use tokenizers::Tokenizer;
+
+/// First line of every ARCHITECTURE.md Dredger writes. Files without it
+/// were written by a human and are never overwritten.
+pub const GENERATED_NOTICE: &str =
+    "<!-- Generated by Dredger from the repo's layout. Edits here are overwritten on the next run. -->";
+
+/// Whether `content` is an ARCHITECTURE.md Dredger wrote
+pub fn is_generated(content: &str) -> bool {
+    content.lines().next().map(str::trim) == Some(GENERATED_NOTICE)
+}
+
+/// A directory and what the model said it's responsible for
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirectorySummary {
+    pub path: String,
+    /// How many directories it's nested in, the root's children being 0;
+    /// `None` for the root itself
+    pub depth: Option<usize>,
+    pub summary: String,
+}
+
+/// Whether any file under `node` is in a language Dredger knows, which
+/// keeps directories of docs, assets and fixtures out of the document
+fn has_source_files(node: &RepoNode) -> bool {
+    node.iter().any(
+        |node| matches!(node, RepoNode::File { path, .. } if registry::for_path(path).is_some()),
+    )
+}
+
+/// The summaries `summaries::summarize_repo` stored on the directories
+/// holding source files, sorted by path so each comes right before its
+/// subdirectories
+fn directory_summaries(root_node: &RepoNode) -> Vec<DirectorySummary> {
+    let root_path = match root_node {
+        RepoNode::Directory { path, .. } => path.as_str(),
+        RepoNode::File { .. } => return Vec::new(),
+    };
+
+    let mut summaries: Vec<DirectorySummary> = root_node
+        .iter()
+        .filter_map(|node| match node {
+            RepoNode::Directory {
+                path,
+                summary: Some(summary),
+                ..
+            } if has_source_files(node) => Some(DirectorySummary {
+                path: path.clone(),
+                depth: nesting_depth(root_path, path),
+                summary: summary.clone(),
+            }),
+            _ => None,
+        })
+        .collect();
+
+    summaries.sort_by(|a, b| a.path.split('/').cmp(b.path.split('/')));
+    summaries
+}
+
+/// How many directories below the root `path` is nested, the root's
+/// children being 0, or `None` if `path` is the root
+fn nesting_depth(root_path: &str, path: &str) -> Option<usize> {
+    let relative = path
+        .strip_prefix(root_path)
+        .unwrap_or(path)
+        .trim_matches('/');
+    (!relative.is_empty()).then(|| relative.matches('/').count())
+}
+
+/// The directory summaries as the model gets them for the document's
+/// overall sections
+fn summaries_digest(summaries: &[DirectorySummary]) -> String {
+    summaries
+        .iter()
+        .map(|summary| {
+            let path = match summary.depth {
+                None => "(repo root)",
+                Some(_) => &summary.path,
+            };
+            format!("- {}/: {}", path, summary.summary)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn overview_prompt() -> Prompt {
+    Prompt {
+        system: "You are an AI that documents the architecture of software projects for new contributors.
+             Given what each directory of a repository is responsible for, write the overview of its ARCHITECTURE.md: the main subsystems, what each one does, and how they relate to each other, such as which one calls which and how data flows between them.
+             Write a few short paragraphs or a short list in GitHub-flavored Markdown, without a heading. Only state what the directory summaries support.".to_string(),
+        examples: Vec::new(),
+    }
+}
+
+fn start_reading_prompt() -> Prompt {
+    Prompt {
+        system: "You are an AI that documents the architecture of software projects for new contributors.
+             Given what each directory of a repository is responsible for and the paths of its files, list the three to five files or directories a new contributor should read first, in reading order.
+             Write a Markdown numbered list, one item per path: the path in backticks, then a sentence on why to read it. Only use paths from the given lists, without a heading.".to_string(),
+        examples: Vec::new(),
+    }
+}
+
+/// Drops list items whose first code span isn't a path of the repo, so
+/// the reading list never points at files that don't exist
+pub fn existing_paths_only(list: &str, root_node: &RepoNode) -> String {
+    let paths: Vec<&str> = root_node
+        .iter()
+        .map(|node| match node {
+            RepoNode::File { path, .. } | RepoNode::Directory { path, .. } => path.as_str(),
+        })
+        .collect();
+
+    list.lines()
+        .filter(|line| {
+            let item = line.trim_start();
+            let is_item = item.starts_with("- ")
+                || item.starts_with("* ")
+                || item.split_once(". ").is_some_and(|(number, _)| {
+                    !number.is_empty() && number.chars().all(|c| c.is_ascii_digit())
+                });
+            let Some(span) = item.split('`').nth(1).filter(|_| is_item) else {
+                return true;
+            };
+            let span = span.trim_start_matches("./").trim_end_matches('/');
+            paths.contains(&span)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Puts the document together: the overview, where to start reading,
+/// and the layout as a nested list of directory summaries
+pub fn render_document(
+    overview: &str,
+    start_reading: &str,
+    summaries: &[DirectorySummary],
+) -> String {
+    let mut document = format!("{}\n# Architecture\n\n{}\n", GENERATED_NOTICE, overview);
+
+    if !start_reading.is_empty() {
+        document.push_str(&format!(
+            "\n## Where to start reading\n\n{}\n",
+            start_reading
+        ));
+    }
+
+    let layout: Vec<String> = summaries
+        .iter()
+        // The root's summary is the overview's job
+        .filter_map(|summary| {
+            Some(format!(
+                "{}- `{}/`: {}",
+                "  ".repeat(summary.depth?),
+                summary.path,
+                summary.summary
+            ))
+        })
+        .collect();
+    if !layout.is_empty() {
+        document.push_str(&format!("\n## Layout\n\n{}\n", layout.join("\n")));
+    }
+
+    document
+}
+
+/// Writes an ARCHITECTURE.md for the repo at `root_node`: the repo is
+/// summarized bottom-up (see `summaries::summarize_repo`), and the model
+/// then works out the main subsystems, how they relate and where a
+/// newcomer should start reading from the directories' summaries.
+///
+/// Fails if the model can't be reached for the overview.
+pub async fn architecture_doc(
+    root_node: &mut RepoNode,
+    tokenizer: &Tokenizer,
+) -> Result<String, Box<DredgerError>> {
+    summaries::summarize_repo(root_node, tokenizer).await;
+    let summaries = directory_summaries(root_node);
+    let digest = summaries_digest(&summaries);
+
+    let overview = ollama_client::query_ollama_for_doc(overview_prompt(), &digest)
+        .await
+        .map_err(|e| Box::new(DredgerError::OllamaClientError(e.to_string())))?;
+
+    let file_paths: Vec<&str> = root_node
+        .iter()
+        .filter_map(|node| match node {
+            RepoNode::File { path, .. } if registry::for_path(path).is_some() => {
+                Some(path.as_str())
+            }
+            _ => None,
+        })
+        .collect();
+    let start_reading = match ollama_client::query_ollama_for_doc(
+        start_reading_prompt(),
+        &format!("{}\n\nFiles:\n{}", digest, file_paths.join("\n")),
+    )
+    .await
+    {
+        Ok(output) => existing_paths_only(&clean_output(&output), root_node),
+        Err(e) => {
+            eprintln!("Error querying Ollama for where to start reading: {}", e);
+            String::new()
+        }
+    };
+
+    Ok(render_document(
+        &clean_output(&overview),
+        &start_reading,
+        &summaries,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str) -> RepoNode {
+        RepoNode::File {
+            name: path.rsplit('/').next().unwrap().to_string(),
+            path: path.to_string(),
+            sha: String::new(),
+            content: "//! Parsing.\n\npub fn parse() {}\n".to_string(),
+            token_count: 0,
+            summary: None,
+        }
+    }
+
+    #[test]
+    fn test_render_document_nests_layout_and_checks_paths() {
+        let root_node = RepoNode::Directory {
+            name: String::new(),
+            path: String::new(),
+            token_count: 0,
+            children: vec![
+                file("README.md"),
+                RepoNode::Directory {
+                    name: "src".to_string(),
+                    path: "src".to_string(),
+                    token_count: 0,
+                    children: vec![file("src/main.rs"), file("src/parser.rs")],
+                    summary: Some("The code.".to_string()),
+                },
+                RepoNode::Directory {
+                    name: "docs".to_string(),
+                    path: "docs".to_string(),
+                    token_count: 0,
+                    children: vec![file("docs/README.md")],
+                    summary: Some("The docs.".to_string()),
+                },
+            ],
+            summary: Some("The repo.".to_string()),
+        };
+        assert_eq!(
+            directory_summaries(&root_node)
+                .iter()
+                .map(|summary| (summary.path.as_str(), summary.depth))
+                .collect::<Vec<_>>(),
+            vec![("", None), ("src", Some(0))]
+        );
+
+        let start_reading = existing_paths_only(
+            "1. `src/main.rs`: the entry point.\n2. `src/cli.rs`: made up.\n3. `src/`: everything.",
+            &root_node,
+        );
+        assert_eq!(
+            start_reading,
+            "1. `src/main.rs`: the entry point.\n3. `src/`: everything."
+        );
+
+        let document = render_document(
+            "A parser.",
+            &start_reading,
+            &[
+                DirectorySummary {
+                    path: String::new(),
+                    depth: nesting_depth("", ""),
+                    summary: "The repo.".to_string(),
+                },
+                DirectorySummary {
+                    path: "src".to_string(),
+                    depth: nesting_depth("", "src"),
+                    summary: "The code.".to_string(),
+                },
+                DirectorySummary {
+                    path: "src/parser".to_string(),
+                    depth: nesting_depth("", "src/parser"),
+                    summary: "Parsing.".to_string(),
+                },
+            ],
+        );
+        assert!(is_generated(&document));
+        assert!(
+            document.ends_with("## Layout\n\n- `src/`: The code.\n  - `src/parser/`: Parsing.\n")
+        );
+        assert!(!is_generated("# Architecture\n"));
+    }
+}
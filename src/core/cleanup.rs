@@ -0,0 +1,141 @@
+use crate::github_client::client::is_dredger_pr;
+use crate::github_client::data::{DredgerBranch, PullRequest};
+use chrono::{DateTime, Duration, Utc};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StaleReason {
+    Merged,    // Its PR was merged
+    Abandoned, // No open PR uses it anymore
+}
+
+/// What a cleanup run would close and delete
+#[derive(Debug, Default)]
+pub struct CleanupPlan {
+    /// Open Dredger PRs that a newer open Dredger PR replaces
+    pub superseded_prs: Vec<PullRequest>,
+    /// The open Dredger PR that stays, if any
+    pub latest_pr: Option<PullRequest>,
+    pub stale_branches: Vec<(DredgerBranch, StaleReason)>,
+}
+
+/// Works out which Dredger PRs and branches of `owner`'s repo to clean up.
+///
+/// All open Dredger PRs but the newest are superseded. A Dredger branch is
+/// stale once its last commit is older than `max_age`, and either its PR
+/// was merged or no remaining open PR uses it.
+pub fn plan_cleanup(
+    owner: &str,
+    pulls: &[PullRequest],
+    branches: &[DredgerBranch],
+    now: DateTime<Utc>,
+    max_age: Duration,
+) -> CleanupPlan {
+    let head_prefix = format!("{}:", owner);
+    let mut dredger_prs: Vec<&PullRequest> = pulls
+        .iter()
+        .filter(|pr| pr.head.label.starts_with(&head_prefix) && is_dredger_pr(pr))
+        .collect();
+    dredger_prs.sort_by_key(|pr| std::cmp::Reverse(pr.number));
+
+    let mut open_prs = dredger_prs.iter().filter(|pr| pr.state == "open");
+    let latest_pr = open_prs.next().map(|pr| (*pr).clone());
+    let superseded_prs: Vec<PullRequest> = open_prs.map(|pr| (*pr).clone()).collect();
+
+    let mut stale_branches = Vec::new();
+    for branch in branches {
+        if now - branch.last_commit_at < max_age {
+            continue;
+        }
+
+        let merged = dredger_prs
+            .iter()
+            .any(|pr| pr.head.branch == branch.name && pr.merged_at.is_some());
+        let in_use = latest_pr
+            .as_ref()
+            .is_some_and(|pr| pr.head.branch == branch.name);
+
+        if merged {
+            stale_branches.push((branch.clone(), StaleReason::Merged));
+        } else if !in_use {
+            stale_branches.push((branch.clone(), StaleReason::Abandoned));
+        }
+    }
+
+    CleanupPlan {
+        superseded_prs,
+        latest_pr,
+        stale_branches,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::github_client::data::PullRequestRef;
+
+    fn pr(number: u64, branch: &str, state: &str, merged: bool) -> PullRequest {
+        let head = PullRequestRef {
+            branch: branch.to_string(),
+            sha: "sha".to_string(),
+            label: format!("owner:{}", branch),
+        };
+        PullRequest {
+            number,
+            html_url: format!("https://github.com/owner/repo/pull/{}", number),
+            title: "Dredger: Add documentation".to_string(),
+            body: None,
+            state: state.to_string(),
+            merged_at: merged.then(Utc::now),
+            head: head.clone(),
+            base: head,
+            labels: Vec::new(),
+        }
+    }
+
+    fn branch(name: &str, age_days: i64) -> DredgerBranch {
+        DredgerBranch {
+            name: name.to_string(),
+            sha: "sha".to_string(),
+            last_commit_at: Utc::now() - Duration::days(age_days),
+        }
+    }
+
+    #[test]
+    fn test_plan_cleanup() {
+        let pulls = vec![
+            pr(3, "dredger/docs-c", "open", false),
+            pr(2, "dredger/docs-b", "open", false),
+            pr(1, "dredger/docs-a", "closed", true),
+            pr(4, "feature/unrelated", "open", false),
+        ];
+        let branches = vec![
+            branch("dredger/docs-a", 30),
+            branch("dredger/docs-b", 30),
+            branch("dredger/docs-c", 30),
+            branch("dredger/docs-d", 2),
+        ];
+
+        let plan = plan_cleanup("owner", &pulls, &branches, Utc::now(), Duration::days(14));
+
+        assert_eq!(plan.latest_pr.map(|pr| pr.number), Some(3));
+        assert_eq!(
+            plan.superseded_prs
+                .iter()
+                .map(|pr| pr.number)
+                .collect::<Vec<_>>(),
+            vec![2]
+        );
+        let stale: Vec<(&str, StaleReason)> = plan
+            .stale_branches
+            .iter()
+            .map(|(branch, reason)| (branch.name.as_str(), *reason))
+            .collect();
+        assert_eq!(
+            stale,
+            vec![
+                ("dredger/docs-a", StaleReason::Merged),
+                ("dredger/docs-b", StaleReason::Abandoned),
+            ]
+        );
+    }
+}
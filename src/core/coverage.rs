@@ -0,0 +1,189 @@
+use crate::github_client::data::{CheckAnnotation, RepoNode};
+use crate::languages::registry;
+use crate::languages::strategy::LanguageStrategy;
+
+/// A public item without any doc comment
+#[derive(Debug, Clone, PartialEq)]
+pub struct UndocumentedItem {
+    pub path: String,
+    /// 1-based line of the item's declaration
+    pub line: usize,
+    pub kind: String,
+    pub name: String,
+}
+
+/// How many of a repo's public items carry docs
+#[derive(Debug, Default)]
+pub struct Coverage {
+    pub documented: usize,
+    pub total: usize,
+    pub undocumented: Vec<UndocumentedItem>,
+}
+
+impl Coverage {
+    /// Share of public items that are documented. A repo without any
+    /// public items counts as fully documented.
+    pub fn percent(&self) -> f64 {
+        if self.total == 0 {
+            return 100.0;
+        }
+        self.documented as f64 * 100.0 / self.total as f64
+    }
+
+    /// One-line summary, e.g. "87% of public items documented (26/30)"
+    pub fn summary(&self) -> String {
+        format!(
+            "{:.0}% of public items documented ({}/{})",
+            self.percent(),
+            self.documented,
+            self.total
+        )
+    }
+
+    /// Whether coverage meets `min_percent`; anything passes without one
+    pub fn passes(&self, min_percent: Option<f64>) -> bool {
+        min_percent.is_none_or(|min_percent| self.percent() >= min_percent)
+    }
+
+    /// One warning per undocumented item, for a check run
+    pub fn annotations(&self) -> Vec<CheckAnnotation> {
+        self.undocumented
+            .iter()
+            .map(|item| CheckAnnotation {
+                path: item.path.clone(),
+                start_line: item.line,
+                end_line: item.line,
+                annotation_level: "warning".to_string(),
+                message: format!("Public {} `{}` has no doc comment", item.kind, item.name),
+            })
+            .collect()
+    }
+}
+
+/// Counts the documented public items of every file Dredger documents,
+/// as listed by its strategy's `coverage_items`. Files that don't parse
+/// are left out.
+pub fn compute_coverage(root: &RepoNode) -> Coverage {
+    let mut coverage = Coverage::default();
+
+    for node in root.iter() {
+        if let RepoNode::File { path, content, .. } = node {
+            if let Some(language) = registry::for_path(path) {
+                count_items(language, path, content, &mut coverage);
+            }
+        }
+    }
+
+    coverage
+        .undocumented
+        .sort_by(|a, b| (&a.path, a.line).cmp(&(&b.path, b.line)));
+    coverage
+}
+
+fn count_items(
+    language: &dyn LanguageStrategy,
+    path: &str,
+    content: &str,
+    coverage: &mut Coverage,
+) {
+    let Ok(items) = language.coverage_items(content) else {
+        return;
+    };
+
+    for item in items {
+        coverage.total += 1;
+        if item.existing_docs.is_some() {
+            coverage.documented += 1;
+        } else {
+            coverage.undocumented.push(UndocumentedItem {
+                path: path.to_string(),
+                line: item.line,
+                kind: item.kind.to_string(),
+                name: item.name,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compute_coverage_counts_public_items() {
+        let content = "\
+pub use other::Thing;
+pub mod elsewhere;
+
+/// Documented
+#[derive(Debug)]
+pub struct Documented;
+
+pub(crate) fn internal() {}
+
+pub async fn undocumented() {}
+
+/** Block docs */
+pub const fn block() {}
+
+pub const LIMIT: usize = 3;
+
+/* Not docs */
+pub fn plain() {
+    let _ = \"pub fn fake() {}\";
+}
+
+/// Documented
+#[cfg_attr(
+    feature = \"serde\",
+    derive(Serialize)
+)]
+pub struct Multi;
+";
+        let root = RepoNode::Directory {
+            name: "".to_string(),
+            path: "".to_string(),
+            token_count: 0,
+            children: vec![RepoNode::File {
+                name: "lib.rs".to_string(),
+                path: "src/lib.rs".to_string(),
+                sha: "abc".to_string(),
+                content: content.to_string(),
+                token_count: 0,
+                summary: None,
+            }],
+            summary: None,
+        };
+
+        let coverage = compute_coverage(&root);
+
+        assert_eq!(coverage.total, 6);
+        assert_eq!(coverage.documented, 3);
+        assert_eq!(
+            coverage.undocumented,
+            vec![
+                UndocumentedItem {
+                    path: "src/lib.rs".to_string(),
+                    line: 10,
+                    kind: "fn".to_string(),
+                    name: "undocumented".to_string(),
+                },
+                UndocumentedItem {
+                    path: "src/lib.rs".to_string(),
+                    line: 15,
+                    kind: "const".to_string(),
+                    name: "LIMIT".to_string(),
+                },
+                UndocumentedItem {
+                    path: "src/lib.rs".to_string(),
+                    line: 18,
+                    kind: "fn".to_string(),
+                    name: "plain".to_string(),
+                },
+            ]
+        );
+        assert_eq!(coverage.summary(), "50% of public items documented (3/6)");
+        assert!(coverage.passes(Some(50.0)));
+        assert!(!coverage.passes(Some(80.0)));
+    }
+}
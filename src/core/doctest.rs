@@ -0,0 +1,464 @@
+use crate::core::symbols::{self, Symbols};
+use crate::github_client::data::RepoNode;
+use crate::ollama_client::client::{DocAnchor, DredgerDoc};
+use crate::utils::config::{ExamplesConfig, FailedExample};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use syn::visit::{self, Visit};
+use tempfile::TempDir;
+
+/// Checks the fenced Rust code in the `# Examples` of each item doc,
+/// marking failing snippets `ignore` or removing them, as configured.
+///
+/// A snippet has to parse, and every path into the crate it uses
+/// (`my_crate::...`) has to name items that exist. With `compile` on,
+/// and when the crate builds in a scratch crate next to it, snippets
+/// have to compile too. That runs the crate's build scripts and proc
+/// macros, with a cleared environment (see `CARGO_ENV`).
+pub fn check_examples(docs: &mut [DredgerDoc], root: &RepoNode, config: &ExamplesConfig) {
+    let symbols = symbols::crate_symbols(root);
+    let scratch = if config.compile {
+        match ScratchCrate::new(root, &symbols) {
+            Ok(scratch) => Some(scratch),
+            Err(e) => {
+                eprintln!("Not compiling examples: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    for doc in docs.iter_mut() {
+        let DocAnchor::Item { name, .. } = &doc.anchor else {
+            continue;
+        };
+
+        doc.comments = fix_examples(&doc.comments, config.on_failure, |code| {
+            let result = check_snippet(code, &symbols, scratch.as_ref());
+            if let Err(reason) = &result {
+                eprintln!("Example for {} in {} {}", name, doc.file_path, reason);
+            }
+            result
+        });
+    }
+}
+
+/// Runs `check` over the code of each fenced Rust block in `comments`
+/// (`///` lines), and marks the blocks it fails `ignore` or drops them.
+fn fix_examples(
+    comments: &str,
+    on_failure: FailedExample,
+    mut check: impl FnMut(&str) -> Result<(), String>,
+) -> String {
+    let mut lines: Vec<String> = comments.lines().map(str::to_string).collect();
+
+    // Bottom-up, so removing a block keeps the ranges above it valid
+    for (open, close) in rust_blocks(&lines).into_iter().rev() {
+        let code: Vec<&str> = lines[open + 1..close]
+            .iter()
+            .map(|line| code_line(doc_text(line)))
+            .collect();
+
+        if check(&code.join("\n")).is_ok() {
+            continue;
+        }
+
+        match on_failure {
+            FailedExample::Ignore => {
+                let prefix_len = lines[open].len() - doc_text(&lines[open]).len();
+                lines[open] = format!("{}```ignore", &lines[open][..prefix_len]);
+            }
+            FailedExample::Remove => {
+                lines.drain(open..=close);
+            }
+        }
+    }
+
+    remove_empty_examples_headings(&mut lines);
+    lines.join("\n")
+}
+
+/// Line ranges (opening and closing fence) of the Rust code blocks in
+/// doc comment lines. Untagged blocks are Rust, as in rustdoc.
+fn rust_blocks(lines: &[String]) -> Vec<(usize, usize)> {
+    let mut blocks = Vec::new();
+    let mut open: Option<(usize, bool)> = None;
+
+    for (i, line) in lines.iter().enumerate() {
+        let Some(info) = doc_text(line).trim().strip_prefix("```") else {
+            continue;
+        };
+
+        match open {
+            None => {
+                let is_rust = info.is_empty()
+                    || info
+                        .split(',')
+                        .any(|attr| matches!(attr.trim(), "rust" | "no_run" | "should_panic"));
+                let already_ignored = info.contains("ignore");
+                open = Some((i, is_rust && !already_ignored));
+            }
+            Some((start, is_rust)) => {
+                if is_rust {
+                    blocks.push((start, i));
+                }
+                open = None;
+            }
+        }
+    }
+
+    blocks
+}
+
+/// The text of a `///` line, without the marker and the one space after it
+fn doc_text(line: &str) -> &str {
+    let text = line.trim_start().trim_start_matches("///");
+    text.strip_prefix(' ').unwrap_or(text)
+}
+
+/// Un-hides doctest lines hidden with a leading `# `
+fn code_line(line: &str) -> &str {
+    if line.trim() == "#" {
+        ""
+    } else {
+        line.strip_prefix("# ").unwrap_or(line)
+    }
+}
+
+/// Drops `# Examples` headings left without any content
+fn remove_empty_examples_headings(lines: &mut Vec<String>) {
+    let mut i = 0;
+    while i < lines.len() {
+        if doc_text(&lines[i]).trim() != "# Examples" {
+            i += 1;
+            continue;
+        }
+
+        let section_end = (i + 1..lines.len())
+            .find(|&j| doc_text(&lines[j]).starts_with("# "))
+            .unwrap_or(lines.len());
+        if lines[i + 1..section_end]
+            .iter()
+            .any(|line| !doc_text(line).trim().is_empty())
+        {
+            i += 1;
+            continue;
+        }
+
+        lines.drain(i..section_end);
+        // Along with the blank line that led into the heading
+        if i > 0 && doc_text(&lines[i - 1]).trim().is_empty() {
+            lines.remove(i - 1);
+        }
+    }
+}
+
+/// Wraps a snippet in `fn main` unless it has its own, like rustdoc.
+/// Snippets using `?` get a `main` returning a `Result`, ending in `Ok(())`.
+fn as_program(code: &str) -> String {
+    if code.contains("fn main") {
+        code.to_string()
+    } else if uses_question_mark(code) {
+        let code = code.trim_end();
+        let separator = match code.ends_with(';') || code.ends_with('}') {
+            true => "",
+            false => ";",
+        };
+        format!(
+            "fn main() -> Result<(), Box<dyn std::error::Error>> {{\n{}{}\nOk(())\n}}\n",
+            code, separator
+        )
+    } else {
+        format!("fn main() {{\n{}\n}}\n", code)
+    }
+}
+
+/// Whether `code`, as the body of `fn main`, uses `?` on its own rather
+/// than inside a closure, async block or nested item
+fn uses_question_mark(code: &str) -> bool {
+    let Ok(block) = syn::parse_str::<syn::Block>(&format!("{{\n{}\n}}", code)) else {
+        return false;
+    };
+    let mut finder = QuestionMarks { found: false };
+    finder.visit_block(&block);
+    finder.found
+}
+
+struct QuestionMarks {
+    found: bool,
+}
+
+impl<'ast> Visit<'ast> for QuestionMarks {
+    fn visit_expr_try(&mut self, _: &'ast syn::ExprTry) {
+        self.found = true;
+    }
+
+    fn visit_expr_closure(&mut self, _: &'ast syn::ExprClosure) {}
+
+    fn visit_expr_async(&mut self, _: &'ast syn::ExprAsync) {}
+
+    fn visit_item(&mut self, _: &'ast syn::Item) {}
+}
+
+fn check_snippet(
+    code: &str,
+    symbols: &Symbols,
+    scratch: Option<&ScratchCrate>,
+) -> Result<(), String> {
+    let program = as_program(code);
+    let file = syn::parse_file(&program).map_err(|e| format!("doesn't parse: {}", e))?;
+
+    if let Some(crate_name) = &symbols.crate_name {
+        let mut paths = CratePaths {
+            crate_name,
+            unknown: Vec::new(),
+            symbols,
+        };
+        paths.visit_file(&file);
+        if !paths.unknown.is_empty() {
+            return Err(format!(
+                "uses items that don't exist: {}",
+                paths.unknown.join(", ")
+            ));
+        }
+    }
+
+    match scratch {
+        Some(scratch) => scratch.check(&program),
+        None => Ok(()),
+    }
+}
+
+/// Collects the paths into the crate that don't resolve
+struct CratePaths<'a> {
+    crate_name: &'a str,
+    symbols: &'a Symbols,
+    unknown: Vec<String>,
+}
+
+impl CratePaths<'_> {
+    fn check(&mut self, segments: Vec<String>) {
+        if segments.first().map(String::as_str) != Some(self.crate_name) {
+            return;
+        }
+        if !self.symbols.resolves(&segments[1..]) {
+            self.unknown.push(segments.join("::"));
+        }
+    }
+
+    fn check_use_tree(&mut self, prefix: Vec<String>, tree: &syn::UseTree) {
+        let mut path = prefix;
+        match tree {
+            syn::UseTree::Path(use_path) => {
+                path.push(use_path.ident.to_string());
+                self.check_use_tree(path, &use_path.tree);
+            }
+            syn::UseTree::Name(name) => {
+                if name.ident != "self" {
+                    path.push(name.ident.to_string());
+                }
+                self.check(path);
+            }
+            syn::UseTree::Rename(rename) => {
+                path.push(rename.ident.to_string());
+                self.check(path);
+            }
+            syn::UseTree::Glob(_) => self.check(path),
+            syn::UseTree::Group(group) => {
+                for tree in &group.items {
+                    self.check_use_tree(path.clone(), tree);
+                }
+            }
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for CratePaths<'_> {
+    fn visit_item_use(&mut self, item: &'ast syn::ItemUse) {
+        self.check_use_tree(Vec::new(), &item.tree);
+    }
+
+    fn visit_path(&mut self, path: &'ast syn::Path) {
+        self.check(
+            path.segments
+                .iter()
+                .map(|segment| segment.ident.to_string())
+                .collect(),
+        );
+        visit::visit_path(self, path);
+    }
+}
+
+/// Environment variables cargo gets to see. Compiling runs the dredged
+/// crate's build scripts and proc macros, which are untrusted code, so
+/// everything else (`GITHUB_TOKEN` included) is cleared.
+const CARGO_ENV: [&str; 5] = [
+    "PATH",
+    "HOME",
+    "CARGO_HOME",
+    "RUSTUP_HOME",
+    "RUSTUP_TOOLCHAIN",
+];
+
+/// A throwaway binary crate depending on a copy of the dredged crate,
+/// for compiling examples the way a doctest would use the crate
+struct ScratchCrate {
+    dir: TempDir,
+}
+
+impl ScratchCrate {
+    fn new(root: &RepoNode, symbols: &Symbols) -> Result<Self, String> {
+        let (Some(crate_name), Some(package_name)) = (&symbols.crate_name, &symbols.package_name)
+        else {
+            return Err("no Cargo.toml with a package name".to_string());
+        };
+        Command::new("cargo")
+            .arg("--version")
+            .output()
+            .map_err(|_| "no Rust toolchain found".to_string())?;
+
+        let dir = TempDir::new().map_err(|e| e.to_string())?;
+        for node in root.iter() {
+            if let RepoNode::File { path, content, .. } = node {
+                let target = dir.path().join("crate").join(path);
+                write_file(&target, content)?;
+            }
+        }
+
+        write_file(
+            &dir.path().join("scratch/Cargo.toml"),
+            &format!(
+                "[package]\nname = \"dredger-scratch\"\nversion = \"0.0.0\"\nedition = \"2021\"\n\n\
+                 [dependencies]\n{} = {{ path = \"../crate\" }}\n\n[workspace]\n",
+                package_name
+            ),
+        )?;
+
+        // Make sure the crate itself builds, so failures below are the
+        // examples' fault
+        let scratch = ScratchCrate { dir };
+        scratch
+            .check("fn main() {}\n")
+            .map_err(|e| format!("{} doesn't build in a scratch crate: {}", crate_name, e))?;
+        Ok(scratch)
+    }
+
+    fn check(&self, program: &str) -> Result<(), String> {
+        write_file(&self.dir.path().join("scratch/src/main.rs"), program)?;
+
+        let output = Command::new("cargo")
+            .args(["check", "--quiet", "--manifest-path"])
+            .arg(self.dir.path().join("scratch/Cargo.toml"))
+            .env_clear()
+            .envs(
+                CARGO_ENV
+                    .iter()
+                    .filter_map(|name| Some((name, std::env::var_os(name)?))),
+            )
+            .env("CARGO_TARGET_DIR", self.dir.path().join("target"))
+            .output()
+            .map_err(|e| e.to_string())?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let first_error = stderr
+                .lines()
+                .find(|line| line.starts_with("error"))
+                .unwrap_or("unknown error");
+            return Err(format!("doesn't compile: {}", first_error));
+        }
+
+        Ok(())
+    }
+}
+
+fn write_file(path: &Path, content: &str) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    fs::write(path, content).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMMENTS: &str = "\
+/// Loads the config.
+///
+/// # Examples
+///
+/// ```
+/// # use my_crate::Config;
+/// let config = Config::load();
+/// ```
+///
+/// ```text
+/// not code
+/// ```";
+
+    #[test]
+    fn test_fix_examples_marks_failing_blocks_ignore() {
+        let mut checked = Vec::new();
+        let fixed = fix_examples(COMMENTS, FailedExample::Ignore, |code| {
+            checked.push(code.to_string());
+            Err("doesn't compile".to_string())
+        });
+
+        assert_eq!(
+            checked,
+            vec!["use my_crate::Config;\nlet config = Config::load();"]
+        );
+        assert_eq!(
+            fixed,
+            COMMENTS.replacen("/// ```\n/// #", "/// ```ignore\n/// #", 1)
+        );
+    }
+
+    #[test]
+    fn test_fix_examples_removes_failing_blocks() {
+        let comments = COMMENTS.split("\n///\n/// ```text").next().unwrap();
+        let fixed = fix_examples(comments, FailedExample::Remove, |_| Err("no".to_string()));
+
+        assert_eq!(fixed, "/// Loads the config.");
+    }
+
+    #[test]
+    fn test_check_snippet_flags_unknown_crate_items() {
+        let symbols = Symbols::default();
+        assert!(check_snippet("let x = 1 +;", &symbols, None)
+            .unwrap_err()
+            .starts_with("doesn't parse"));
+
+        let mut symbols = Symbols::default();
+        symbols.crate_name = Some("my_crate".to_string());
+        symbols.insert("Config");
+        assert_eq!(
+            check_snippet(
+                "use my_crate::{Config, Missing};\nmy_crate::Config::load();",
+                &symbols,
+                None
+            ),
+            Err(
+                "uses items that don't exist: my_crate::Missing, my_crate::Config::load"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_as_program_returns_a_result_for_question_marks() {
+        assert_eq!(
+            as_program("let config = Config::load()?;\nconfig.run()"),
+            "fn main() -> Result<(), Box<dyn std::error::Error>> {\n\
+             let config = Config::load()?;\nconfig.run();\nOk(())\n}\n"
+        );
+        assert_eq!(
+            as_program("let parse = |s: &str| s.parse::<u8>().ok()?.checked_add(1);"),
+            "fn main() {\nlet parse = |s: &str| s.parse::<u8>().ok()?.checked_add(1);\n}\n"
+        );
+        assert!(check_snippet("let x: u8 = \"1\".parse()?;", &Symbols::default(), None).is_ok());
+    }
+}
@@ -0,0 +1,317 @@
+use crate::languages::strategy::{ParsedFile, SourceItem};
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{
+    Attribute, Expr, ImplItemFn, ItemConst, ItemEnum, ItemFn, ItemImpl, ItemMod, ItemStatic,
+    ItemStruct, ItemTrait, ItemType, ItemUnion, Lit, Meta, ReturnType, Signature, Type, Visibility,
+};
+
+/// Parses a Rust file, listing its public functions, structs, enums and
+/// traits, plus the public methods of its inherent impls, in file order.
+///
+/// Items of inline modules are included; trait impl methods aren't,
+/// since rustdoc shows the trait's docs for them.
+pub fn parse_file(content: &str) -> Result<ParsedFile, syn::Error> {
+    let file = syn::parse_file(content)?;
+    Ok(ParsedFile {
+        module_docs: doc_text(&file.attrs),
+        items: collect_items(content, &file, false),
+    })
+}
+
+/// Like `parse_file`'s items, plus the public items Dredger doesn't
+/// document but rustdoc shows: constants, statics, type aliases, unions
+/// and inline modules. Re-exports and `mod x;` declarations are left
+/// out, since their docs live elsewhere.
+pub fn public_items(content: &str) -> Result<Vec<SourceItem>, syn::Error> {
+    let file = syn::parse_file(content)?;
+    Ok(collect_items(content, &file, true))
+}
+
+fn collect_items(content: &str, file: &syn::File, every_kind: bool) -> Vec<SourceItem> {
+    let mut collector = ItemCollector {
+        lines: content.lines().collect(),
+        items: Vec::new(),
+        every_kind,
+    };
+    collector.visit_file(file);
+
+    let mut items = collector.items;
+    items.sort_by_key(|item| item.line);
+    items
+}
+
+/// Joins the text of `attrs`' doc attributes, which is what `///` and
+/// `//!` comments are parsed into
+fn doc_text(attrs: &[Attribute]) -> Option<String> {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(name_value) => match &name_value.value {
+                Expr::Lit(expr) => match &expr.lit {
+                    Lit::Str(text) => Some(text.value()),
+                    _ => None,
+                },
+                // e.g. `#[doc = include_str!("README.md")]`: docs whose
+                // text isn't known here, but docs all the same
+                _ => Some(String::new()),
+            },
+            _ => None, // e.g. `#[doc(hidden)]`, which isn't documentation
+        })
+        .collect();
+
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
+/// Sections clippy's `missing_errors_doc`, `missing_panics_doc` and
+/// `missing_safety_doc` lints would ask a function's docs for
+fn required_sections(sig: &Signature, source: &str) -> Vec<&'static str> {
+    let mut sections = Vec::new();
+
+    if let ReturnType::Type(_, ty) = &sig.output {
+        if let Type::Path(path) = ty.as_ref() {
+            if path
+                .path
+                .segments
+                .last()
+                .is_some_and(|segment| segment.ident == "Result")
+            {
+                sections.push("Errors");
+            }
+        }
+    }
+    if ["panic!(", ".unwrap()", ".expect(", "unreachable!("]
+        .iter()
+        .any(|pattern| source.contains(pattern))
+    {
+        sections.push("Panics");
+    }
+    if sig.unsafety.is_some() {
+        sections.push("Safety");
+    }
+
+    sections
+}
+
+struct ItemCollector<'a> {
+    lines: Vec<&'a str>,
+    items: Vec<SourceItem>,
+    /// Also collect the kinds of items only `public_items` lists
+    every_kind: bool,
+}
+
+impl ItemCollector<'_> {
+    fn push(
+        &mut self,
+        kind: &'static str,
+        name: String,
+        vis: &Visibility,
+        attrs: &[Attribute],
+        sig: Option<&Signature>,
+        item: &impl Spanned,
+    ) {
+        let Visibility::Public(pub_token) = vis else {
+            return;
+        };
+
+        // Attributes come before `pub`, so the item proper starts at `pub`
+        let line = pub_token.span.start().line;
+        let end_line = item.span().end().line;
+        let Some(first) = self.lines.get(line - 1) else {
+            return;
+        };
+
+        let source = self.lines[line - 1..end_line.min(self.lines.len())].join("\n");
+        let required_sections = sig
+            .map(|sig| required_sections(sig, &source))
+            .unwrap_or_default();
+
+        self.items.push(SourceItem {
+            kind,
+            name,
+            line,
+            indent: first[..first.len() - first.trim_start().len()].to_string(),
+            source,
+            existing_docs: doc_text(attrs),
+            required_sections,
+            params: Vec::new(),
+        });
+    }
+}
+
+impl<'ast> Visit<'ast> for ItemCollector<'_> {
+    fn visit_item_fn(&mut self, item: &'ast ItemFn) {
+        let name = item.sig.ident.to_string();
+        self.push("fn", name, &item.vis, &item.attrs, Some(&item.sig), item);
+    }
+
+    fn visit_item_struct(&mut self, item: &'ast ItemStruct) {
+        let name = item.ident.to_string();
+        self.push("struct", name, &item.vis, &item.attrs, None, item);
+    }
+
+    fn visit_item_enum(&mut self, item: &'ast ItemEnum) {
+        let name = item.ident.to_string();
+        self.push("enum", name, &item.vis, &item.attrs, None, item);
+    }
+
+    fn visit_item_trait(&mut self, item: &'ast ItemTrait) {
+        let name = item.ident.to_string();
+        self.push("trait", name, &item.vis, &item.attrs, None, item);
+        // An unsafe trait needs its implementors told what to uphold
+        if item.unsafety.is_some() {
+            if let Some(pushed) = self.items.last_mut() {
+                pushed.required_sections.push("Safety");
+            }
+        }
+    }
+
+    fn visit_item_impl(&mut self, item: &'ast ItemImpl) {
+        if item.trait_.is_none() {
+            visit::visit_item_impl(self, item);
+        }
+    }
+
+    fn visit_item_const(&mut self, item: &'ast ItemConst) {
+        if self.every_kind {
+            let name = item.ident.to_string();
+            self.push("const", name, &item.vis, &item.attrs, None, item);
+        }
+    }
+
+    fn visit_item_static(&mut self, item: &'ast ItemStatic) {
+        if self.every_kind {
+            let name = item.ident.to_string();
+            self.push("static", name, &item.vis, &item.attrs, None, item);
+        }
+    }
+
+    fn visit_item_type(&mut self, item: &'ast ItemType) {
+        if self.every_kind {
+            let name = item.ident.to_string();
+            self.push("type", name, &item.vis, &item.attrs, None, item);
+        }
+    }
+
+    fn visit_item_union(&mut self, item: &'ast ItemUnion) {
+        if self.every_kind {
+            let name = item.ident.to_string();
+            self.push("union", name, &item.vis, &item.attrs, None, item);
+        }
+    }
+
+    fn visit_item_mod(&mut self, item: &'ast ItemMod) {
+        if self.every_kind && item.content.is_some() {
+            let name = item.ident.to_string();
+            self.push("mod", name, &item.vis, &item.attrs, None, item);
+        }
+        visit::visit_item_mod(self, item);
+    }
+
+    fn visit_impl_item_fn(&mut self, item: &'ast ImplItemFn) {
+        let name = item.sig.ident.to_string();
+        self.push(
+            "method",
+            name,
+            &item.vis,
+            &item.attrs,
+            Some(&item.sig),
+            item,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_file_finds_items_and_methods() {
+        let content = "\
+use std::fmt;
+
+#[derive(Debug)]
+pub struct Config {
+    pub verbose: bool,
+}
+
+impl Config {
+    pub fn new() -> Self {
+        Config { verbose: false }
+    }
+
+    fn private(&self) {}
+}
+
+impl fmt::Display for Config {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, \"config\")
+    }
+}
+
+pub(crate) fn internal() {}
+
+pub mod inner {
+    pub enum Mode { Fast }
+}
+";
+        let items = parse_file(content).unwrap().items;
+        let summary: Vec<(&str, &str, usize)> = items
+            .iter()
+            .map(|item| (item.kind, item.name.as_str(), item.line))
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                ("struct", "Config", 4),
+                ("method", "new", 9),
+                ("enum", "Mode", 25),
+            ]
+        );
+        assert_eq!(items[1].indent, "    ");
+        assert_eq!(
+            items[1].source,
+            "    pub fn new() -> Self {\n        Config { verbose: false }\n    }"
+        );
+    }
+
+    #[test]
+    fn test_parse_file_reads_existing_docs() {
+        let content = "\
+//! Module docs
+
+/// Loads the config.
+///
+/// # Errors
+/// When the file is unreadable.
+#[inline]
+pub fn load() -> Result<Config, Error> {
+    read().unwrap()
+}
+
+#[doc = \"Runs it.\"]
+pub unsafe fn run() {}
+
+pub fn plain() {}
+
+#[doc = include_str!(\"included.md\")]
+pub fn included() {}
+";
+        let file = parse_file(content).unwrap();
+
+        assert_eq!(file.module_docs.as_deref(), Some(" Module docs"));
+        assert_eq!(
+            file.items[0].existing_docs.as_deref(),
+            Some(" Loads the config.\n\n # Errors\n When the file is unreadable.")
+        );
+        assert_eq!(file.items[0].required_sections, vec!["Errors", "Panics"]);
+        assert_eq!(file.items[0].missing_sections(), vec!["Panics"]);
+        assert_eq!(file.items[1].existing_docs.as_deref(), Some("Runs it."));
+        assert_eq!(file.items[1].missing_sections(), vec!["Safety"]);
+        assert_eq!(file.items[2].existing_docs, None);
+        assert!(file.items[2].missing_sections().is_empty());
+        assert_eq!(file.items[3].existing_docs.as_deref(), Some(""));
+    }
+}
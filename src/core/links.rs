@@ -0,0 +1,216 @@
+use crate::core::symbols::Symbols;
+
+/// Rust's primitive types, which intra-doc links can point at
+const PRIMITIVES: [&str; 18] = [
+    "bool", "char", "str", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64",
+    "i128", "isize", "f32", "f64", "slice",
+];
+
+/// Rewrites the intra-doc links in generated doc comments that don't
+/// resolve against `symbols` into plain code spans, e.g. a link to a
+/// made-up type, `` [`Missing`] ``, becomes `` `Missing` ``.
+///
+/// Links into `std`, `core` and `alloc` are left alone, as are URLs and
+/// anything inside code blocks or code spans.
+///
+/// Returns the fixed comments and how many links were rewritten.
+pub fn fix_links(comments: &str, symbols: &Symbols) -> (String, usize) {
+    let mut fixed = 0;
+    let mut in_code_block = false;
+
+    let lines: Vec<String> = comments
+        .lines()
+        .map(|line| {
+            let text = line
+                .trim_start()
+                .trim_start_matches("///")
+                .trim_start_matches("//!");
+            if text.trim_start().starts_with("```") {
+                in_code_block = !in_code_block;
+                return line.to_string();
+            }
+            if in_code_block {
+                return line.to_string();
+            }
+
+            let (new_line, line_fixed) = fix_line(line, symbols);
+            fixed += line_fixed;
+            new_line
+        })
+        .collect();
+
+    (lines.join("\n"), fixed)
+}
+
+fn fix_line(line: &str, symbols: &Symbols) -> (String, usize) {
+    let mut out = String::with_capacity(line.len());
+    let mut fixed = 0;
+    let mut rest = line;
+
+    while let Some(i) = rest.find(['[', '`']) {
+        out.push_str(&rest[..i]);
+        rest = &rest[i..];
+
+        // Code spans are copied as-is
+        if rest.starts_with('`') {
+            let end = rest[1..].find('`').map_or(rest.len(), |end| end + 2);
+            out.push_str(&rest[..end]);
+            rest = &rest[end..];
+            continue;
+        }
+
+        let Some(link) = parse_link(rest) else {
+            out.push('[');
+            rest = &rest[1..];
+            continue;
+        };
+
+        if resolves(link.target, symbols) {
+            out.push_str(&rest[..link.len]);
+        } else {
+            let text = link.text.trim();
+            if text.starts_with('`') && text.ends_with('`') && text.len() > 1 {
+                out.push_str(text);
+            } else {
+                out.push_str(&format!("`{}`", text));
+            }
+            fixed += 1;
+        }
+        rest = &rest[link.len..];
+    }
+
+    out.push_str(rest);
+    (out, fixed)
+}
+
+struct Link<'a> {
+    text: &'a str,
+    target: &'a str,
+    /// Length of the whole link in the source text
+    len: usize,
+}
+
+/// Parses an intra-doc link at the start of `text`: `[target]`,
+/// `[text](target)` or `[text][target]`. Anything whose target isn't
+/// a Rust path (URLs, anchors, `[0, 1]`, ...) isn't one.
+fn parse_link(text: &str) -> Option<Link<'_>> {
+    let close = text.find(']')?;
+    let link_text = &text[1..close];
+    if link_text.contains('[') {
+        return None;
+    }
+
+    let after = &text[close + 1..];
+    let (target, len) = if let Some(inner) = after.strip_prefix('(') {
+        let end = inner.find(')')?;
+        (&inner[..end], close + 1 + end + 2)
+    } else if let Some(inner) = after.strip_prefix('[') {
+        let end = inner.find(']')?;
+        (&inner[..end], close + 1 + end + 2)
+    } else if after.starts_with(':') {
+        // `[name]: target` defines a reference, it isn't a link
+        return None;
+    } else {
+        (link_text, close + 1)
+    };
+
+    rust_path(target)?;
+    Some(Link {
+        text: link_text,
+        target,
+        len,
+    })
+}
+
+/// The segments of the Rust path an intra-doc link target names, e.g.
+/// `["Config", "load"]` for `` `Config::load()` ``
+fn rust_path(target: &str) -> Option<Vec<&str>> {
+    let mut path = target.trim().trim_matches('`');
+    // Disambiguators, like `struct@Config` or `Config::load()`
+    if let Some((_, rest)) = path.split_once('@') {
+        path = rest;
+    }
+    path = path.trim_end_matches("()").trim_end_matches('!');
+    if let Some(generics) = path.find('<') {
+        path = &path[..generics];
+    }
+
+    let segments: Vec<&str> = path.split("::").collect();
+    let is_ident = |segment: &&str| {
+        segment
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_alphabetic() || c == '_')
+            && segment.chars().all(|c| c.is_alphanumeric() || c == '_')
+    };
+    segments.iter().all(is_ident).then_some(segments)
+}
+
+fn resolves(target: &str, symbols: &Symbols) -> bool {
+    let Some(segments) = rust_path(target) else {
+        return true;
+    };
+
+    let mut segments = segments.as_slice();
+    if matches!(segments.first(), Some(&"std" | &"core" | &"alloc")) {
+        return true;
+    }
+    while let Some((first, rest)) = segments.split_first() {
+        let is_crate = symbols.crate_name.as_deref() == Some(*first);
+        if matches!(*first, "crate" | "self" | "super" | "Self") || is_crate {
+            segments = rest;
+        } else {
+            break;
+        }
+    }
+
+    if let [single] = segments {
+        if PRIMITIVES.contains(single) {
+            return true;
+        }
+    }
+
+    let segments: Vec<String> = segments.iter().map(|s| s.to_string()).collect();
+    !segments.is_empty() && symbols.resolves(&segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fix_links_rewrites_unresolved_links() {
+        let mut symbols = Symbols::default();
+        symbols.crate_name = Some("my_crate".to_string());
+        symbols.insert_member("Config", "load");
+        symbols.insert("save");
+
+        let comments = "\
+/// Loads a [`Config`] via [`Config::load()`], see [`crate::Config`].
+/// Not [`Config::save`] or [`Config::missing_method`].
+/// Returns a [`Settings`], or a [parse error](ParseError).
+/// Uses [`std::fs::read`], [docs](https://example.com) and [0, 1].
+/// Not a link: `[Missing]`
+///
+/// ```
+/// let x = [Missing];
+/// ```";
+
+        let (fixed, count) = fix_links(comments, &symbols);
+
+        assert_eq!(
+            fixed,
+            "\
+/// Loads a [`Config`] via [`Config::load()`], see [`crate::Config`].
+/// Not `Config::save` or `Config::missing_method`.
+/// Returns a `Settings`, or a `parse error`.
+/// Uses [`std::fs::read`], [docs](https://example.com) and [0, 1].
+/// Not a link: `[Missing]`
+///
+/// ```
+/// let x = [Missing];
+/// ```"
+        );
+        assert_eq!(count, 4);
+    }
+}
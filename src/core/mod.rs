@@ -1 +1,16 @@
 pub mod actions;
+pub mod architecture;
+pub mod cleanup;
+pub mod coverage;
+pub mod doctest;
+pub mod items;
+pub mod links;
+pub mod patch;
+pub mod plan;
+pub mod readme;
+pub mod report;
+pub mod review;
+pub mod splice;
+pub mod summaries;
+pub mod symbols;
+pub mod verify;
@@ -0,0 +1,184 @@
+use crate::core::splice::DocEdit;
+use crate::utils::config::{PatchConfig, PatchFormat};
+use crate::utils::errors::DredgerError;
+use chrono::Utc;
+use similar::TextDiff;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const PATCH_AUTHOR: &str = "Dredger <dredger@users.noreply.github.com>";
+
+/// Git-style unified diff of a single file, `diff --git` header included
+pub fn unified_diff(path: &str, old: Option<&str>, new: &str) -> String {
+    let diff = TextDiff::from_lines(old.unwrap_or_default(), new);
+    let (old_header, mode) = match old {
+        Some(_) => (format!("a/{}", path), ""),
+        None => ("/dev/null".to_string(), "new file mode 100644\n"),
+    };
+    let body = diff
+        .unified_diff()
+        .context_radius(3)
+        .header(&old_header, &format!("b/{}", path))
+        .to_string();
+
+    format!("diff --git a/{} b/{}\n{}{}", path, path, mode, body)
+}
+
+/// Writes every edit of a run into `patch_config.dir`, either as one patch
+/// per file or as a single combined patch.
+///
+/// Returns the paths of the written files, in the order they should be
+/// applied.
+pub fn write_patches(
+    edits: &[DocEdit],
+    patch_config: &PatchConfig,
+) -> Result<Vec<PathBuf>, DredgerError> {
+    fs::create_dir_all(&patch_config.dir).map_err(DredgerError::IoError)?;
+
+    let mut written = Vec::new();
+    for (path, contents) in render_patches(edits, patch_config) {
+        fs::write(&path, contents).map_err(DredgerError::IoError)?;
+        written.push(path);
+    }
+
+    Ok(written)
+}
+
+/// The patch files `write_patches` would write, as (path, contents) pairs
+pub fn render_patches(edits: &[DocEdit], patch_config: &PatchConfig) -> Vec<(PathBuf, String)> {
+    let dir = Path::new(&patch_config.dir);
+
+    // Each group of edits becomes one patch
+    let groups: Vec<&[DocEdit]> = if patch_config.per_file {
+        edits.chunks(1).collect()
+    } else {
+        vec![edits]
+    };
+
+    let mut patches = Vec::new();
+    for (index, group) in groups.iter().enumerate() {
+        let subject = match group {
+            [edit] => format!("Add Dredger documentation to {}", edit.change.path),
+            _ => format!("Add Dredger documentation to {} files", group.len()),
+        };
+        let diffs: String = group
+            .iter()
+            .map(|edit| {
+                // Files without a base blob are new
+                let old = edit
+                    .change
+                    .base_sha
+                    .as_ref()
+                    .map(|_| edit.original_content.as_str());
+                unified_diff(&edit.change.path, old, &edit.change.content)
+            })
+            .collect();
+
+        let (contents, extension) = match patch_config.format {
+            PatchFormat::Diff => (diffs, "diff"),
+            PatchFormat::Mbox => (
+                format_mbox(&subject, index + 1, groups.len(), group, &diffs),
+                "patch",
+            ),
+        };
+
+        let file_name = format!("{:04}-{}.{}", index + 1, slugify(&subject), extension);
+        patches.push((dir.join(file_name), contents));
+    }
+
+    patches
+}
+
+/// Wraps `diffs` as one mail of a `git format-patch` series
+fn format_mbox(
+    subject: &str,
+    number: usize,
+    total: usize,
+    group: &[DocEdit],
+    diffs: &str,
+) -> String {
+    let subject_prefix = if total > 1 {
+        format!("[PATCH {}/{}]", number, total)
+    } else {
+        "[PATCH]".to_string()
+    };
+    let file_list = group
+        .iter()
+        .map(|edit| format!(" {}", edit.change.path))
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    format!(
+        "From 0000000000000000000000000000000000000000 Mon Sep 17 00:00:00 2001\n\
+         From: {}\n\
+         Date: {}\n\
+         Subject: {} {}\n\
+         \n\
+         Generated by Dredger. Each change only adds or replaces doc comments.\n\
+         ---\n\
+         {}\n\
+         \n\
+         {}\
+         -- \n\
+         dredger\n\n",
+        PATCH_AUTHOR,
+        Utc::now().to_rfc2822(),
+        subject_prefix,
+        subject,
+        file_list,
+        diffs
+    )
+}
+
+/// "Add docs to src/lib.rs" -> "add-docs-to-src-lib-rs", like format-patch
+fn slugify(subject: &str) -> String {
+    subject
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<&str>>()
+        .join("-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_diff_has_git_headers() {
+        let diff = unified_diff(
+            "src/lib.rs",
+            Some("pub mod core;\n"),
+            "//! Docs\n\npub mod core;\n",
+        );
+
+        assert_eq!(
+            diff,
+            "diff --git a/src/lib.rs b/src/lib.rs\n\
+             --- a/src/lib.rs\n\
+             +++ b/src/lib.rs\n\
+             @@ -1 +1,3 @@\n\
+             +//! Docs\n\
+             +\n \
+             pub mod core;\n"
+        );
+
+        assert_eq!(
+            unified_diff("README.md", None, "# Tool\n"),
+            "diff --git a/README.md b/README.md\n\
+             new file mode 100644\n\
+             --- /dev/null\n\
+             +++ b/README.md\n\
+             @@ -0,0 +1 @@\n\
+             +# Tool\n"
+        );
+    }
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(
+            slugify("Add Dredger documentation to src/core/mod.rs"),
+            "add-dredger-documentation-to-src-core-mod-rs"
+        );
+    }
+}
@@ -0,0 +1,138 @@
+use serde::Serialize;
+use std::fmt;
+
+/// Everything a run would do, worked out without writing anything
+#[derive(Debug, Serialize)]
+pub struct ActionPlan {
+    /// "owner/name" of a GitHub repo, or the path of a local checkout
+    pub repo: String,
+    pub output: String,
+    pub files_scanned: usize,
+    pub total_tokens: usize,
+    /// Whether the language model was skipped, in which case `files`
+    /// lists the files that would be sent to it rather than the files
+    /// that would get docs
+    pub llm_skipped: bool,
+    pub files: Vec<PlannedFile>,
+    /// Branch that would be created, or refreshed for an existing PR
+    pub branch: Option<String>,
+    pub pull_request: Option<PlannedPullRequest>,
+    pub coverage: Option<PlannedCoverage>,
+    /// Files that would be written locally (patches, or a checkout's files)
+    pub written_files: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PlannedFile {
+    pub path: String,
+    pub token_count: usize,
+    pub lines_added: usize,
+    pub lines_replaced: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PlannedPullRequest {
+    /// Number of the open Dredger PR that would be refreshed, if any
+    pub existing_number: Option<u64>,
+    /// Repo the branch would be pushed to, when it has to be a fork
+    pub fork: Option<String>,
+    pub base: String,
+    pub title: String,
+    pub body: String,
+    pub draft: bool,
+    pub labels: Vec<String>,
+    pub assignees: Vec<String>,
+    pub milestone: Option<String>,
+}
+
+/// Doc coverage that would be posted to the dredged commit
+#[derive(Debug, Serialize)]
+pub struct PlannedCoverage {
+    pub sha: String,
+    /// "status" or "check_run"
+    pub report: String,
+    /// Whether it would pass the configured minimum
+    pub passes: bool,
+    pub summary: String,
+    pub undocumented: usize,
+}
+
+impl fmt::Display for ActionPlan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Dry run for {} (output: {})", self.repo, self.output)?;
+        writeln!(
+            f,
+            "Scanned {} file(s), {} tokens",
+            self.files_scanned, self.total_tokens
+        )?;
+
+        if self.llm_skipped {
+            writeln!(
+                f,
+                "\nLanguage model skipped, {} file(s) would be sent to it:",
+                self.files.len()
+            )?;
+            for file in &self.files {
+                writeln!(f, "  {} ({} tokens)", file.path, file.token_count)?;
+            }
+        } else {
+            writeln!(f, "\n{} file(s) would get docs:", self.files.len())?;
+            for file in &self.files {
+                writeln!(
+                    f,
+                    "  {} (+{} / ~{} doc lines)",
+                    file.path, file.lines_added, file.lines_replaced
+                )?;
+            }
+        }
+
+        if let Some(branch) = &self.branch {
+            writeln!(f, "\nBranch: {}", branch)?;
+        }
+
+        if let Some(pr) = &self.pull_request {
+            match pr.existing_number {
+                Some(number) => writeln!(f, "Would refresh open PR #{}", number)?,
+                None => writeln!(f, "Would open a PR against {}", pr.base)?,
+            }
+            if let Some(fork) = &pr.fork {
+                writeln!(f, "Would push to fork {}", fork)?;
+            }
+            writeln!(f, "Title: {}", pr.title)?;
+            writeln!(
+                f,
+                "Draft: {}, labels: [{}], assignees: [{}], milestone: {}",
+                pr.draft,
+                pr.labels.join(", "),
+                pr.assignees.join(", "),
+                pr.milestone.as_deref().unwrap_or("none")
+            )?;
+            writeln!(f, "\n{}", pr.body)?;
+        }
+
+        if let Some(coverage) = &self.coverage {
+            writeln!(
+                f,
+                "Would post a {} coverage {} on {}: {} ({} undocumented item(s))",
+                if coverage.passes {
+                    "passing"
+                } else {
+                    "failing"
+                },
+                coverage.report.replace('_', " "),
+                coverage.sha,
+                coverage.summary,
+                coverage.undocumented
+            )?;
+        }
+
+        if !self.written_files.is_empty() {
+            writeln!(f, "\nWould write:")?;
+            for path in &self.written_files {
+                writeln!(f, "  {}", path)?;
+            }
+        }
+
+        Ok(())
+    }
+}
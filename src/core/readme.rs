@@ -0,0 +1,450 @@
+use crate::core::splice::DocEdit;
+use crate::github_client::data::{FileChange, RepoNode};
+use crate::languages::registry;
+use crate::languages::strategy::Prompt;
+use crate::ollama_client::client as ollama_client;
+use crate::utils::config::{ReadmeConfig, ReadmeSection};
+
+/// Path of the README Dredger creates when a repo has none
+const README_PATH: &str = "README.md";
+
+/// Files whose contents tell how a project is built and installed
+const MANIFESTS: [&str; 9] = [
+    "Cargo.toml",
+    "package.json",
+    "pyproject.toml",
+    "setup.py",
+    "requirements.txt",
+    "go.mod",
+    "Makefile",
+    "Dockerfile",
+    "docker-compose.yml",
+];
+
+/// Rough cap on the repo digest sent along with each section prompt
+const DIGEST_CHARS: usize = 12_000;
+
+fn start_marker(section: ReadmeSection) -> String {
+    format!("<!-- dredger:start {} -->", section.name())
+}
+
+fn end_marker(section: ReadmeSection) -> String {
+    format!("<!-- dredger:end {} -->", section.name())
+}
+
+/// Line indexes of the start and end markers of `section`, if the
+/// README has both, in that order
+fn marker_lines(lines: &[&str], section: ReadmeSection) -> Option<(usize, usize)> {
+    let start = lines
+        .iter()
+        .position(|line| line.trim() == start_marker(section))?;
+    let end = start
+        + lines[start..]
+            .iter()
+            .position(|line| line.trim() == end_marker(section))?;
+    Some((start, end))
+}
+
+/// The sections of `readme` wrapped in Dredger's markers, in file order.
+/// A start marker without its end marker doesn't count.
+pub fn marked_sections(readme: &str) -> Vec<ReadmeSection> {
+    let lines: Vec<&str> = readme.lines().collect();
+    let mut sections: Vec<(usize, ReadmeSection)> = ReadmeSection::ALL
+        .iter()
+        .filter_map(|&section| marker_lines(&lines, section).map(|(start, _)| (start, section)))
+        .collect();
+    sections.sort_by_key(|(start, _)| *start);
+    sections.into_iter().map(|(_, section)| section).collect()
+}
+
+/// Replaces what's between the markers of each of `sections` with its
+/// new text. Everything outside the markers stays as it is.
+///
+/// Returns the new README along with how many lines were added and how
+/// many were replaced.
+pub fn splice_sections(
+    readme: &str,
+    sections: &[(ReadmeSection, String)],
+) -> (String, usize, usize) {
+    let mut lines: Vec<String> = readme.lines().map(str::to_string).collect();
+    let mut lines_added = 0;
+    let mut lines_replaced = 0;
+
+    for (section, text) in sections {
+        let line_refs: Vec<&str> = lines.iter().map(String::as_str).collect();
+        let Some((start, end)) = marker_lines(&line_refs, *section) else {
+            continue;
+        };
+        let new_lines: Vec<String> = text.lines().map(str::to_string).collect();
+        if lines[start + 1..end] == new_lines[..] {
+            continue;
+        }
+
+        lines_added += new_lines.len();
+        lines_replaced += end - start - 1;
+        lines.splice(start + 1..end, new_lines);
+    }
+
+    let mut new_readme = lines.join("\n");
+    if readme.ends_with('\n') {
+        new_readme.push('\n');
+    }
+    (new_readme, lines_added, lines_replaced)
+}
+
+/// A README made of nothing but Dredger sections, each under its own
+/// heading, for a repo that has none
+pub fn new_readme(title: &str, sections: &[(ReadmeSection, String)]) -> String {
+    let mut readme = format!("# {}\n", title);
+    for (section, text) in sections {
+        readme.push_str(&format!(
+            "\n## {}\n\n{}\n{}\n{}\n",
+            section.title(),
+            start_marker(*section),
+            text,
+            end_marker(*section)
+        ));
+    }
+    readme
+}
+
+/// The model's text for a section, minus a fence around all of it, a
+/// heading repeating the section's title, and any Dredger markers
+pub fn clean_section(output: &str, section: ReadmeSection) -> String {
+    let mut lines: Vec<&str> = output.trim().lines().collect();
+    if lines.first().is_some_and(|line| line.starts_with("```"))
+        && lines.last().is_some_and(|line| line.trim() == "```")
+        && lines.len() > 1
+    {
+        lines = lines[1..lines.len() - 1].to_vec();
+    }
+    if lines.first().is_some_and(|line| {
+        line.starts_with('#') && line.trim_start_matches('#').trim() == section.title()
+    }) {
+        lines.remove(0);
+    }
+
+    lines
+        .into_iter()
+        .filter(|line| !line.contains("<!-- dredger:"))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+/// Whether `name` is a README: `README`, `README.md`, `readme.rst`,
+/// `Readme.txt`, ...
+pub fn is_readme(name: &str) -> bool {
+    name.split('.')
+        .next()
+        .is_some_and(|stem| stem.eq_ignore_ascii_case("readme"))
+}
+
+fn is_markdown(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    name.ends_with(".md") || name.ends_with(".markdown")
+}
+
+/// The root README of the repo, if it has one, whatever its format. A
+/// Markdown one comes first, since section markers only work there.
+fn find_readme(root_node: &RepoNode) -> Option<&RepoNode> {
+    let RepoNode::Directory { children, .. } = root_node else {
+        return None;
+    };
+    children
+        .iter()
+        .filter(|child| matches!(child, RepoNode::File { name, .. } if is_readme(name)))
+        .min_by_key(|child| !is_markdown(child_name(child)))
+}
+
+fn child_name(node: &RepoNode) -> &str {
+    match node {
+        RepoNode::File { name, .. } | RepoNode::Directory { name, .. } => name,
+    }
+}
+
+/// What the model gets to know about the repo: its build manifests, the
+/// module docs of its source files (as this run leaves them), the
+/// README's current text and its files, cut off at `DIGEST_CHARS`
+fn repo_digest(root_node: &RepoNode, edits: &[DocEdit], readme: Option<&str>) -> String {
+    let files: Vec<(&str, &str)> = root_node
+        .iter()
+        .filter_map(|node| match node {
+            RepoNode::File { path, content, .. } => {
+                let content = edits
+                    .iter()
+                    .find(|edit| edit.change.path == *path)
+                    .map_or(content.as_str(), |edit| edit.change.content.as_str());
+                Some((path.as_str(), content))
+            }
+            RepoNode::Directory { .. } => None,
+        })
+        .collect();
+
+    let mut digest = String::new();
+    for (path, content) in &files {
+        if MANIFESTS.contains(path) {
+            let cut = content
+                .char_indices()
+                .nth(2_000)
+                .map_or(content.len(), |(i, _)| i);
+            digest.push_str(&format!("{}:\n```\n{}\n```\n\n", path, &content[..cut]));
+        }
+    }
+
+    digest.push_str("Module docs:\n");
+    for (path, content) in &files {
+        let module_docs = registry::for_path(path)
+            .and_then(|language| language.parse(content).ok())
+            .and_then(|parsed_file| parsed_file.module_docs);
+        if let Some(docs) = module_docs {
+            let summary = docs.trim().split("\n\n").next().unwrap_or_default();
+            digest.push_str(&format!("- {}: {}\n", path, summary.replace('\n', " ")));
+        }
+    }
+
+    if let Some(readme) = readme {
+        let text: Vec<&str> = readme
+            .lines()
+            .filter(|line| !line.contains("<!-- dredger:"))
+            .collect();
+        digest.push_str(&format!("\nCurrent README:\n{}\n", text.join("\n")));
+    }
+
+    // Last, since big repos have more files than fit
+    digest.push_str("\nFiles:\n");
+    for (path, _) in &files {
+        digest.push_str(&format!("- {}\n", path));
+    }
+
+    match digest.char_indices().nth(DIGEST_CHARS) {
+        Some((cut, _)) => digest[..cut].to_string(),
+        None => digest,
+    }
+}
+
+fn section_prompt(section: ReadmeSection) -> Prompt {
+    let task = match section {
+        ReadmeSection::Overview => "an Overview section: what the project is, what problem it solves and its main features, in one to three short paragraphs",
+        ReadmeSection::Installation => "an Installation section: what's needed to build or install the project and the commands to do it, in fenced code blocks, as the build manifests show",
+        ReadmeSection::Usage => "a Usage section: how to run or use the project, with short examples in fenced code blocks",
+        ReadmeSection::Architecture => "an Architecture section: the main directories and modules, what each is responsible for and how they fit together, as a short list",
+    };
+
+    Prompt {
+        system: format!("You are an AI that writes README files for software projects, in GitHub-flavored Markdown.
+             Given facts about a repository (its files, build manifests, module docs and current README), write {}.
+             Only state what the facts support; do NOT invent commands, flags or features.
+             Do NOT write the section's heading, only its body.", task),
+        examples: Vec::new(),
+    }
+}
+
+/// Writes the configured README sections from what the run learned about
+/// the repo, as an edit of its root README.
+///
+/// An existing README only gets the configured sections it wraps in
+/// `<!-- dredger:start <section> -->` and `<!-- dredger:end <section> -->`
+/// markers refreshed; everything else in it is left alone. A repo without
+/// a README of any kind (`README.rst` and `readme.txt` count) gets a new
+/// `README.md` titled `repo_name` with every configured section.
+///
+/// Returns `None` if there's nothing to write.
+pub async fn readme_edit(
+    root_node: &RepoNode,
+    repo_name: &str,
+    edits: &[DocEdit],
+    readme_config: &ReadmeConfig,
+) -> Option<DocEdit> {
+    let readme = find_readme(root_node);
+    let (path, content, sha, token_count) = match readme {
+        Some(RepoNode::File {
+            path,
+            content,
+            sha,
+            token_count,
+            ..
+        }) => (path.clone(), Some(content), Some(sha.clone()), *token_count),
+        _ => (README_PATH.to_string(), None, None, 0),
+    };
+
+    let wanted: Vec<ReadmeSection> = match content {
+        Some(content) => marked_sections(content)
+            .into_iter()
+            .filter(|section| readme_config.sections.contains(section))
+            .collect(),
+        None => readme_config.sections.clone(),
+    };
+    if wanted.is_empty() {
+        return None;
+    }
+
+    let digest = repo_digest(root_node, edits, content.map(String::as_str));
+    let mut sections = Vec::new();
+    for section in wanted {
+        match ollama_client::query_ollama_for_doc(section_prompt(section), &digest).await {
+            Ok(output) => {
+                let text = clean_section(&output, section);
+                if !text.is_empty() {
+                    sections.push((section, text));
+                }
+            }
+            Err(e) => eprintln!(
+                "Error querying Ollama for the README's {} section: {}",
+                section.name(),
+                e
+            ),
+        }
+    }
+    if sections.is_empty() {
+        return None;
+    }
+
+    let (new_content, lines_added, lines_replaced) = match content {
+        Some(content) => splice_sections(content, &sections),
+        None => {
+            let new_content = new_readme(repo_name, &sections);
+            let lines_added = new_content.lines().count();
+            (new_content, lines_added, 0)
+        }
+    };
+    if content.is_some_and(|content| *content == new_content) {
+        return None;
+    }
+
+    let names: Vec<&str> = sections
+        .iter()
+        .map(|(section, _)| section.title())
+        .collect();
+    Some(DocEdit {
+        change: FileChange {
+            path,
+            content: new_content,
+            base_sha: sha,
+        },
+        original_content: content.cloned().unwrap_or_default(),
+        token_count,
+        lines_added,
+        lines_replaced,
+        rationale: format!("Wrote the README's {} section(s).", names.join(", ")),
+        links_fixed: 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const README: &str = "\
+# Tool
+
+Hand-written intro.
+
+## Usage
+
+<!-- dredger:start usage -->
+Old usage.
+<!-- dredger:end usage -->
+
+## Install
+
+<!-- dredger:start installation -->
+Never closed.
+";
+
+    #[test]
+    fn test_splice_sections_only_touches_marked_text() {
+        assert_eq!(marked_sections(README), vec![ReadmeSection::Usage]);
+
+        let (new_readme, added, replaced) = splice_sections(
+            README,
+            &[
+                (
+                    ReadmeSection::Usage,
+                    "Run `tool`.\n\n```sh\ntool --help\n```".to_string(),
+                ),
+                (
+                    ReadmeSection::Installation,
+                    "cargo install tool".to_string(),
+                ),
+                (ReadmeSection::Overview, "A tool.".to_string()),
+            ],
+        );
+        assert_eq!((added, replaced), (5, 1));
+        assert_eq!(
+            new_readme,
+            README.replace("Old usage.", "Run `tool`.\n\n```sh\ntool --help\n```")
+        );
+        assert_eq!(
+            splice_sections(
+                &new_readme,
+                &[(
+                    ReadmeSection::Usage,
+                    "Run `tool`.\n\n```sh\ntool --help\n```".to_string()
+                )]
+            ),
+            (new_readme.clone(), 0, 0)
+        );
+    }
+
+    #[test]
+    fn test_find_readme_matches_any_format() {
+        let root = |names: &[&str]| RepoNode::Directory {
+            name: String::new(),
+            path: String::new(),
+            children: names
+                .iter()
+                .map(|name| RepoNode::File {
+                    name: name.to_string(),
+                    path: name.to_string(),
+                    sha: String::new(),
+                    content: String::new(),
+                    token_count: 0,
+                    summary: None,
+                })
+                .collect(),
+            token_count: 0,
+            summary: None,
+        };
+        let found =
+            |names: &[&str]| find_readme(&root(names)).map(|node| child_name(node).to_string());
+
+        assert_eq!(
+            found(&["main.rs", "README.rst"]).as_deref(),
+            Some("README.rst")
+        );
+        assert_eq!(found(&["readme.txt"]).as_deref(), Some("readme.txt"));
+        assert_eq!(
+            found(&["README.rst", "Readme.markdown"]).as_deref(),
+            Some("Readme.markdown")
+        );
+        assert_eq!(found(&["READMEFIRST.md", "main.rs"]), None);
+    }
+
+    #[test]
+    fn test_new_readme_wraps_sections_in_markers() {
+        let readme = new_readme(
+            "tool",
+            &[
+                (ReadmeSection::Overview, "A tool.".to_string()),
+                (ReadmeSection::Usage, "Run it.".to_string()),
+            ],
+        );
+        assert_eq!(
+            readme,
+            "# tool\n\n## Overview\n\n<!-- dredger:start overview -->\nA tool.\n<!-- dredger:end overview -->\n\n## Usage\n\n<!-- dredger:start usage -->\nRun it.\n<!-- dredger:end usage -->\n"
+        );
+        assert_eq!(
+            marked_sections(&readme),
+            vec![ReadmeSection::Overview, ReadmeSection::Usage]
+        );
+        assert_eq!(
+            clean_section(
+                "```markdown\n## Usage\nRun it.\n<!-- dredger:end usage -->\n```",
+                ReadmeSection::Usage
+            ),
+            "Run it."
+        );
+    }
+}
@@ -0,0 +1,123 @@
+use crate::core::splice::DocEdit;
+use std::time::Duration;
+
+/// Everything needed to describe a Dredger run in its pull request
+pub struct RunSummary<'a> {
+    pub model: &'a str,
+    pub prompt_version: &'a str,
+    pub duration: Duration,
+    pub edits: &'a [DocEdit],
+}
+
+/// Renders the markdown body of a Dredger pull request: a table of the
+/// touched files, the run's metadata, and the model's rationale for
+/// each file tucked into a collapsible section.
+pub fn render_pr_body(summary: &RunSummary) -> String {
+    let mut body = String::from(
+        "🦀 Dredger generated documentation for the files below. \
+         Each change only adds or replaces doc comments.\n\n",
+    );
+
+    body.push_str("| File | Doc lines added | Doc lines replaced | Tokens |\n");
+    body.push_str("| --- | ---: | ---: | ---: |\n");
+    for edit in summary.edits {
+        body.push_str(&format!(
+            "| `{}` | {} | {} | {} |\n",
+            edit.change.path, edit.lines_added, edit.lines_replaced, edit.token_count
+        ));
+    }
+    body.push_str(&format!(
+        "| **Total** | **{}** | **{}** | **{}** |\n",
+        summary.edits.iter().map(|e| e.lines_added).sum::<usize>(),
+        summary
+            .edits
+            .iter()
+            .map(|e| e.lines_replaced)
+            .sum::<usize>(),
+        summary.edits.iter().map(|e| e.token_count).sum::<usize>(),
+    ));
+
+    body.push_str("\n### Run details\n\n");
+    body.push_str(&format!("- **Model:** `{}`\n", summary.model));
+    body.push_str(&format!(
+        "- **Prompt version:** `{}`\n",
+        summary.prompt_version
+    ));
+    body.push_str(&format!(
+        "- **Run duration:** {}\n",
+        format_duration(summary.duration)
+    ));
+    body.push_str(&format!(
+        "- **Broken intra-doc links fixed:** {}\n",
+        summary.edits.iter().map(|e| e.links_fixed).sum::<usize>()
+    ));
+
+    body.push_str("\n### Rationale\n");
+    for edit in summary.edits {
+        let rationale = if edit.rationale.is_empty() {
+            "_The model gave no rationale for this file._"
+        } else {
+            edit.rationale.as_str()
+        };
+        body.push_str(&format!(
+            "\n<details>\n<summary><code>{}</code></summary>\n\n{}\n\n</details>\n",
+            edit.change.path, rationale
+        ));
+    }
+
+    body
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 60 {
+        format!("{}m {}s", secs / 60, secs % 60)
+    } else {
+        format!("{:.1}s", duration.as_secs_f64())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::github_client::data::FileChange;
+
+    fn edit(path: &str, lines_added: usize, links_fixed: usize, rationale: &str) -> DocEdit {
+        DocEdit {
+            change: FileChange {
+                path: path.to_string(),
+                content: String::new(),
+                base_sha: Some("abc".to_string()),
+            },
+            original_content: String::new(),
+            token_count: 100,
+            lines_added,
+            lines_replaced: 1,
+            rationale: rationale.to_string(),
+            links_fixed,
+        }
+    }
+
+    #[test]
+    fn test_render_pr_body_lists_files_and_totals() {
+        let edits = vec![
+            edit("src/lib.rs", 4, 2, "Documents the crate root."),
+            edit("src/parser.rs", 6, 1, ""),
+        ];
+        let body = render_pr_body(&RunSummary {
+            model: "llama3.1",
+            prompt_version: "1",
+            duration: Duration::from_secs(75),
+            edits: &edits,
+        });
+
+        assert!(
+            body.contains("| `src/lib.rs` | 4 | 1 | 100 |\n| `src/parser.rs` | 6 | 1 | 100 |\n")
+        );
+        assert!(body.contains("| **Total** | **10** | **2** | **200** |\n"));
+        assert!(body.contains("- **Run duration:** 1m 15s\n"));
+        assert!(body.contains("- **Broken intra-doc links fixed:** 3\n"));
+        assert!(body.contains("Documents the crate root."));
+        assert!(body.contains("_The model gave no rationale for this file._"));
+    }
+}
@@ -0,0 +1,176 @@
+use crate::github_client::data::{PullRequestFile, ReviewComment};
+use similar::{DiffOp, TextDiff};
+use std::collections::HashSet;
+
+/// Line numbers (in the new version of the file) that a unified diff
+/// `patch` covers. GitHub only accepts review comments on these lines.
+pub fn commentable_lines(patch: &str) -> HashSet<usize> {
+    let mut lines = HashSet::new();
+    let mut new_line = 0;
+
+    for line in patch.lines() {
+        if let Some(header) = line.strip_prefix("@@ ") {
+            // @@ -old_start,old_len +new_start,new_len @@
+            new_line = header
+                .split_whitespace()
+                .find_map(|part| part.strip_prefix('+'))
+                .and_then(|range| range.split(',').next())
+                .and_then(|start| start.parse().ok())
+                .unwrap_or(0);
+        } else if line.starts_with('-') || line.starts_with('\\') {
+            // Removed lines and "\ No newline at end of file" don't exist on the new side
+        } else {
+            lines.insert(new_line);
+            new_line += 1;
+        }
+    }
+
+    lines
+}
+
+/// A `suggestion` block holding `text`, fenced with more backticks than
+/// any run of them in it, so fenced examples in the docs don't close it
+fn suggestion_block(text: &str) -> String {
+    let longest_run = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest_run.max(2) + 1);
+    format!("{}suggestion\n{}\n{}", fence, text, fence)
+}
+
+/// Turns the difference between a file's current content and its
+/// documented content into review comments holding `suggestion` blocks,
+/// so the PR author can accept each doc with one click.
+///
+/// Returns the anchored comments, plus the number of suggestions that
+/// couldn't be anchored because they fall outside the PR's diff.
+pub fn suggestions_for_file(
+    pr_file: &PullRequestFile,
+    original: &str,
+    documented: &str,
+) -> (Vec<ReviewComment>, usize) {
+    let commentable = pr_file
+        .patch
+        .as_deref()
+        .map(commentable_lines)
+        .unwrap_or_default();
+    let original_lines: Vec<&str> = original.lines().collect();
+    let documented_lines: Vec<&str> = documented.lines().collect();
+
+    let mut comments = Vec::new();
+    let mut unanchored = 0;
+
+    let diff = TextDiff::from_lines(original, documented);
+    for op in diff.ops() {
+        let (old_range, new_range) = match op {
+            DiffOp::Equal { .. } => continue,
+            _ => (op.old_range(), op.new_range()),
+        };
+
+        let mut suggested: Vec<&str> = documented_lines[new_range].to_vec();
+
+        // Suggestions replace a range of existing lines (1-based, inclusive).
+        // Pure insertions get folded into the line after them, or the line
+        // before them at the end of the file.
+        let (start_line, end_line) = if !old_range.is_empty() {
+            (old_range.start + 1, old_range.end)
+        } else if old_range.start < original_lines.len() {
+            suggested.push(original_lines[old_range.start]);
+            (old_range.start + 1, old_range.start + 1)
+        } else if old_range.start > 0 {
+            suggested.insert(0, original_lines[old_range.start - 1]);
+            (old_range.start, old_range.start)
+        } else {
+            unanchored += 1; // Nothing to anchor to in an empty file
+            continue;
+        };
+
+        if !(start_line..=end_line).all(|line| commentable.contains(&line)) {
+            unanchored += 1;
+            continue;
+        }
+
+        comments.push(ReviewComment {
+            path: pr_file.filename.clone(),
+            body: suggestion_block(&suggested.join("\n")),
+            line: end_line,
+            start_line: (start_line != end_line).then_some(start_line),
+            side: "RIGHT".to_string(),
+        });
+    }
+
+    (comments, unanchored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pr_file(patch: &str) -> PullRequestFile {
+        PullRequestFile {
+            filename: "src/lib.rs".to_string(),
+            status: "modified".to_string(),
+            patch: Some(patch.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_commentable_lines_follows_hunks() {
+        let patch =
+            "@@ -1,2 +1,3 @@\n use std::fmt;\n+use std::io;\n \n@@ -10,1 +11,1 @@\n-old\n+new";
+        let lines = commentable_lines(patch);
+
+        assert_eq!(lines, HashSet::from([1, 2, 3, 11]));
+    }
+
+    #[test]
+    fn test_suggestions_fold_insertions_into_next_line() {
+        let original = "use std::fmt;\n\npub fn run() {}\n";
+        let documented = "//! Runs things.\n\nuse std::fmt;\n\npub fn run() {}\n";
+
+        let (comments, unanchored) = suggestions_for_file(
+            &pr_file("@@ -1,3 +1,3 @@\n use std::fmt;\n \n pub fn run() {}"),
+            original,
+            documented,
+        );
+
+        assert_eq!(unanchored, 0);
+        assert_eq!(comments.len(), 1);
+        assert_eq!(comments[0].line, 1);
+        assert_eq!(comments[0].start_line, None);
+        assert_eq!(
+            comments[0].body,
+            "```suggestion\n//! Runs things.\n\nuse std::fmt;\n```"
+        );
+    }
+
+    #[test]
+    fn test_suggestions_with_fenced_examples_get_a_longer_fence() {
+        let original = "pub fn run() {}\n";
+        let documented = "/// Runs.\n///\n/// ```\n/// run();\n/// ```\npub fn run() {}\n";
+
+        let (comments, _) = suggestions_for_file(
+            &pr_file("@@ -0,0 +1,1 @@\n+pub fn run() {}"),
+            original,
+            documented,
+        );
+
+        assert_eq!(
+            comments[0].body,
+            "````suggestion\n/// Runs.\n///\n/// ```\n/// run();\n/// ```\npub fn run() {}\n````"
+        );
+    }
+
+    #[test]
+    fn test_suggestions_outside_the_diff_are_unanchored() {
+        let original = "use std::fmt;\n\npub fn run() {}\n";
+        let documented = "//! Runs things.\n\nuse std::fmt;\n\npub fn run() {}\n";
+
+        let (comments, unanchored) = suggestions_for_file(
+            &pr_file("@@ -3,1 +3,1 @@\n-pub fn go() {}\n+pub fn run() {}"),
+            original,
+            documented,
+        );
+
+        assert!(comments.is_empty());
+        assert_eq!(unanchored, 1);
+    }
+}
@@ -0,0 +1,482 @@
+use crate::github_client::data::{FileChange, RepoNode};
+use crate::languages::registry;
+use crate::ollama_client::client::{DocAnchor, DredgerDoc};
+use std::cmp::Reverse;
+
+/// A file change produced from a `DredgerDoc`, plus what went into it
+#[derive(Debug, Clone)]
+pub struct DocEdit {
+    pub change: FileChange,
+    pub original_content: String,
+    pub token_count: usize,
+    pub lines_added: usize,
+    pub lines_replaced: usize,
+    pub rationale: String,
+    /// Broken intra-doc links in the generated docs that were rewritten
+    /// to code spans
+    pub links_fixed: usize,
+}
+
+/// Places generated `//!` module docs at the top of a file's content.
+///
+/// The docs go below a shebang line and a leading license header, and
+/// above any `#![...]` inner attributes. An existing `//!` (or `/*! */`)
+/// block in that header is replaced in place instead of getting a
+/// second block stacked above it.
+///
+/// Returns the new content along with how many doc lines were added
+/// and how many existing doc lines were replaced.
+pub fn splice_module_docs(content: &str, comments: &str) -> (String, usize, usize) {
+    let docs: Vec<&str> = comments
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| line.trim_start().starts_with("//!"))
+        .collect();
+    if docs.is_empty() {
+        return (content.to_string(), 0, 0);
+    }
+
+    let lines: Vec<&str> = content.lines().collect();
+    let header = module_header(&lines);
+
+    let (before, after, lines_replaced) = match header.existing_docs {
+        Some((start, end)) => {
+            if lines[start..end] == docs[..] {
+                return (content.to_string(), 0, 0);
+            }
+            (&lines[..start], &lines[end..], end - start)
+        }
+        None => {
+            // Keep one blank line between the docs and whatever surrounds them
+            let rest = &lines[header.insert_at..];
+            let blank_lines = rest
+                .iter()
+                .take_while(|line| line.trim().is_empty())
+                .count();
+            (&lines[..header.insert_at], &rest[blank_lines..], 0)
+        }
+    };
+
+    let mut new_lines: Vec<&str> = before.to_vec();
+    if header.existing_docs.is_none() && before.last().is_some_and(|line| !line.trim().is_empty()) {
+        new_lines.push("");
+    }
+    new_lines.extend(&docs);
+    if header.existing_docs.is_none() && !after.is_empty() {
+        new_lines.push("");
+    }
+    new_lines.extend(after);
+
+    let mut new_content = new_lines.join("\n");
+    if content.ends_with('\n') || content.is_empty() {
+        new_content.push('\n');
+    }
+
+    (new_content, docs.len(), lines_replaced)
+}
+
+/// Whether a file has module docs, found by scanning its header rather
+/// than parsing it, so it also works on files that don't parse
+pub fn has_module_docs(content: &str) -> bool {
+    let lines: Vec<&str> = content.lines().collect();
+    module_header(&lines).existing_docs.is_some()
+}
+
+/// Where module docs belong in a file
+struct ModuleHeader {
+    /// Line new docs go on: after the shebang and license header
+    insert_at: usize,
+    /// Line range of the module docs the file already has
+    existing_docs: Option<(usize, usize)>,
+}
+
+fn module_header(lines: &[&str]) -> ModuleHeader {
+    let mut i = 0;
+    let mut insert_at = 0;
+
+    // `#!/usr/bin/env ...`, which `#![...]` must not be mistaken for
+    if lines
+        .first()
+        .is_some_and(|line| line.starts_with("#!") && !line.starts_with("#!["))
+    {
+        i = 1;
+        insert_at = 1;
+    }
+
+    // License header: plain comments before any code
+    while i < lines.len() {
+        let line = lines[i].trim();
+        if line.starts_with("//") && !line.starts_with("///") && !line.starts_with("//!") {
+            i += 1;
+            insert_at = i;
+        } else if line.starts_with("/*") && !line.starts_with("/**") && !line.starts_with("/*!") {
+            i = block_comment_end(lines, i);
+            insert_at = i;
+        } else if line.is_empty() {
+            i += 1;
+        } else {
+            break;
+        }
+    }
+
+    // Existing docs may sit before, between or after inner attributes
+    while i < lines.len() {
+        let line = lines[i].trim();
+        if line.starts_with("//!") {
+            let end = i + lines[i..]
+                .iter()
+                .take_while(|line| line.trim_start().starts_with("//!"))
+                .count();
+            return ModuleHeader {
+                insert_at,
+                existing_docs: Some((i, end)),
+            };
+        } else if line.starts_with("/*!") {
+            return ModuleHeader {
+                insert_at,
+                existing_docs: Some((i, block_comment_end(lines, i))),
+            };
+        } else if line
+            .strip_prefix("#![doc")
+            .is_some_and(|rest| rest.trim_start().starts_with('='))
+        {
+            // e.g. `#![doc = include_str!("../README.md")]`
+            return ModuleHeader {
+                insert_at,
+                existing_docs: Some((i, attribute_end(lines, i) + 1)),
+            };
+        } else if line.starts_with("#![") {
+            // Attributes can span several lines
+            i = attribute_end(lines, i) + 1;
+        } else if line.is_empty() || line.starts_with("//") {
+            i += 1;
+        } else {
+            break;
+        }
+    }
+
+    ModuleHeader {
+        insert_at,
+        existing_docs: None,
+    }
+}
+
+/// The line an attribute starting on line `start` closes on, found by
+/// bracket depth (outside string literals), so a trailing comment or a
+/// continuation line like `)]` doesn't throw it off
+fn attribute_end(lines: &[&str], start: usize) -> usize {
+    let mut depth = 0;
+    let mut in_string = false;
+    for (index, line) in lines.iter().enumerate().skip(start) {
+        let mut chars = line.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' if in_string => {
+                    chars.next();
+                }
+                '"' => in_string = !in_string,
+                '[' if !in_string => depth += 1,
+                ']' if !in_string => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return index;
+                    }
+                }
+                '/' if !in_string && chars.clone().next() == Some('/') => break,
+                _ => {}
+            }
+        }
+    }
+
+    lines.len() - 1
+}
+
+/// Returns the line after the one closing the block comment opened on
+/// line `start`
+fn block_comment_end(lines: &[&str], start: usize) -> usize {
+    let mut i = start;
+    // The opening `/*` itself must not count as the closing `*/`
+    let mut rest = &lines[i].trim_start()[2..];
+    loop {
+        if rest.contains("*/") || i + 1 >= lines.len() {
+            return i + 1;
+        }
+        i += 1;
+        rest = lines[i];
+    }
+}
+
+/// Inserts `///` docs for the item `name`, whose declaration starts on
+/// `line` (1-based), directly above that line. Attributes stay above
+/// the docs, and the docs get the item's `indent`.
+///
+/// Returns the new content and how many doc lines were added, or `None`
+/// if `line` doesn't declare `name` (e.g. the file changed since the
+/// item was found).
+pub fn splice_item_docs(
+    content: &str,
+    line: usize,
+    name: &str,
+    indent: &str,
+    comments: &str,
+) -> Option<(String, usize)> {
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    if line == 0 || !lines.get(line - 1)?.contains(name) {
+        return None;
+    }
+
+    let docs: Vec<String> = comments
+        .lines()
+        .map(str::trim)
+        .filter(|comment| comment.starts_with("///"))
+        .map(|comment| format!("{}{}", indent, comment))
+        .collect();
+    let lines_added = docs.len();
+    lines.splice(line - 1..line - 1, docs);
+
+    let mut new_content = lines.join("\n");
+    if content.ends_with('\n') {
+        new_content.push('\n');
+    }
+    Some((new_content, lines_added))
+}
+
+/// Splices the `DredgerDoc`s of each file into the matching file of the
+/// repo tree, the way the file's language places docs, returning only
+/// the files whose content actually changed.
+///
+/// Item docs go in bottom-up, so the line anchors of the items above
+/// stay valid, and the module docs go in last.
+pub fn apply_docs(root_node: &RepoNode, docs: &[DredgerDoc]) -> Vec<DocEdit> {
+    let mut edits = Vec::new();
+
+    let mut file_paths: Vec<&str> = Vec::new();
+    for doc in docs {
+        if !file_paths.contains(&doc.file_path.as_str()) {
+            file_paths.push(&doc.file_path);
+        }
+    }
+
+    for file_path in file_paths {
+        let file = root_node.iter().find_map(|node| match node {
+            RepoNode::File {
+                path,
+                sha,
+                content,
+                token_count,
+                ..
+            } if path == file_path => Some((sha, content, token_count)),
+            _ => None,
+        });
+
+        let Some((sha, content, token_count)) = file else {
+            eprintln!("No file found for generated docs: {}", file_path);
+            continue;
+        };
+        let Some(language) = registry::for_path(file_path) else {
+            eprintln!("No language found for generated docs: {}", file_path);
+            continue;
+        };
+
+        let mut file_docs: Vec<&DredgerDoc> = docs
+            .iter()
+            .filter(|doc| doc.file_path == file_path)
+            .collect();
+        // Bottom-up: items from the last line to the first, then the module
+        file_docs.sort_by_key(|doc| match &doc.anchor {
+            DocAnchor::Item { line, .. } => (0, Reverse(*line)),
+            DocAnchor::Module => (1, Reverse(0)),
+        });
+
+        let mut new_content = content.clone();
+        let mut lines_added = 0;
+        let mut lines_replaced = 0;
+        for doc in &file_docs {
+            match &doc.anchor {
+                DocAnchor::Module => {
+                    let (spliced, added, replaced) =
+                        language.splice_module_docs(&new_content, &doc.comments);
+                    new_content = spliced;
+                    lines_added += added;
+                    lines_replaced += replaced;
+                }
+                DocAnchor::Item { name, line, indent } => {
+                    match language.splice_item_docs(
+                        &new_content,
+                        *line,
+                        name,
+                        indent,
+                        &doc.comments,
+                    ) {
+                        Some((spliced, added)) => {
+                            new_content = spliced;
+                            lines_added += added;
+                        }
+                        None => eprintln!(
+                            "{} is no longer on line {} of {}, skipping its docs",
+                            name, line, file_path
+                        ),
+                    }
+                }
+            }
+        }
+
+        if new_content != *content {
+            edits.push(DocEdit {
+                change: FileChange {
+                    path: file_path.to_string(),
+                    content: new_content,
+                    base_sha: Some(sha.clone()),
+                },
+                original_content: content.clone(),
+                token_count: *token_count,
+                lines_added,
+                lines_replaced,
+                rationale: combined_rationale(&file_docs),
+                links_fixed: 0,
+            });
+        }
+    }
+
+    edits
+}
+
+/// The rationales of a file's docs, module docs first, then each item's
+/// under its name. `file_docs` is in splicing (bottom-up) order.
+fn combined_rationale(file_docs: &[&DredgerDoc]) -> String {
+    let mut sections: Vec<String> = Vec::new();
+    for doc in file_docs.iter().rev() {
+        if doc.rationale.is_empty() {
+            continue;
+        }
+        match &doc.anchor {
+            DocAnchor::Module => sections.push(doc.rationale.clone()),
+            DocAnchor::Item { name, .. } => sections.push(format!("`{}`: {}", name, doc.rationale)),
+        }
+    }
+    sections.join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_splice_module_docs_after_shebang_and_license() {
+        let content = "\
+#!/usr/bin/env run-cargo-script
+// Copyright (c) Example
+// SPDX-License-Identifier: MIT
+
+#![deny(missing_docs)]
+
+fn main() {}
+";
+        let (new_content, added, replaced) = splice_module_docs(
+            content,
+            "Here are the docs:\n//! Runs things.\n//!\n//! Fast.",
+        );
+
+        assert_eq!(
+            new_content,
+            "\
+#!/usr/bin/env run-cargo-script
+// Copyright (c) Example
+// SPDX-License-Identifier: MIT
+
+//! Runs things.
+//!
+//! Fast.
+
+#![deny(missing_docs)]
+
+fn main() {}
+"
+        );
+        assert_eq!((added, replaced), (3, 0));
+    }
+
+    #[test]
+    fn test_splice_module_docs_replaces_existing_block() {
+        let content = "\
+/* Licensed under MIT */
+#![allow(dead_code)] // Until it's all wired up
+//! Old docs
+//! that span two lines
+
+pub fn run() {}
+";
+        let (new_content, added, replaced) = splice_module_docs(content, "//! New docs");
+
+        assert_eq!(
+            new_content,
+            "\
+/* Licensed under MIT */
+#![allow(dead_code)] // Until it's all wired up
+//! New docs
+
+pub fn run() {}
+"
+        );
+        assert_eq!((added, replaced), (1, 2));
+
+        // Splicing the same docs again is a no-op
+        assert_eq!(
+            splice_module_docs(&new_content, "//! New docs"),
+            (new_content.clone(), 0, 0)
+        );
+    }
+
+    #[test]
+    fn test_has_module_docs_without_parsing() {
+        // Doesn't parse, but its docs are still a human's
+        assert!(has_module_docs("//! Old docs\n\npub fn run( {}\n"));
+        assert!(has_module_docs(
+            "#![doc = include_str!(\"../README.md\")]\n#![deny(missing_docs)]\n"
+        ));
+        assert!(!has_module_docs(
+            "#![doc(html_root_url = \"https://docs.rs\")]\n"
+        ));
+        assert!(!has_module_docs("/// Item docs\npub fn run() {}\n"));
+    }
+
+    #[test]
+    fn test_splice_item_docs_goes_below_attributes() {
+        let content = "\
+impl Config {
+    #[inline]
+    pub fn new() -> Self {
+        Config
+    }
+}
+";
+        let (new_content, added) = splice_item_docs(
+            content,
+            3,
+            "new",
+            "    ",
+            "Sure!\n/// Creates a config.\n///\n/// Nothing is set.",
+        )
+        .unwrap();
+
+        assert_eq!(
+            new_content,
+            "\
+impl Config {
+    #[inline]
+    /// Creates a config.
+    ///
+    /// Nothing is set.
+    pub fn new() -> Self {
+        Config
+    }
+}
+"
+        );
+        assert_eq!(added, 3);
+        assert_eq!(
+            splice_item_docs(content, 2, "new", "    ", "/// Docs"),
+            None
+        );
+    }
+}
@@ -0,0 +1,315 @@
+use crate::core::readme::is_readme;
+use crate::github_client::data::RepoNode;
+use crate::languages::registry;
+use crate::languages::strategy::Prompt;
+use crate::ollama_client::client as ollama_client;
+use crate::utils::tokens::truncate_to_tokens;
+use std::future::Future;
+use std::pin::Pin;
+use tokenizers::Tokenizer;
+
+/// Most of a file the model reads to summarize it
+const FILE_INPUT_TOKENS: usize = 3_000;
+
+/// Most of a directory's digest the model reads to summarize it
+const DIRECTORY_INPUT_TOKENS: usize = 4_000;
+
+/// Longest a summary may get. Every file's doc prompt carries two of
+/// them (its directory's and the repo's), so they're kept short.
+const SUMMARY_TOKENS: usize = 150;
+
+/// Whether a file gets a summary: source files in a language Dredger
+/// knows, plus READMEs, which say best what a project is for
+fn is_summarized(path: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    registry::for_path(path).is_some() || is_readme(name)
+}
+
+fn file_prompt(path: &str) -> Prompt {
+    Prompt {
+        system: format!("You are an AI that summarizes the files of software projects.
+             Given the contents of the file `{}`, write one or two sentences on what it is responsible for.
+             Only write the sentences, without a heading, list or the file's name.", path),
+        examples: Vec::new(),
+    }
+}
+
+fn directory_prompt(path: &str, is_root: bool) -> Prompt {
+    let task = match is_root {
+        true => "write two or three sentences on what the project does and how it is organized"
+            .to_string(),
+        false => format!(
+            "write one or two sentences on what the directory `{}` is responsible for",
+            path
+        ),
+    };
+    Prompt {
+        system: format!(
+            "You are an AI that summarizes the directories of software projects.
+             Given a summary of each of its files and subdirectories, {}.
+             Only write the sentences, without a heading, list or the directory's name.",
+            task
+        ),
+        examples: Vec::new(),
+    }
+}
+
+/// The model's text, minus a fence around all of it and any heading line
+pub fn clean_output(output: &str) -> String {
+    let mut lines: Vec<&str> = output.trim().lines().collect();
+    if lines.first().is_some_and(|line| line.starts_with("```"))
+        && lines.last().is_some_and(|line| line.trim() == "```")
+        && lines.len() > 1
+    {
+        lines = lines[1..lines.len() - 1].to_vec();
+    }
+
+    lines
+        .into_iter()
+        .filter(|line| !line.starts_with('#'))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+/// The first paragraph of a file's module docs. A human wrote those, so
+/// they stand in for a summary without asking the model.
+fn existing_summary(path: &str, content: &str) -> Option<String> {
+    let module_docs = registry::for_path(path)?.parse(content).ok()?.module_docs?;
+    let paragraph = module_docs.trim().split("\n\n").next()?;
+    let paragraph = paragraph.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!paragraph.is_empty()).then_some(paragraph)
+}
+
+/// What the model reads to summarize a directory: the summary of each
+/// of its children that has one, one per line
+pub fn children_digest(children: &[RepoNode]) -> String {
+    children
+        .iter()
+        .filter_map(|child| match child {
+            RepoNode::File {
+                path,
+                summary: Some(summary),
+                ..
+            } => Some(format!("- {}: {}", path, summary)),
+            RepoNode::Directory {
+                path,
+                summary: Some(summary),
+                ..
+            } => Some(format!("- {}/: {}", path, summary)),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// What a file's doc prompts get to know about where it sits: the
+/// repo's summary and that of its `directory`, plus the summaries of
+/// everything in the directory when the file's module docs describe it
+/// (see `LanguageStrategy::describes_directory`)
+pub fn prompt_context(
+    root_node: &RepoNode,
+    directory: Option<&RepoNode>,
+    describes_directory: bool,
+) -> String {
+    let mut context = root_node.summary().unwrap_or_default().to_string();
+    // Files at the root get its summary as the repo's already
+    let is_root = directory.is_some_and(|directory| std::ptr::eq(directory, root_node));
+    let Some(RepoNode::Directory {
+        path,
+        children,
+        summary,
+        ..
+    }) = directory
+    else {
+        return context;
+    };
+
+    if let Some(summary) = summary.as_deref().filter(|_| !is_root) {
+        context.push_str(&format!("\n\nThe directory `{}`: {}", path, summary));
+    }
+    if describes_directory {
+        // At roughly four chars a token
+        let digest: String = children_digest(children)
+            .chars()
+            .take(DIRECTORY_INPUT_TOKENS * 4)
+            .collect();
+        if !digest.is_empty() {
+            context.push_str(&format!("\n\nWhat the directory holds:\n{}", digest));
+        }
+    }
+
+    context.trim().to_string()
+}
+
+/// Sends `input`, cut down to `input_budget` tokens, and returns the
+/// model's answer on one line and within `SUMMARY_TOKENS`. Failures are
+/// logged, since a missing summary only means less context.
+async fn summarize(
+    prompt: Prompt,
+    input: &str,
+    input_budget: usize,
+    tokenizer: &Tokenizer,
+    path: &str,
+) -> Option<String> {
+    let input = truncate_to_tokens(input, input_budget, tokenizer)
+        .map_err(|e| eprintln!("Could not count the tokens of {}: {}", path, e))
+        .ok()?;
+    let output = ollama_client::query_ollama_for_doc(prompt, &input)
+        .await
+        .map_err(|e| eprintln!("Error querying Ollama for a summary of {}: {}", path, e))
+        .ok()?;
+
+    let summary = clean_output(&output).replace('\n', " ");
+    let summary = truncate_to_tokens(&summary, SUMMARY_TOKENS, tokenizer).ok()?;
+    (!summary.trim().is_empty()).then(|| summary.trim().to_string())
+}
+
+/// Summarizes `node`'s children before `node` itself, so a directory's
+/// summary is reduced from theirs
+fn summarize_node<'a>(
+    node: &'a mut RepoNode,
+    is_root: bool,
+    tokenizer: &'a Tokenizer,
+) -> Pin<Box<dyn Future<Output = ()> + 'a>> {
+    Box::pin(async move {
+        match node {
+            RepoNode::File {
+                path,
+                content,
+                summary,
+                ..
+            } => {
+                if !is_summarized(path) {
+                    return;
+                }
+                *summary = match existing_summary(path, content) {
+                    Some(existing) => truncate_to_tokens(&existing, SUMMARY_TOKENS, tokenizer).ok(),
+                    None => {
+                        summarize(
+                            file_prompt(path),
+                            content,
+                            FILE_INPUT_TOKENS,
+                            tokenizer,
+                            path,
+                        )
+                        .await
+                    }
+                };
+            }
+            RepoNode::Directory {
+                path,
+                children,
+                summary,
+                ..
+            } => {
+                for child in children.iter_mut() {
+                    summarize_node(child, false, tokenizer).await;
+                }
+
+                let digest = children_digest(children);
+                if digest.is_empty() {
+                    return;
+                }
+                *summary = summarize(
+                    directory_prompt(path, is_root),
+                    &digest,
+                    DIRECTORY_INPUT_TOKENS,
+                    tokenizer,
+                    if is_root { "the repo" } else { path },
+                )
+                .await;
+            }
+        }
+    })
+}
+
+/// Map-reduces the repo into summaries stored on its nodes: each source
+/// file (and README) is summarized on its own, then every directory from
+/// its children's summaries, up to the root, whose summary is the repo's.
+///
+/// Files with module docs use their first paragraph instead of a model
+/// call. Every input is cut to a token budget, and directories with
+/// nothing summarized under them get no summary.
+pub async fn summarize_repo(root_node: &mut RepoNode, tokenizer: &Tokenizer) {
+    summarize_node(root_node, true, tokenizer).await;
+    if let Some(summary) = root_node.summary() {
+        eprintln!("📄 Project Summary:\n{}", summary);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, content: &str, summary: Option<&str>) -> RepoNode {
+        RepoNode::File {
+            name: path.rsplit('/').next().unwrap().to_string(),
+            path: path.to_string(),
+            sha: String::new(),
+            content: content.to_string(),
+            token_count: 0,
+            summary: summary.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_digest_context_and_existing_summary() {
+        let children = vec![
+            file("src/main.rs", "", Some("The entry point.")),
+            file("src/logo.png", "", None),
+            RepoNode::Directory {
+                name: "src/parser".to_string(),
+                path: "src/parser".to_string(),
+                children: Vec::new(),
+                token_count: 0,
+                summary: Some("Parsing.".to_string()),
+            },
+        ];
+        assert_eq!(
+            children_digest(&children),
+            "- src/main.rs: The entry point.\n- src/parser/: Parsing."
+        );
+
+        let root_node = RepoNode::Directory {
+            name: String::new(),
+            path: String::new(),
+            children: vec![RepoNode::Directory {
+                name: "src".to_string(),
+                path: "src".to_string(),
+                children,
+                token_count: 0,
+                summary: Some("The code.".to_string()),
+            }],
+            token_count: 0,
+            summary: Some("A parser.".to_string()),
+        };
+        let RepoNode::Directory { children, .. } = &root_node else {
+            unreachable!()
+        };
+        assert_eq!(
+            prompt_context(&root_node, Some(&children[0]), false),
+            "A parser.\n\nThe directory `src`: The code."
+        );
+        assert_eq!(
+            prompt_context(&root_node, Some(&children[0]), true),
+            "A parser.\n\nThe directory `src`: The code.\n\nWhat the directory holds:\n- src/main.rs: The entry point.\n- src/parser/: Parsing."
+        );
+        assert_eq!(
+            prompt_context(&root_node, Some(&root_node), false),
+            "A parser."
+        );
+
+        assert_eq!(
+            existing_summary(
+                "src/lib.rs",
+                "//! Reads config\n//! files.\n//!\n//! More.\n\npub fn read() {}\n"
+            ),
+            Some("Reads config files.".to_string())
+        );
+        assert_eq!(existing_summary("src/lib.rs", "pub fn read() {}\n"), None);
+        assert!(is_summarized("docs/README.md"));
+        assert!(!is_summarized("logo.png"));
+    }
+}
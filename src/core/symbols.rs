@@ -0,0 +1,324 @@
+use crate::github_client::data::RepoNode;
+use std::collections::{HashMap, HashSet};
+use syn::visit::{self, Visit};
+
+/// Names of everything defined in a dredged crate, used to check that
+/// generated docs only mention items that exist
+#[derive(Debug, Default)]
+pub struct Symbols {
+    /// The crate's name as code refers to it (dashes become underscores)
+    pub crate_name: Option<String>,
+    /// The package's name in its Cargo.toml, as dependents list it
+    pub package_name: Option<String>,
+    names: HashSet<String>,
+    /// The items under each type and trait: methods, associated items,
+    /// variants and fields
+    members: HashMap<String, HashSet<String>>,
+    /// The traits each type implements, whose items are under it too
+    traits: HashMap<String, HashSet<String>>,
+}
+
+impl Symbols {
+    pub fn contains(&self, name: &str) -> bool {
+        self.names.contains(name)
+    }
+
+    #[cfg(test)]
+    pub(crate) fn insert(&mut self, name: &str) {
+        self.names.insert(name.to_string());
+    }
+
+    #[cfg(test)]
+    pub(crate) fn insert_member(&mut self, owner: &str, name: &str) {
+        self.insert(owner);
+        self.insert(name);
+        self.members
+            .entry(owner.to_string())
+            .or_default()
+            .insert(name.to_string());
+    }
+
+    /// Whether `segments` (e.g. `["config", "RepoConfig", "load"]`) can
+    /// name something in the crate. Every segment has to be a known
+    /// symbol, and one following a type or trait has to be among its
+    /// items. Modules aren't followed that way, since re-exports make
+    /// an item reachable from more than where it's defined.
+    pub fn resolves(&self, segments: &[String]) -> bool {
+        segments.iter().all(|segment| self.contains(segment))
+            && segments
+                .windows(2)
+                .all(|pair| self.is_member(&pair[0], &pair[1]))
+    }
+
+    /// Whether `name` is among `owner`'s items, or its traits' items.
+    /// Anything is, under an owner that isn't a type or trait.
+    fn is_member(&self, owner: &str, name: &str) -> bool {
+        let Some(members) = self.members.get(owner) else {
+            return true;
+        };
+        members.contains(name)
+            || self.traits.get(owner).is_some_and(|traits| {
+                traits.iter().any(|name_of_trait| {
+                    self.members
+                        .get(name_of_trait)
+                        .is_some_and(|members| members.contains(name))
+                })
+            })
+    }
+}
+
+/// Builds the symbol table of the crate in `root`: every item, method,
+/// enum variant and named field of its Rust files, plus the modules
+/// those files make up. Files that don't parse are skipped.
+pub fn crate_symbols(root: &RepoNode) -> Symbols {
+    let mut collector = SymbolCollector::default();
+
+    for node in root.iter() {
+        let RepoNode::File { path, content, .. } = node else {
+            continue;
+        };
+
+        if path == "Cargo.toml" {
+            (collector.package_name, collector.crate_name) = manifest_names(content);
+        }
+        if !path.ends_with(".rs") {
+            continue;
+        }
+
+        // `src/config.rs` and `src/config/mod.rs` both define `config`
+        let module = path.trim_end_matches(".rs").trim_end_matches("/mod");
+        if let Some(name) = module.rsplit('/').next() {
+            collector.names.insert(name.to_string());
+        }
+
+        if let Ok(file) = syn::parse_file(content) {
+            collector.visit_file(&file);
+        }
+    }
+
+    Symbols {
+        crate_name: collector.crate_name,
+        package_name: collector.package_name,
+        names: collector.names,
+        members: collector.members,
+        traits: collector.traits,
+    }
+}
+
+/// Reads the package name from a Cargo.toml, and the library's name:
+/// `[lib] name` if set, the package name with underscores otherwise
+fn manifest_names(manifest: &str) -> (Option<String>, Option<String>) {
+    let mut section = "";
+    let mut package_name = None;
+    let mut lib_name = None;
+
+    for line in manifest.lines().map(str::trim) {
+        if line.starts_with('[') {
+            section = line;
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        if key.trim() != "name" {
+            continue;
+        }
+
+        let value = value.trim().trim_matches('"').to_string();
+        match section {
+            "[package]" => package_name = Some(value),
+            "[lib]" => lib_name = Some(value),
+            _ => {}
+        }
+    }
+
+    let crate_name = lib_name.or_else(|| package_name.as_ref().map(|name| name.replace('-', "_")));
+    (package_name, crate_name)
+}
+
+#[derive(Default)]
+struct SymbolCollector {
+    crate_name: Option<String>,
+    package_name: Option<String>,
+    names: HashSet<String>,
+    members: HashMap<String, HashSet<String>>,
+    traits: HashMap<String, HashSet<String>>,
+}
+
+impl SymbolCollector {
+    fn add_members(&mut self, owner: &syn::Ident, members: impl Iterator<Item = String>) {
+        self.members
+            .entry(owner.to_string())
+            .or_default()
+            .extend(members);
+    }
+}
+
+/// The name of the type an `impl` block is for, e.g. `Config` for
+/// `impl<T> fmt::Display for Config<T>`
+fn impl_owner(item: &syn::ItemImpl) -> Option<&syn::Ident> {
+    match item.self_ty.as_ref() {
+        syn::Type::Path(path) => path.path.segments.last().map(|segment| &segment.ident),
+        _ => None,
+    }
+}
+
+fn field_names(fields: &syn::Fields) -> impl Iterator<Item = String> + '_ {
+    fields
+        .iter()
+        .filter_map(|field| field.ident.as_ref().map(ToString::to_string))
+}
+
+impl<'ast> Visit<'ast> for SymbolCollector {
+    fn visit_item(&mut self, item: &'ast syn::Item) {
+        let ident = match item {
+            syn::Item::Const(item) => Some(&item.ident),
+            syn::Item::Enum(item) => Some(&item.ident),
+            syn::Item::Fn(item) => Some(&item.sig.ident),
+            syn::Item::Macro(item) => item.ident.as_ref(),
+            syn::Item::Mod(item) => Some(&item.ident),
+            syn::Item::Static(item) => Some(&item.ident),
+            syn::Item::Struct(item) => Some(&item.ident),
+            syn::Item::Trait(item) => Some(&item.ident),
+            syn::Item::Type(item) => Some(&item.ident),
+            syn::Item::Union(item) => Some(&item.ident),
+            _ => None,
+        };
+        if let Some(ident) = ident {
+            self.names.insert(ident.to_string());
+        }
+
+        match item {
+            syn::Item::Enum(item) => self.add_members(
+                &item.ident,
+                item.variants
+                    .iter()
+                    .map(|variant| variant.ident.to_string()),
+            ),
+            syn::Item::Struct(item) => self.add_members(&item.ident, field_names(&item.fields)),
+            syn::Item::Union(item) => self.add_members(
+                &item.ident,
+                item.fields
+                    .named
+                    .iter()
+                    .filter_map(|field| field.ident.as_ref().map(ToString::to_string)),
+            ),
+            syn::Item::Trait(item) => {
+                let members = item.items.iter().filter_map(|item| match item {
+                    syn::TraitItem::Const(item) => Some(item.ident.to_string()),
+                    syn::TraitItem::Fn(item) => Some(item.sig.ident.to_string()),
+                    syn::TraitItem::Type(item) => Some(item.ident.to_string()),
+                    _ => None,
+                });
+                self.add_members(&item.ident, members)
+            }
+            syn::Item::Impl(item) => {
+                if let Some(owner) = impl_owner(item) {
+                    let members = item.items.iter().filter_map(|item| match item {
+                        syn::ImplItem::Const(item) => Some(item.ident.to_string()),
+                        syn::ImplItem::Fn(item) => Some(item.sig.ident.to_string()),
+                        syn::ImplItem::Type(item) => Some(item.ident.to_string()),
+                        _ => None,
+                    });
+                    self.add_members(owner, members);
+                    if let Some((_, path, _)) = &item.trait_ {
+                        if let Some(segment) = path.segments.last() {
+                            self.traits
+                                .entry(owner.to_string())
+                                .or_default()
+                                .insert(segment.ident.to_string());
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        visit::visit_item(self, item);
+    }
+
+    fn visit_impl_item(&mut self, item: &'ast syn::ImplItem) {
+        match item {
+            syn::ImplItem::Const(item) => self.names.insert(item.ident.to_string()),
+            syn::ImplItem::Fn(item) => self.names.insert(item.sig.ident.to_string()),
+            syn::ImplItem::Type(item) => self.names.insert(item.ident.to_string()),
+            _ => false,
+        };
+
+        visit::visit_impl_item(self, item);
+    }
+
+    fn visit_trait_item(&mut self, item: &'ast syn::TraitItem) {
+        match item {
+            syn::TraitItem::Const(item) => self.names.insert(item.ident.to_string()),
+            syn::TraitItem::Fn(item) => self.names.insert(item.sig.ident.to_string()),
+            syn::TraitItem::Type(item) => self.names.insert(item.ident.to_string()),
+            _ => false,
+        };
+
+        visit::visit_trait_item(self, item);
+    }
+
+    fn visit_variant(&mut self, variant: &'ast syn::Variant) {
+        self.names.insert(variant.ident.to_string());
+        visit::visit_variant(self, variant);
+    }
+
+    fn visit_field(&mut self, field: &'ast syn::Field) {
+        if let Some(ident) = &field.ident {
+            self.names.insert(ident.to_string());
+        }
+        visit::visit_field(self, field);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, content: &str) -> RepoNode {
+        RepoNode::File {
+            name: path.rsplit('/').next().unwrap().to_string(),
+            path: path.to_string(),
+            sha: "abc".to_string(),
+            content: content.to_string(),
+            token_count: 0,
+            summary: None,
+        }
+    }
+
+    #[test]
+    fn test_crate_symbols_collects_items() {
+        let root = RepoNode::Directory {
+            name: "".to_string(),
+            path: "".to_string(),
+            token_count: 0,
+            children: vec![
+                file("Cargo.toml", "[package]\nname = \"my-crate\"\n"),
+                file(
+                    "src/config/mod.rs",
+                    "pub enum Mode { Fast }\npub struct Config { pub mode: Mode }\nimpl Config { pub fn load() {} }\npub trait Save { fn save(&self) {} }\nimpl Save for Mode {}",
+                ),
+            ],
+            summary: None,
+        };
+
+        let symbols = crate_symbols(&root);
+
+        assert_eq!(symbols.crate_name.as_deref(), Some("my_crate"));
+        assert_eq!(symbols.package_name.as_deref(), Some("my-crate"));
+        for name in ["config", "Mode", "Fast", "Config", "mode", "load"] {
+            assert!(symbols.contains(name), "missing {}", name);
+        }
+        assert!(symbols.resolves(&["config".to_string(), "Config".to_string()]));
+        assert!(symbols.resolves(&["Config".to_string(), "load".to_string()]));
+        assert!(symbols.resolves(&["Mode".to_string(), "Fast".to_string()]));
+        // Through the trait `Mode` implements
+        assert!(symbols.resolves(&["Mode".to_string(), "save".to_string()]));
+        // `save` and `Fast` exist, just not under `Config`
+        assert!(!symbols.resolves(&["Config".to_string(), "save".to_string()]));
+        assert!(!symbols.resolves(&["Config".to_string(), "Fast".to_string()]));
+        assert!(!symbols.resolves(&["Config".to_string(), "missing_method".to_string()]));
+    }
+}
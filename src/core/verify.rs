@@ -0,0 +1,129 @@
+use proc_macro2::{Delimiter, Group, TokenStream, TokenTree};
+use std::str::FromStr;
+
+/// Checks that splicing docs into a Rust file only added docs.
+///
+/// The modified file has to still parse (if the original did), and its
+/// token stream with every `///`, `//!` and `#[doc = ...]` taken out has
+/// to match the original's. Anything else, like a stray `*/` or code the
+/// model pasted into a comment block, is rejected with the reason why.
+pub fn verify_edit(original: &str, modified: &str) -> Result<(), String> {
+    if syn::parse_file(original).is_ok() {
+        if let Err(e) = syn::parse_file(modified) {
+            let start = e.span().start();
+            return Err(format!(
+                "no longer parses at line {}, column {}: {}",
+                start.line, start.column, e
+            ));
+        }
+    }
+
+    let modified_tokens =
+        TokenStream::from_str(modified).map_err(|e| format!("no longer tokenizes: {}", e))?;
+    let Ok(original_tokens) = TokenStream::from_str(original) else {
+        // Nothing to compare against
+        return Ok(());
+    };
+
+    if strip_docs(original_tokens).to_string() != strip_docs(modified_tokens).to_string() {
+        return Err("changed code outside of doc comments".to_string());
+    }
+
+    Ok(())
+}
+
+/// Removes doc attributes, which is what doc comments lex into
+fn strip_docs(stream: TokenStream) -> TokenStream {
+    let tokens: Vec<TokenTree> = stream.into_iter().collect();
+    let mut stripped = Vec::with_capacity(tokens.len());
+    let mut i = 0;
+
+    while i < tokens.len() {
+        // `#[doc = ...]`, or `#![doc = ...]` for inner docs
+        if is_punct(&tokens[i], '#') {
+            let bracket = if tokens.get(i + 1).is_some_and(|tt| is_punct(tt, '!')) {
+                i + 2
+            } else {
+                i + 1
+            };
+            if tokens.get(bracket).is_some_and(is_doc_attribute) {
+                i = bracket + 1;
+                continue;
+            }
+        }
+
+        stripped.push(match &tokens[i] {
+            TokenTree::Group(group) => {
+                TokenTree::Group(Group::new(group.delimiter(), strip_docs(group.stream())))
+            }
+            tt => tt.clone(),
+        });
+        i += 1;
+    }
+
+    stripped.into_iter().collect()
+}
+
+fn is_punct(tt: &TokenTree, ch: char) -> bool {
+    matches!(tt, TokenTree::Punct(punct) if punct.as_char() == ch)
+}
+
+/// Whether `tt` is the `[doc = ...]` part of a doc attribute. Other
+/// forms, like `[doc(hidden)]`, are code and must not change.
+fn is_doc_attribute(tt: &TokenTree) -> bool {
+    let TokenTree::Group(group) = tt else {
+        return false;
+    };
+    if group.delimiter() != Delimiter::Bracket {
+        return false;
+    }
+
+    let mut inner = group.stream().into_iter();
+    matches!(inner.next(), Some(TokenTree::Ident(ident)) if ident == "doc")
+        && inner.next().is_some_and(|tt| is_punct(&tt, '='))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORIGINAL: &str = "\
+#[doc(hidden)]
+pub fn run(x: u32) -> u32 {
+    x + 1
+}
+";
+
+    #[test]
+    fn test_verify_edit_accepts_doc_only_changes() {
+        let modified = "\
+//! Module docs
+#![doc = \"more\"]
+
+#[doc(hidden)]
+/// Adds one.
+pub fn run(x: u32) -> u32 {
+    x + 1
+}
+";
+        assert_eq!(verify_edit(ORIGINAL, modified), Ok(()));
+    }
+
+    #[test]
+    fn test_verify_edit_rejects_broken_or_changed_code() {
+        let unparsable = format!("/// Adds one. */\n{}", ORIGINAL.replace("x + 1", "x +"));
+        assert!(verify_edit(ORIGINAL, &unparsable)
+            .unwrap_err()
+            .starts_with("no longer parses"));
+
+        let changed = ORIGINAL.replace("#[doc(hidden)]", "/// Adds one.\npub fn helper() {}");
+        assert_eq!(
+            verify_edit(ORIGINAL, &changed),
+            Err("changed code outside of doc comments".to_string())
+        );
+
+        // Unterminated block comment
+        let unterminated = format!("/* Adds one.\n{}", ORIGINAL);
+        assert!(verify_edit(ORIGINAL, &unterminated).is_err());
+    }
+}
@@ -1,27 +1,95 @@
-use super::data::{RepoContent, RepoNode};
-use crate::utils::errors::DredgerError;
-use crate::utils::tokens::{count_tokens, TokenizerError};
+use super::data::{
+    Account, CheckAnnotation, DredgerBranch, FileChange, PublishedPr, PullRequest, PullRequestFile,
+    RepoContent, RepoNode, Repository, ReviewComment,
+};
+use crate::utils::config::{PullRequestConfig, RepoConfig};
+use crate::utils::errors::{github_error, DredgerError};
+use crate::utils::tokens::{count_tokens, load_tokenizer};
 use base64::prelude::*;
 use base64::Engine;
 use futures::future::BoxFuture;
 use reqwest::Client;
 use serde_json::json;
+use std::collections::HashMap;
 use std::env;
 use std::error::Error;
-use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 use tokenizers::Tokenizer;
 
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+/// Every branch Dredger pushes starts with this prefix
+pub const DREDGER_BRANCH_PREFIX: &str = "dredger/";
+
+/// Label that marks a pull request as Dredger's, whatever its branch
+pub const DREDGER_LABEL: &str = "dredger";
+
+/// Name of the commit status / check run carrying the doc coverage
+pub const COVERAGE_CONTEXT: &str = "dredger/doc-coverage";
+
+/// The checks API takes at most this many annotations per request
+const MAX_CHECK_ANNOTATIONS: usize = 50;
+
+/// Name of the branch a run pushes its docs to, e.g. "dredger/docs-1a2b3c4-9f8e7d6c".
+///
+/// Built from the base commit and a hash of the changes, so the same run
+/// against the same commit always gets the same name, while any other
+/// run gets a fresh one. A branch of that name left over from an earlier
+/// run is reset, see `create_or_reset_branch`.
+pub fn run_branch_name(base_sha: &str, changes: &[FileChange]) -> String {
+    // FNV-1a, which unlike std's hasher is stable across Rust versions
+    let mut hash: u32 = 0x811c9dc5;
+    for change in changes {
+        for byte in change.path.bytes().chain([0]).chain(change.content.bytes()) {
+            hash ^= byte as u32;
+            hash = hash.wrapping_mul(0x01000193);
+        }
+    }
+
+    format!(
+        "{}docs-{}-{:08x}",
+        DREDGER_BRANCH_PREFIX,
+        &base_sha[..base_sha.len().min(7)],
+        hash
+    )
+}
+
+/// Base URL of the GitHub REST API
+///
+/// In the test environment this points at the mockito server instead,
+/// so requests can be stubbed out.
+pub fn github_api_url() -> String {
+    let current_env = env::var("ENV").unwrap_or_else(|_| "production".to_string());
+
+    if current_env == "test" {
+        mockito::server_url()
+    } else {
+        "https://api.github.com".to_string()
+    }
+}
+
+/// Fetches and decodes a file's content, either from the default branch
+/// or, when `git_ref` is given, from that branch/commit.
 async fn fetch_file_content(
     client: &Client,
     repo_owner: &str,
     repo_name: &str,
     file_path: &str,
+    git_ref: Option<&str>,
     github_token: &str,
 ) -> Result<String, Box<dyn Error>> {
-    let url = format!(
-        "https://api.github.com/repos/{}/{}/contents/{}",
-        repo_owner, repo_name, file_path
+    let mut url = format!(
+        "{}/repos/{}/{}/contents/{}",
+        github_api_url(),
+        repo_owner,
+        repo_name,
+        file_path
     );
+    if let Some(git_ref) = git_ref {
+        url.push_str(&format!("?ref={}", git_ref));
+    }
 
     let response = client
         .get(&url)
@@ -47,15 +115,22 @@ fn read_repo_recursive(
     client: Client,
     repo_owner: String,
     repo_name: String,
-    tokenizer_path: String,
+    tokenizer: Arc<Tokenizer>,
     path: String,
     github_token: String,
+    git_ref: Option<String>,
 ) -> BoxFuture<'static, Result<RepoNode, Box<DredgerError>>> {
     Box::pin(async move {
-        let url = format!(
-            "https://api.github.com/repos/{}/{}/contents/{}",
-            repo_owner, repo_name, path
+        let mut url = format!(
+            "{}/repos/{}/{}/contents/{}",
+            github_api_url(),
+            repo_owner,
+            repo_name,
+            path
         );
+        if let Some(git_ref) = &git_ref {
+            url.push_str(&format!("?ref={}", git_ref));
+        }
 
         let response = client
             .get(&url)
@@ -80,43 +155,32 @@ fn read_repo_recursive(
                         &repo_owner,
                         &repo_name,
                         &file.path,
+                        git_ref.as_deref(),
                         &github_token,
                     )
                     .await
                     .unwrap_or_else(|_| "Failed to fetch content".to_string());
 
-                    // let tokenizer_path = "tokenizers/llama.json";
-                    //
-                    let copy_of_tokenizer_path = tokenizer_path.clone();
-
-                    if !Path::new(&copy_of_tokenizer_path).exists() {
-                        return Err(Box::new(DredgerError::TokenizerError(
-                            TokenizerError::FileNotFound(tokenizer_path.to_string()),
-                        )));
-                    }
-
-                    let tokenizer = Tokenizer::from_file(copy_of_tokenizer_path).map_err(|e| {
-                        Box::new(DredgerError::TokenizerError(TokenizerError::LoadError(
-                            e.to_string(),
-                        )))
-                    })?;
-
-                    let token_count = count_tokens(&content, &tokenizer).unwrap();
+                    let token_count = count_tokens(&content, &tokenizer)
+                        .map_err(|e| Box::new(DredgerError::TokenizerError(e)))?;
 
                     children.push(RepoNode::File {
                         name: file.name,
                         path: file.path,
+                        sha: file.sha,
                         content,
                         token_count,
+                        summary: None,
                     });
                 } else if file.r#type == "dir" {
                     let subdir_node = read_repo_recursive(
                         client.clone(),
                         repo_owner.clone(),
                         repo_name.clone(),
-                        tokenizer_path.clone(),
+                        tokenizer.clone(),
                         file.path.clone(),
                         github_token.clone(),
+                        git_ref.clone(),
                     )
                     .await?;
 
@@ -135,6 +199,7 @@ fn read_repo_recursive(
                 path,
                 children,
                 token_count: total_token_count,
+                summary: None,
             })
         } else {
             eprintln!("Failed to fetch repository contents: {}", response.status());
@@ -156,11 +221,13 @@ fn read_repo_recursive(
 /// we're also calculating the # of language model tokens in this
 /// GitHub client, in the read_repo / read_repo_recursive functions.
 ///
-// TODO: Add branch name?
+/// The repo is read at `git_ref` (a branch, tag or commit SHA) when
+/// given, and at its default branch otherwise.
 pub async fn read_repo(
     repo_owner: String,
     repo_name: String,
     tokenizer_path: String,
+    git_ref: Option<String>,
 ) -> Result<RepoNode, Box<DredgerError>> {
     let client = Client::new();
 
@@ -168,37 +235,100 @@ pub async fn read_repo(
     // so we don't check again here--we just load the token
     let github_token =
         std::env::var("GITHUB_PAT").map_err(|e| Box::new(DredgerError::VarError(e)))?;
+    let tokenizer =
+        load_tokenizer(&tokenizer_path).map_err(|e| Box::new(DredgerError::TokenizerError(e)))?;
 
     let root_node = read_repo_recursive(
         client,
         repo_owner,
         repo_name,
-        tokenizer_path,
+        Arc::new(tokenizer),
         "".to_string(), // Indicates root, start of recursion
         github_token,
+        git_ref,
     )
     .await?;
 
     Ok(root_node)
 }
 
-pub async fn validate_token() -> Result<(), DredgerError> {
+/// Reads the files changed by pull request `number`, as they are on
+/// the PR's head commit, into a flat `RepoNode` directory.
+///
+/// Removed files are left out. Returns the PR alongside the node, plus
+/// the changed files with their patches so callers know which lines
+/// the PR touches.
+pub async fn read_pull_request(
+    repo_owner: &str,
+    repo_name: &str,
+    number: u64,
+    tokenizer_path: &str,
+) -> Result<(PullRequest, Vec<PullRequestFile>, RepoNode), Box<DredgerError>> {
     let client = Client::new();
+    let github_token =
+        std::env::var("GITHUB_PAT").map_err(|e| Box::new(DredgerError::VarError(e)))?;
 
-    // Get the GitHub token from the environment variable
-    let token = env::var("GITHUB_PAT").map_err(|e| DredgerError::VarError(e))?;
+    let pr = get_pull_request(&client, repo_owner, repo_name, number, &github_token)
+        .await
+        .map_err(github_error)?;
+    let pr_files = list_pull_request_files(&client, repo_owner, repo_name, number, &github_token)
+        .await
+        .map_err(github_error)?;
 
-    // Determine the environment (default to production)
-    let current_env = env::var("ENV").unwrap_or_else(|_| "production".to_string());
+    let tokenizer =
+        load_tokenizer(tokenizer_path).map_err(|e| Box::new(DredgerError::TokenizerError(e)))?;
 
-    // Choose the correct URL based on the environment
-    let url = if current_env == "test" {
-        // Use mockito's server URL and append "/user"
-        format!("{}/user", mockito::server_url())
-    } else {
-        "https://api.github.com/user".to_string()
+    let mut children = Vec::new();
+    for pr_file in pr_files.iter().filter(|file| file.status != "removed") {
+        let content = fetch_file_content(
+            &client,
+            repo_owner,
+            repo_name,
+            &pr_file.filename,
+            Some(&pr.head.sha),
+            &github_token,
+        )
+        .await
+        .map_err(github_error)?;
+
+        let token_count = count_tokens(&content, &tokenizer)
+            .map_err(|e| Box::new(DredgerError::TokenizerError(e)))?;
+
+        children.push(RepoNode::File {
+            name: pr_file
+                .filename
+                .rsplit('/')
+                .next()
+                .unwrap_or(&pr_file.filename)
+                .to_string(),
+            path: pr_file.filename.clone(),
+            sha: String::new(), // Not needed, the review anchors to the head commit
+            content,
+            token_count,
+            summary: None,
+        });
+    }
+
+    let token_count = children.iter().map(|child| child.token_count()).sum();
+    let root_node = RepoNode::Directory {
+        name: String::new(),
+        path: String::new(),
+        children,
+        token_count,
+        summary: None,
     };
 
+    Ok((pr, pr_files, root_node))
+}
+
+pub async fn validate_token() -> Result<(), DredgerError> {
+    let client = Client::new();
+
+    // Get the GitHub token from the environment variable
+    let token = env::var("GITHUB_PAT").map_err(DredgerError::VarError)?;
+
+    let url = format!("{}/user", github_api_url());
+
     // Make the GET request with the necessary headers
     let res = client
         .get(&url)
@@ -228,6 +358,26 @@ pub async fn validate_token() -> Result<(), DredgerError> {
     }
 }
 
+/// Turns dry-run mode on or off for every request `make_request` sends.
+///
+/// While it's on, only GET requests go out; anything that would write
+/// to GitHub fails before it reaches the network.
+pub fn set_dry_run(dry_run: bool) {
+    DRY_RUN.store(dry_run, Ordering::SeqCst);
+}
+
+pub fn is_dry_run() -> bool {
+    DRY_RUN.load(Ordering::SeqCst)
+}
+
+fn guard_dry_run(dry_run: bool, method: &reqwest::Method, url: &str) -> Result<(), Box<dyn Error>> {
+    if dry_run && method != reqwest::Method::GET {
+        return Err(format!("Dry run: refusing to send {} {}", method, url).into());
+    }
+
+    Ok(())
+}
+
 pub async fn make_request<T>(
     client: &Client,
     url: &str,
@@ -238,6 +388,8 @@ pub async fn make_request<T>(
 where
     T: serde::de::DeserializeOwned,
 {
+    guard_dry_run(is_dry_run(), &method, url)?;
+
     let mut request = client
         .request(method, url)
         .header("Authorization", format!("Bearer {}", token))
@@ -256,14 +408,17 @@ where
     if !status.is_success() {
         // Use the captured error_text for error handling
         eprintln!("Request failed: {}: {}", status, error_text);
-        return Err(Box::new(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            "Request failed",
-        )));
+        return Err(Box::new(std::io::Error::other("Request failed")));
     }
 
-    // Parse the successful response into the expected result type
-    let response_json: T = serde_json::from_str(&error_text)?;
+    // Parse the successful response into the expected result type. Some
+    // endpoints (e.g. DELETE) reply with no content at all.
+    let response_text = if error_text.is_empty() {
+        "null"
+    } else {
+        error_text
+    };
+    let response_json: T = serde_json::from_str(response_text)?;
     Ok(response_json)
 }
 
@@ -275,7 +430,7 @@ pub async fn create_branch(
     new_branch: &str,
     token: &str,
 ) -> Result<(), Box<dyn Error>> {
-    let create_ref_url = format!("https://api.github.com/repos/{}/{}/git/refs", owner, repo);
+    let create_ref_url = format!("{}/repos/{}/{}/git/refs", github_api_url(), owner, repo);
     let new_ref_body = json!({
         "ref": format!("refs/heads/{}", new_branch),
         "sha": base_sha,
@@ -292,38 +447,257 @@ pub async fn create_branch(
     Ok(())
 }
 
-async fn add_file_to_repo(
+/// Points `branch` at `base_sha`, creating it if needed. A branch left
+/// over from an earlier run with the same name (say its PR was closed
+/// but the branch kept) is force-reset instead.
+pub async fn create_or_reset_branch(
     client: &Client,
     owner: &str,
     repo: &str,
-    file_path: &str,
-    file_content: &str,
-    new_branch: &str,
+    base_sha: &str,
+    branch: &str,
     token: &str,
 ) -> Result<(), Box<dyn Error>> {
-    let create_file_url = format!(
-        "https://api.github.com/repos/{}/{}/contents/{}",
-        owner, repo, file_path
+    if get_branch_sha(client, owner, repo, branch, token)
+        .await
+        .is_ok()
+    {
+        return update_ref(client, owner, repo, branch, base_sha, true, token).await;
+    }
+
+    create_branch(client, owner, repo, base_sha, branch, token).await
+}
+
+/// Returns the commit SHA that `branch` currently points at
+pub async fn get_branch_sha(
+    client: &Client,
+    owner: &str,
+    repo: &str,
+    branch: &str,
+    token: &str,
+) -> Result<String, Box<dyn Error>> {
+    let ref_url = format!(
+        "{}/repos/{}/{}/git/ref/heads/{}",
+        github_api_url(),
+        owner,
+        repo,
+        branch
     );
-    let encoded_content = base64::engine::general_purpose::STANDARD.encode(file_content);
+    let ref_resp: serde_json::Value =
+        make_request(client, &ref_url, reqwest::Method::GET, None, token).await?;
+
+    Ok(ref_resp["object"]["sha"]
+        .as_str()
+        .ok_or("Could not find branch SHA")?
+        .to_string())
+}
 
-    let create_file_body = json!({
-        "message": format!("Add {}", file_path),
-        "content": encoded_content,
-        "branch": new_branch
+/// Points `branch` at `sha`. With `force` set the update doesn't have
+/// to be a fast-forward, which is how an existing branch gets rewritten.
+pub async fn update_ref(
+    client: &Client,
+    owner: &str,
+    repo: &str,
+    branch: &str,
+    sha: &str,
+    force: bool,
+    token: &str,
+) -> Result<(), Box<dyn Error>> {
+    let ref_url = format!(
+        "{}/repos/{}/{}/git/refs/heads/{}",
+        github_api_url(),
+        owner,
+        repo,
+        branch
+    );
+    let body = json!({
+        "sha": sha,
+        "force": force,
     });
 
-    let _: serde_json::Value = make_request(
+    let _: serde_json::Value =
+        make_request(client, &ref_url, reqwest::Method::PATCH, Some(body), token).await?;
+    Ok(())
+}
+
+async fn create_blob(
+    client: &Client,
+    owner: &str,
+    repo: &str,
+    content: &str,
+    token: &str,
+) -> Result<String, Box<dyn Error>> {
+    let blob_url = format!("{}/repos/{}/{}/git/blobs", github_api_url(), owner, repo);
+    let body = json!({
+        "content": content,
+        "encoding": "utf-8",
+    });
+
+    let blob_resp: serde_json::Value =
+        make_request(client, &blob_url, reqwest::Method::POST, Some(body), token).await?;
+
+    Ok(blob_resp["sha"]
+        .as_str()
+        .ok_or("Blob SHA not found")?
+        .to_string())
+}
+
+/// Writes every file in `changes` to `branch` as a single commit, using
+/// the Git Data API (blobs -> tree -> commit -> update ref).
+///
+/// Files whose new blob matches their `base_sha` are left out of the
+/// tree. Returns the SHA of the new commit, or the current head of
+/// `branch` if nothing actually changed.
+pub async fn commit_files(
+    client: &Client,
+    owner: &str,
+    repo: &str,
+    branch: &str,
+    changes: &[FileChange],
+    message: &str,
+    token: &str,
+) -> Result<String, Box<dyn Error>> {
+    let parent_sha = get_branch_sha(client, owner, repo, branch, token).await?;
+
+    let commit_sha =
+        create_commit(client, owner, repo, &parent_sha, changes, message, token).await?;
+
+    if commit_sha != parent_sha {
+        update_ref(client, owner, repo, branch, &commit_sha, false, token).await?;
+    }
+
+    Ok(commit_sha)
+}
+
+/// Builds a commit holding `changes` on top of `parent_sha`, without
+/// moving any branch to it.
+///
+/// Returns `parent_sha` itself if none of the files actually changed.
+pub async fn create_commit(
+    client: &Client,
+    owner: &str,
+    repo: &str,
+    parent_sha: &str,
+    changes: &[FileChange],
+    message: &str,
+    token: &str,
+) -> Result<String, Box<dyn Error>> {
+    let parent_commit_url = format!(
+        "{}/repos/{}/{}/git/commits/{}",
+        github_api_url(),
+        owner,
+        repo,
+        parent_sha
+    );
+    let parent_commit: serde_json::Value = make_request(
         client,
-        &create_file_url,
-        reqwest::Method::PUT,
-        Some(create_file_body),
+        &parent_commit_url,
+        reqwest::Method::GET,
+        None,
         token,
     )
     .await?;
-    Ok(())
+    let base_tree_sha = parent_commit["tree"]["sha"]
+        .as_str()
+        .ok_or("Could not find base tree SHA")?;
+
+    // Existing files keep their mode, so executables stay executable
+    let base_modes = if changes.iter().any(|change| change.base_sha.is_some()) {
+        tree_modes(client, owner, repo, base_tree_sha, token).await?
+    } else {
+        HashMap::new()
+    };
+
+    let mut tree_entries = Vec::new();
+    for change in changes {
+        let blob_sha = create_blob(client, owner, repo, &change.content, token).await?;
+
+        if change.base_sha.as_deref() == Some(blob_sha.as_str()) {
+            continue; // Unchanged, keep the entry from the base tree
+        }
+
+        let mode = base_modes
+            .get(&change.path)
+            .map_or("100644", String::as_str);
+        tree_entries.push(json!({
+            "path": change.path,
+            "mode": mode,
+            "type": "blob",
+            "sha": blob_sha,
+        }));
+    }
+
+    if tree_entries.is_empty() {
+        return Ok(parent_sha.to_string());
+    }
+
+    let tree_url = format!("{}/repos/{}/{}/git/trees", github_api_url(), owner, repo);
+    let tree_body = json!({
+        "base_tree": base_tree_sha,
+        "tree": tree_entries,
+    });
+    let tree_resp: serde_json::Value = make_request(
+        client,
+        &tree_url,
+        reqwest::Method::POST,
+        Some(tree_body),
+        token,
+    )
+    .await?;
+    let tree_sha = tree_resp["sha"].as_str().ok_or("Tree SHA not found")?;
+
+    let commit_url = format!("{}/repos/{}/{}/git/commits", github_api_url(), owner, repo);
+    let commit_body = json!({
+        "message": message,
+        "tree": tree_sha,
+        "parents": [parent_sha],
+    });
+    let commit_resp: serde_json::Value = make_request(
+        client,
+        &commit_url,
+        reqwest::Method::POST,
+        Some(commit_body),
+        token,
+    )
+    .await?;
+    Ok(commit_resp["sha"]
+        .as_str()
+        .ok_or("Commit SHA not found")?
+        .to_string())
+}
+
+/// The mode of every blob in the tree `tree_sha`, by path
+async fn tree_modes(
+    client: &Client,
+    owner: &str,
+    repo: &str,
+    tree_sha: &str,
+    token: &str,
+) -> Result<HashMap<String, String>, Box<dyn Error>> {
+    let tree_url = format!(
+        "{}/repos/{}/{}/git/trees/{}?recursive=1",
+        github_api_url(),
+        owner,
+        repo,
+        tree_sha
+    );
+    let tree: serde_json::Value =
+        make_request(client, &tree_url, reqwest::Method::GET, None, token).await?;
+
+    Ok(tree["tree"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            Some((
+                entry["path"].as_str()?.to_string(),
+                entry["mode"].as_str()?.to_string(),
+            ))
+        })
+        .collect())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn create_pull_request(
     client: &Client,
     owner: &str,
@@ -332,81 +706,1158 @@ pub async fn create_pull_request(
     new_branch: &str,
     title: &str,
     body: &str,
+    draft: bool,
     token: &str,
-) -> Result<String, Box<dyn Error>> {
-    let create_pr_url = format!("https://api.github.com/repos/{}/{}/pulls", owner, repo);
+) -> Result<PullRequest, Box<dyn Error>> {
+    let create_pr_url = format!("{}/repos/{}/{}/pulls", github_api_url(), owner, repo);
     let create_pr_body = json!({
         "title": title,
         "head": new_branch,
         "base": base_branch,
-        "body": body
+        "body": body,
+        "draft": draft
     });
 
-    let pr_response_json: serde_json::Value = make_request(
+    make_request(
         client,
         &create_pr_url,
         reqwest::Method::POST,
         Some(create_pr_body),
         token,
     )
-    .await?;
-    let pr_url = pr_response_json["html_url"]
-        .as_str()
-        .ok_or("PR URL not found")?
-        .to_string();
-
-    Ok(pr_url)
+    .await
 }
 
-pub async fn open_test_pr() -> Result<(), Box<dyn Error>> {
-    let token = env::var("GITHUB_PAT").map_err(|_| "Missing GITHUB_PAT environment variable")?;
-    let client = Client::new();
-
-    let owner = "nickagliano";
-    let repo = "tbg-rust";
-    let base_branch = "master";
-    let new_branch = "hello-world-test-1";
-
-    // Get the SHA of the base branch
-    let base_ref_url = format!(
-        "https://api.github.com/repos/{}/{}/git/ref/heads/{}",
-        owner, repo, base_branch
+/// Looks up the number of the open milestone with the given title
+async fn find_milestone(
+    client: &Client,
+    owner: &str,
+    repo: &str,
+    title: &str,
+    token: &str,
+) -> Result<u64, Box<dyn Error>> {
+    let milestones_url = format!(
+        "{}/repos/{}/{}/milestones?state=open&per_page=100",
+        github_api_url(),
+        owner,
+        repo
     );
-    let base_ref_resp: serde_json::Value =
-        make_request(&client, &base_ref_url, reqwest::Method::GET, None, &token).await?;
-    let base_sha = base_ref_resp["object"]["sha"]
-        .as_str()
-        .ok_or("Could not find base SHA")?;
+    let milestones: Vec<serde_json::Value> =
+        make_request(client, &milestones_url, reqwest::Method::GET, None, token).await?;
+
+    milestones
+        .iter()
+        .find(|milestone| milestone["title"].as_str() == Some(title))
+        .and_then(|milestone| milestone["number"].as_u64())
+        .ok_or_else(|| format!("Milestone not found: {}", title).into())
+}
 
-    // 1. Create a new branch
-    create_branch(&client, owner, repo, base_sha, new_branch, &token).await?;
+/// The Dredger label followed by the configured ones, each once
+fn pr_labels(pr_config: &PullRequestConfig) -> Vec<&str> {
+    let mut labels = vec![DREDGER_LABEL];
+    for label in &pr_config.labels {
+        if !labels.contains(&label.as_str()) {
+            labels.push(label);
+        }
+    }
+    labels
+}
 
-    // 2. Add a file
-    add_file_to_repo(
-        &client,
+/// Applies the Dredger label (see `is_dredger_pr`) and the configured
+/// labels, assignees and milestone to a pull request.
+///
+/// Pull requests are issues as far as these fields go, so this goes
+/// through the issues API rather than the pulls API. The PR is already
+/// open by now, so this is best-effort: each failure is logged and
+/// returned, and the rest are still applied.
+pub async fn apply_pr_metadata(
+    client: &Client,
+    owner: &str,
+    repo: &str,
+    number: u64,
+    pr_config: &PullRequestConfig,
+    token: &str,
+) -> Vec<String> {
+    let issue_url = format!(
+        "{}/repos/{}/{}/issues/{}",
+        github_api_url(),
         owner,
         repo,
-        "hello.txt",
-        "hello world",
-        new_branch,
-        &token,
+        number
+    );
+    let mut failures = Vec::new();
+
+    let labels: Result<serde_json::Value, _> = make_request(
+        client,
+        &format!("{}/labels", issue_url),
+        reqwest::Method::POST,
+        Some(json!({ "labels": pr_labels(pr_config) })),
+        token,
     )
-    .await?;
+    .await;
+    if let Err(e) = labels {
+        failures.push(format!("Could not add labels: {}", e));
+    }
 
-    // 3. Open a pull request
-    let pr_url = create_pull_request(
-        &client,
-        owner,
-        repo,
-        base_branch,
-        new_branch,
-        "Test PR: Hello World",
-        "This PR adds a hello world file.",
-        &token,
+    if !pr_config.assignees.is_empty() {
+        let assignees: Result<serde_json::Value, _> = make_request(
+            client,
+            &format!("{}/assignees", issue_url),
+            reqwest::Method::POST,
+            Some(json!({ "assignees": pr_config.assignees })),
+            token,
+        )
+        .await;
+        if let Err(e) = assignees {
+            failures.push(format!("Could not add assignees: {}", e));
+        }
+    }
+
+    if let Some(milestone_title) = &pr_config.milestone {
+        let milestone = async {
+            let milestone = find_milestone(client, owner, repo, milestone_title, token).await?;
+            make_request::<serde_json::Value>(
+                client,
+                &issue_url,
+                reqwest::Method::PATCH,
+                Some(json!({ "milestone": milestone })),
+                token,
+            )
+            .await
+        }
+        .await;
+        if let Err(e) = milestone {
+            failures.push(format!("Could not set the milestone: {}", e));
+        }
+    }
+
+    for failure in &failures {
+        eprintln!("{} on pull request #{}", failure, number);
+    }
+    failures
+}
+
+pub async fn get_repository(
+    client: &Client,
+    owner: &str,
+    repo: &str,
+    token: &str,
+) -> Result<Repository, Box<dyn Error>> {
+    let repo_url = format!("{}/repos/{}/{}", github_api_url(), owner, repo);
+
+    make_request(client, &repo_url, reqwest::Method::GET, None, token).await
+}
+
+/// Returns the login of the account the token belongs to
+pub async fn get_authenticated_user(
+    client: &Client,
+    token: &str,
+) -> Result<String, Box<dyn Error>> {
+    let user_url = format!("{}/user", github_api_url());
+    let user: Account = make_request(client, &user_url, reqwest::Method::GET, None, token).await?;
+
+    Ok(user.login)
+}
+
+/// Forks `owner/repo` into the authenticated account, or into
+/// `organization` when given. Asking for a fork that already exists
+/// just returns the existing one.
+///
+/// GitHub creates forks asynchronously, so the returned fork may not be
+/// usable yet; see `wait_for_fork`.
+pub async fn create_fork(
+    client: &Client,
+    owner: &str,
+    repo: &str,
+    organization: Option<&str>,
+    token: &str,
+) -> Result<Repository, Box<dyn Error>> {
+    let forks_url = format!("{}/repos/{}/{}/forks", github_api_url(), owner, repo);
+    let fork_body = match organization {
+        Some(organization) => json!({ "organization": organization }),
+        None => json!({}),
+    };
+
+    make_request(
+        client,
+        &forks_url,
+        reqwest::Method::POST,
+        Some(fork_body),
+        token,
     )
-    .await?;
+    .await
+}
 
-    println!("Pull request created: {}", pr_url);
+/// Polls a freshly created fork until `branch` can be read from it
+pub async fn wait_for_fork(
+    client: &Client,
+    fork: &Repository,
+    branch: &str,
+    token: &str,
+) -> Result<(), Box<dyn Error>> {
+    const ATTEMPTS: u32 = 30;
 
-    Ok(())
+    for _ in 0..ATTEMPTS {
+        if get_branch_sha(client, &fork.owner.login, &fork.name, branch, token)
+            .await
+            .is_ok()
+        {
+            return Ok(());
+        }
+        tokio::time::sleep(Duration::from_secs(2)).await;
+    }
+
+    Err(format!(
+        "Fork {}/{} was not ready in time",
+        fork.owner.login, fork.name
+    )
+    .into())
+}
+
+pub async fn list_open_pull_requests(
+    client: &Client,
+    owner: &str,
+    repo: &str,
+    token: &str,
+) -> Result<Vec<PullRequest>, Box<dyn Error>> {
+    list_pull_requests(client, owner, repo, "open", token).await
+}
+
+/// Lists the most recently created pull requests in `state`: "open",
+/// "closed" or "all"
+pub async fn list_pull_requests(
+    client: &Client,
+    owner: &str,
+    repo: &str,
+    state: &str,
+    token: &str,
+) -> Result<Vec<PullRequest>, Box<dyn Error>> {
+    let pulls_url = format!(
+        "{}/repos/{}/{}/pulls?state={}&sort=created&direction=desc&per_page=100",
+        github_api_url(),
+        owner,
+        repo,
+        state
+    );
+
+    make_request(client, &pulls_url, reqwest::Method::GET, None, token).await
+}
+
+pub async fn close_pull_request(
+    client: &Client,
+    owner: &str,
+    repo: &str,
+    number: u64,
+    token: &str,
+) -> Result<(), Box<dyn Error>> {
+    let pr_url = format!(
+        "{}/repos/{}/{}/pulls/{}",
+        github_api_url(),
+        owner,
+        repo,
+        number
+    );
+
+    let _: serde_json::Value = make_request(
+        client,
+        &pr_url,
+        reqwest::Method::PATCH,
+        Some(json!({ "state": "closed" })),
+        token,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Lists every branch carrying the Dredger prefix, along with the date
+/// of the commit it points at
+pub async fn list_dredger_branches(
+    client: &Client,
+    owner: &str,
+    repo: &str,
+    token: &str,
+) -> Result<Vec<DredgerBranch>, Box<dyn Error>> {
+    let refs_url = format!(
+        "{}/repos/{}/{}/git/matching-refs/heads/{}",
+        github_api_url(),
+        owner,
+        repo,
+        DREDGER_BRANCH_PREFIX
+    );
+    let refs: Vec<serde_json::Value> =
+        make_request(client, &refs_url, reqwest::Method::GET, None, token).await?;
+
+    let mut branches = Vec::new();
+    for git_ref in refs {
+        let name = git_ref["ref"]
+            .as_str()
+            .and_then(|name| name.strip_prefix("refs/heads/"))
+            .ok_or("Branch name not found")?
+            .to_string();
+        let sha = git_ref["object"]["sha"]
+            .as_str()
+            .ok_or("Branch SHA not found")?
+            .to_string();
+
+        let commit_url = format!(
+            "{}/repos/{}/{}/git/commits/{}",
+            github_api_url(),
+            owner,
+            repo,
+            sha
+        );
+        let commit: serde_json::Value =
+            make_request(client, &commit_url, reqwest::Method::GET, None, token).await?;
+        let last_commit_at = commit["committer"]["date"]
+            .as_str()
+            .ok_or("Commit date not found")?
+            .parse()?;
+
+        branches.push(DredgerBranch {
+            name,
+            sha,
+            last_commit_at,
+        });
+    }
+
+    Ok(branches)
+}
+
+pub async fn delete_branch(
+    client: &Client,
+    owner: &str,
+    repo: &str,
+    branch: &str,
+    token: &str,
+) -> Result<(), Box<dyn Error>> {
+    let ref_url = format!(
+        "{}/repos/{}/{}/git/refs/heads/{}",
+        github_api_url(),
+        owner,
+        repo,
+        branch
+    );
+
+    let _: serde_json::Value =
+        make_request(client, &ref_url, reqwest::Method::DELETE, None, token).await?;
+    Ok(())
+}
+
+/// Finds the pull request Dredger opened on a previous run, if it's
+/// still open and its branch lives on `head_owner`'s repo.
+pub async fn find_dredger_pr(
+    client: &Client,
+    owner: &str,
+    repo: &str,
+    head_owner: &str,
+    token: &str,
+) -> Result<Option<PullRequest>, Box<dyn Error>> {
+    let pulls = list_open_pull_requests(client, owner, repo, token).await?;
+    let head_prefix = format!("{}:", head_owner);
+
+    Ok(pulls
+        .into_iter()
+        .find(|pr| pr.head.label.starts_with(&head_prefix) && is_dredger_pr(pr)))
+}
+
+/// Whether Dredger opened `pr`: its branch carries the Dredger prefix,
+/// or it has the Dredger label
+pub fn is_dredger_pr(pr: &PullRequest) -> bool {
+    pr.head.branch.starts_with(DREDGER_BRANCH_PREFIX)
+        || pr.labels.iter().any(|label| label.name == DREDGER_LABEL)
+}
+
+pub async fn get_pull_request(
+    client: &Client,
+    owner: &str,
+    repo: &str,
+    number: u64,
+    token: &str,
+) -> Result<PullRequest, Box<dyn Error>> {
+    let pr_url = format!(
+        "{}/repos/{}/{}/pulls/{}",
+        github_api_url(),
+        owner,
+        repo,
+        number
+    );
+
+    make_request(client, &pr_url, reqwest::Method::GET, None, token).await
+}
+
+pub async fn list_pull_request_files(
+    client: &Client,
+    owner: &str,
+    repo: &str,
+    number: u64,
+    token: &str,
+) -> Result<Vec<PullRequestFile>, Box<dyn Error>> {
+    let files_url = format!(
+        "{}/repos/{}/{}/pulls/{}/files?per_page=100",
+        github_api_url(),
+        owner,
+        repo,
+        number
+    );
+
+    make_request(client, &files_url, reqwest::Method::GET, None, token).await
+}
+
+pub async fn update_pull_request(
+    client: &Client,
+    owner: &str,
+    repo: &str,
+    number: u64,
+    title: &str,
+    body: &str,
+    token: &str,
+) -> Result<(), Box<dyn Error>> {
+    let pr_url = format!(
+        "{}/repos/{}/{}/pulls/{}",
+        github_api_url(),
+        owner,
+        repo,
+        number
+    );
+    let update_body = json!({
+        "title": title,
+        "body": body,
+    });
+
+    let _: serde_json::Value = make_request(
+        client,
+        &pr_url,
+        reqwest::Method::PATCH,
+        Some(update_body),
+        token,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Posts a review with inline comments on the given commit of a PR.
+///
+/// Returns the URL of the review.
+#[allow(clippy::too_many_arguments)]
+pub async fn create_review(
+    client: &Client,
+    owner: &str,
+    repo: &str,
+    number: u64,
+    commit_id: &str,
+    body: &str,
+    comments: &[ReviewComment],
+    token: &str,
+) -> Result<String, Box<dyn Error>> {
+    let reviews_url = format!(
+        "{}/repos/{}/{}/pulls/{}/reviews",
+        github_api_url(),
+        owner,
+        repo,
+        number
+    );
+    let review_body = json!({
+        "commit_id": commit_id,
+        "body": body,
+        "event": "COMMENT",
+        "comments": comments,
+    });
+
+    let review_resp: serde_json::Value = make_request(
+        client,
+        &reviews_url,
+        reqwest::Method::POST,
+        Some(review_body),
+        token,
+    )
+    .await?;
+
+    Ok(review_resp["html_url"]
+        .as_str()
+        .ok_or("Review URL not found")?
+        .to_string())
+}
+
+/// Comments on an issue or pull request (they share the issues API)
+pub async fn add_issue_comment(
+    client: &Client,
+    owner: &str,
+    repo: &str,
+    number: u64,
+    body: &str,
+    token: &str,
+) -> Result<(), Box<dyn Error>> {
+    let comments_url = format!(
+        "{}/repos/{}/{}/issues/{}/comments",
+        github_api_url(),
+        owner,
+        repo,
+        number
+    );
+
+    let _: serde_json::Value = make_request(
+        client,
+        &comments_url,
+        reqwest::Method::POST,
+        Some(json!({ "body": body })),
+        token,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Sets the doc coverage commit status on `sha`.
+///
+/// `state` is one of "success", "failure", "pending" or "error".
+pub async fn create_commit_status(
+    client: &Client,
+    owner: &str,
+    repo: &str,
+    sha: &str,
+    state: &str,
+    description: &str,
+    token: &str,
+) -> Result<(), Box<dyn Error>> {
+    let status_url = format!(
+        "{}/repos/{}/{}/statuses/{}",
+        github_api_url(),
+        owner,
+        repo,
+        sha
+    );
+    let status_body = json!({
+        "state": state,
+        // GitHub rejects descriptions over 140 characters
+        "description": description.chars().take(140).collect::<String>(),
+        "context": COVERAGE_CONTEXT,
+    });
+
+    let _: serde_json::Value = make_request(
+        client,
+        &status_url,
+        reqwest::Method::POST,
+        Some(status_body),
+        token,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Creates a completed doc coverage check run on `sha`.
+///
+/// `conclusion` is "success", "failure" or "neutral". Only the first
+/// `MAX_CHECK_ANNOTATIONS` annotations are sent; `summary` should say
+/// how many there were in total. Check runs need a GitHub App token, so
+/// callers should be ready to fall back to `create_commit_status`.
+///
+/// Returns the URL of the check run.
+#[allow(clippy::too_many_arguments)]
+pub async fn create_check_run(
+    client: &Client,
+    owner: &str,
+    repo: &str,
+    sha: &str,
+    conclusion: &str,
+    title: &str,
+    summary: &str,
+    annotations: &[CheckAnnotation],
+    token: &str,
+) -> Result<String, Box<dyn Error>> {
+    let check_runs_url = format!("{}/repos/{}/{}/check-runs", github_api_url(), owner, repo);
+    let annotations = &annotations[..annotations.len().min(MAX_CHECK_ANNOTATIONS)];
+    let check_run_body = json!({
+        "name": COVERAGE_CONTEXT,
+        "head_sha": sha,
+        "status": "completed",
+        "conclusion": conclusion,
+        "output": {
+            "title": title,
+            "summary": summary,
+            "annotations": annotations,
+        },
+    });
+
+    let check_run_resp: serde_json::Value = make_request(
+        client,
+        &check_runs_url,
+        reqwest::Method::POST,
+        Some(check_run_body),
+        token,
+    )
+    .await?;
+
+    Ok(check_run_resp["html_url"]
+        .as_str()
+        .ok_or("Check run URL not found")?
+        .to_string())
+}
+
+/// Publishes `changes` as a Dredger pull request against the repo's
+/// default branch.
+///
+/// If a Dredger PR is already open, its branch is force-updated with a
+/// fresh commit on top of the default branch, its title and body are
+/// replaced, and a comment summarizing what changed since the last run
+/// is added. Otherwise a new branch and PR are created.
+///
+/// When the token can't push to the repo, the branch is pushed to a
+/// fork instead (see `push_target`) and the PR is opened across repos.
+///
+/// Either way, the Dredger label and the configured labels, assignees
+/// and milestone are applied to the PR afterwards, except on PRs from a
+/// fork: a token that can't push upstream can't set those either.
+///
+/// Returns the pull request, with whatever metadata couldn't be applied.
+pub async fn publish_docs_pr(
+    owner: &str,
+    repo: &str,
+    changes: &[FileChange],
+    commit_message: &str,
+    title: &str,
+    body: &str,
+    repo_config: &RepoConfig,
+) -> Result<PublishedPr, Box<dyn Error>> {
+    let token = env::var("GITHUB_PAT").map_err(|_| "Missing GITHUB_PAT environment variable")?;
+    let client = Client::new();
+    let pr_config = &repo_config.pull_request;
+
+    let upstream = get_repository(&client, owner, repo, &token).await?;
+    let base_branch = upstream.default_branch.clone();
+
+    // Get the SHA of the base branch
+    let base_sha = get_branch_sha(&client, owner, repo, &base_branch, &token).await?;
+
+    // Where the branch gets pushed: the repo itself, or a fork of it
+    let target = push_target(&client, &upstream, repo_config, &token).await?;
+    let (push_owner, push_repo) = (target.owner.login.as_str(), target.name.as_str());
+    let from_fork = push_owner != upstream.owner.login;
+
+    if let Some(existing_pr) = find_dredger_pr(&client, owner, repo, push_owner, &token).await? {
+        let previous_files =
+            list_pull_request_files(&client, owner, repo, existing_pr.number, &token).await?;
+
+        // Rebuild the branch from scratch on the current base, so the PR
+        // only ever holds the latest run
+        let commit_sha = create_commit(
+            &client,
+            push_owner,
+            push_repo,
+            &base_sha,
+            changes,
+            commit_message,
+            &token,
+        )
+        .await?;
+        update_ref(
+            &client,
+            push_owner,
+            push_repo,
+            &existing_pr.head.branch,
+            &commit_sha,
+            true,
+            &token,
+        )
+        .await?;
+
+        update_pull_request(
+            &client,
+            owner,
+            repo,
+            existing_pr.number,
+            title,
+            body,
+            &token,
+        )
+        .await?;
+        add_issue_comment(
+            &client,
+            owner,
+            repo,
+            existing_pr.number,
+            &summarize_rerun(&previous_files, changes),
+            &token,
+        )
+        .await?;
+        let metadata_failures = match from_fork {
+            true => skipped_fork_metadata(pr_config),
+            false => {
+                apply_pr_metadata(&client, owner, repo, existing_pr.number, pr_config, &token).await
+            }
+        };
+
+        return Ok(PublishedPr {
+            url: existing_pr.html_url,
+            metadata_failures,
+        });
+    }
+
+    let new_branch = run_branch_name(&base_sha, changes);
+
+    // 1. Create a new branch. A fork shares its object store with the
+    //    upstream repo, so the upstream base SHA is valid there too.
+    create_or_reset_branch(
+        &client,
+        push_owner,
+        push_repo,
+        &base_sha,
+        &new_branch,
+        &token,
+    )
+    .await?;
+
+    // 2. Commit the files
+    commit_files(
+        &client,
+        push_owner,
+        push_repo,
+        &new_branch,
+        changes,
+        commit_message,
+        &token,
+    )
+    .await?;
+
+    // 3. Open a pull request. Cross-repo PRs name their head "owner:branch".
+    let head = if from_fork {
+        format!("{}:{}", push_owner, new_branch)
+    } else {
+        new_branch
+    };
+    let pr = create_pull_request(
+        &client,
+        owner,
+        repo,
+        &base_branch,
+        &head,
+        title,
+        body,
+        pr_config.draft,
+        &token,
+    )
+    .await?;
+
+    // 4. Labels, assignees and milestone can't be set on creation
+    let metadata_failures = match from_fork {
+        true => skipped_fork_metadata(pr_config),
+        false => apply_pr_metadata(&client, owner, repo, pr.number, pr_config, &token).await,
+    };
+
+    Ok(PublishedPr {
+        url: pr.html_url,
+        metadata_failures,
+    })
+}
+
+/// What `publish_docs_pr` reports instead of applying metadata to a PR
+/// opened from a fork
+fn skipped_fork_metadata(pr_config: &PullRequestConfig) -> Vec<String> {
+    let configured = !pr_config.labels.is_empty()
+        || !pr_config.assignees.is_empty()
+        || pr_config.milestone.is_some();
+    match configured {
+        true => vec![
+            "Skipped labels, assignees and milestone: the PR comes from a fork, and the token can't set them upstream".to_string(),
+        ],
+        false => Vec::new(),
+    }
+}
+
+/// Where a run's branch goes, decided from the upstream repo and the
+/// config alone. The real run (`push_target`) and a dry run's plan both
+/// go by it, so they can't disagree.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PushDecision {
+    /// The token can push to `upstream` itself
+    Upstream,
+    /// A fork, in `organization` or else the authenticated account
+    Fork { organization: Option<String> },
+}
+
+impl PushDecision {
+    /// Owner of the repo the branch goes to. For a fork in the
+    /// authenticated account, GitHub is asked whose token this is.
+    pub async fn owner(
+        &self,
+        client: &Client,
+        upstream: &Repository,
+        token: &str,
+    ) -> Result<String, Box<dyn Error>> {
+        match self {
+            PushDecision::Upstream => Ok(upstream.owner.login.clone()),
+            PushDecision::Fork {
+                organization: Some(organization),
+            } => Ok(organization.clone()),
+            PushDecision::Fork { organization: None } => {
+                get_authenticated_user(client, token).await
+            }
+        }
+    }
+}
+
+/// Pushes to `upstream` when the token has push access, and to a fork
+/// in the configured `fork_organization` otherwise
+pub fn push_decision(upstream: &Repository, repo_config: &RepoConfig) -> PushDecision {
+    let can_push = upstream
+        .permissions
+        .as_ref()
+        .is_some_and(|permissions| permissions.push);
+
+    match can_push {
+        true => PushDecision::Upstream,
+        false => PushDecision::Fork {
+            organization: repo_config.fork_organization.clone(),
+        },
+    }
+}
+
+/// Picks the repo Dredger pushes its branch to (see `push_decision`),
+/// forking `upstream` if need be and returning the fork once it's ready.
+pub async fn push_target(
+    client: &Client,
+    upstream: &Repository,
+    repo_config: &RepoConfig,
+    token: &str,
+) -> Result<Repository, Box<dyn Error>> {
+    let decision = push_decision(upstream, repo_config);
+    let PushDecision::Fork { organization } = &decision else {
+        return Ok(upstream.clone());
+    };
+
+    let fork = create_fork(
+        client,
+        &upstream.owner.login,
+        &upstream.name,
+        organization.as_deref(),
+        token,
+    )
+    .await?;
+
+    // Sanity check: the fork should land where we asked for it
+    let expected_owner = decision.owner(client, upstream, token).await?;
+    if !fork.owner.login.eq_ignore_ascii_case(&expected_owner) {
+        return Err(format!(
+            "Fork landed in {} instead of {}",
+            fork.owner.login, expected_owner
+        )
+        .into());
+    }
+
+    wait_for_fork(client, &fork, &fork.default_branch, token).await?;
+
+    Ok(fork)
+}
+
+/// The repo `push_target` would pick, without forking anything: `upstream`
+/// itself when the token can push to it, otherwise the fork, if one
+/// exists yet.
+pub async fn existing_push_target(
+    client: &Client,
+    upstream: &Repository,
+    repo_config: &RepoConfig,
+    token: &str,
+) -> Result<Option<Repository>, Box<dyn Error>> {
+    let decision = push_decision(upstream, repo_config);
+    if decision == PushDecision::Upstream {
+        return Ok(Some(upstream.clone()));
+    }
+
+    let fork_owner = decision.owner(client, upstream, token).await?;
+    // A missing repo fails like any other request, and either way there's
+    // no fork to look at
+    Ok(get_repository(client, &fork_owner, &upstream.name, token)
+        .await
+        .ok())
+}
+
+/// Markdown comment describing how a re-run differs from what the PR
+/// held before it was force-updated
+fn summarize_rerun(previous_files: &[PullRequestFile], changes: &[FileChange]) -> String {
+    let previous: Vec<&str> = previous_files
+        .iter()
+        .map(|file| file.filename.as_str())
+        .collect();
+    let current: Vec<&str> = changes.iter().map(|change| change.path.as_str()).collect();
+
+    let newly_documented: Vec<&str> = current
+        .iter()
+        .filter(|path| !previous.contains(path))
+        .copied()
+        .collect();
+    let regenerated: Vec<&str> = current
+        .iter()
+        .filter(|path| previous.contains(path))
+        .copied()
+        .collect();
+    let dropped: Vec<&str> = previous
+        .iter()
+        .filter(|path| !current.contains(path))
+        .copied()
+        .collect();
+
+    let mut summary = String::from("🔁 **Dredger re-ran and refreshed this PR.**\n");
+
+    for (heading, paths) in [
+        ("Newly documented", &newly_documented),
+        ("Regenerated", &regenerated),
+        ("No longer changed", &dropped),
+    ] {
+        if paths.is_empty() {
+            continue;
+        }
+        summary.push_str(&format!("\n**{}** ({})\n", heading, paths.len()));
+        for path in paths.iter() {
+            summary.push_str(&format!("- `{}`\n", path));
+        }
+    }
+
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::github_client::data::Permissions;
+    use mockito::{mock, Matcher};
+
+    #[tokio::test]
+    async fn test_commit_files_builds_single_commit() {
+        env::set_var("ENV", "test");
+
+        let _ref = mock("GET", "/repos/owner/commit-test/git/ref/heads/docs")
+            .with_status(200)
+            .with_body(r#"{"object": {"sha": "parent-sha"}}"#)
+            .create();
+        let _parent = mock("GET", "/repos/owner/commit-test/git/commits/parent-sha")
+            .with_status(200)
+            .with_body(r#"{"tree": {"sha": "base-tree-sha"}}"#)
+            .create();
+        let _base_tree = mock(
+            "GET",
+            "/repos/owner/commit-test/git/trees/base-tree-sha?recursive=1",
+        )
+        .with_status(200)
+        .with_body(r#"{"tree": [{"path": "bin/run.py", "mode": "100755", "type": "blob"}]}"#)
+        .create();
+        let _blob = mock("POST", "/repos/owner/commit-test/git/blobs")
+            .with_status(201)
+            .with_body(r#"{"sha": "new-blob-sha"}"#)
+            .expect(3)
+            .create();
+        let tree = mock("POST", "/repos/owner/commit-test/git/trees")
+            .match_body(Matcher::PartialJson(json!({
+                "base_tree": "base-tree-sha",
+                "tree": [
+                    {"path": "src/new.rs", "mode": "100644", "sha": "new-blob-sha"},
+                    {"path": "bin/run.py", "mode": "100755", "sha": "new-blob-sha"},
+                ],
+            })))
+            .with_status(201)
+            .with_body(r#"{"sha": "tree-sha"}"#)
+            .create();
+        let commit = mock("POST", "/repos/owner/commit-test/git/commits")
+            .match_body(Matcher::PartialJson(json!({
+                "tree": "tree-sha",
+                "parents": ["parent-sha"],
+            })))
+            .with_status(201)
+            .with_body(r#"{"sha": "commit-sha"}"#)
+            .create();
+        let update = mock("PATCH", "/repos/owner/commit-test/git/refs/heads/docs")
+            .match_body(Matcher::PartialJson(json!({"sha": "commit-sha"})))
+            .with_status(200)
+            .with_body("{}")
+            .create();
+
+        let changes = vec![
+            FileChange {
+                path: "src/new.rs".to_string(),
+                content: "//! New".to_string(),
+                base_sha: None,
+            },
+            FileChange {
+                path: "src/unchanged.rs".to_string(),
+                content: "//! Same".to_string(),
+                base_sha: Some("new-blob-sha".to_string()),
+            },
+            FileChange {
+                path: "bin/run.py".to_string(),
+                content: "#!/usr/bin/env python\n\"\"\"Runs it.\"\"\"\n".to_string(),
+                base_sha: Some("old-blob-sha".to_string()),
+            },
+        ];
+
+        let sha = commit_files(
+            &Client::new(),
+            "owner",
+            "commit-test",
+            "docs",
+            &changes,
+            "Add docs",
+            "test_token",
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(sha, "commit-sha");
+        tree.assert();
+        commit.assert();
+        update.assert();
+    }
+
+    #[test]
+    fn test_summarize_rerun_groups_files() {
+        let previous = vec![
+            PullRequestFile {
+                filename: "src/kept.rs".to_string(),
+                status: "modified".to_string(),
+                patch: None,
+            },
+            PullRequestFile {
+                filename: "src/dropped.rs".to_string(),
+                status: "modified".to_string(),
+                patch: None,
+            },
+        ];
+        let changes = vec![
+            FileChange {
+                path: "src/kept.rs".to_string(),
+                content: String::new(),
+                base_sha: None,
+            },
+            FileChange {
+                path: "src/new.rs".to_string(),
+                content: String::new(),
+                base_sha: None,
+            },
+        ];
+
+        let summary = summarize_rerun(&previous, &changes);
+
+        assert!(summary.contains("**Newly documented** (1)\n- `src/new.rs`"));
+        assert!(summary.contains("**Regenerated** (1)\n- `src/kept.rs`"));
+        assert!(summary.contains("**No longer changed** (1)\n- `src/dropped.rs`"));
+    }
+
+    #[tokio::test]
+    async fn test_create_or_reset_branch_resets_a_leftover_branch() {
+        env::set_var("ENV", "test");
+
+        let _existing = mock("GET", "/repos/owner/reset-test/git/ref/heads/dredger/docs")
+            .with_status(200)
+            .with_body(r#"{"object": {"sha": "old-sha"}}"#)
+            .create();
+        let create = mock("POST", "/repos/owner/reset-test/git/refs")
+            .expect(0)
+            .create();
+        let reset = mock(
+            "PATCH",
+            "/repos/owner/reset-test/git/refs/heads/dredger/docs",
+        )
+        .match_body(Matcher::PartialJson(
+            json!({"sha": "base-sha", "force": true}),
+        ))
+        .with_status(200)
+        .with_body("{}")
+        .create();
+
+        create_or_reset_branch(
+            &Client::new(),
+            "owner",
+            "reset-test",
+            "base-sha",
+            "dredger/docs",
+            "test_token",
+        )
+        .await
+        .unwrap();
+
+        reset.assert();
+        create.assert();
+    }
+
+    #[tokio::test]
+    async fn test_apply_pr_metadata_reports_failures_and_goes_on() {
+        env::set_var("ENV", "test");
+
+        let labels = mock("POST", "/repos/owner/metadata-test/issues/7/labels")
+            .with_status(200)
+            .with_body("[]")
+            .create();
+        let _milestones = mock(
+            "GET",
+            "/repos/owner/metadata-test/milestones?state=open&per_page=100",
+        )
+        .with_status(200)
+        .with_body("[]")
+        .create();
+
+        let pr_config = PullRequestConfig {
+            milestone: Some("v2".to_string()),
+            ..Default::default()
+        };
+        let failures = apply_pr_metadata(
+            &Client::new(),
+            "owner",
+            "metadata-test",
+            7,
+            &pr_config,
+            "test_token",
+        )
+        .await;
+
+        labels.assert();
+        assert_eq!(failures.len(), 1);
+        assert!(failures[0].contains("Milestone not found: v2"));
+    }
+
+    #[test]
+    fn test_pr_labels_start_with_the_dredger_label() {
+        let pr_config = PullRequestConfig {
+            labels: vec!["documentation".to_string(), DREDGER_LABEL.to_string()],
+            ..Default::default()
+        };
+        assert_eq!(pr_labels(&pr_config), vec![DREDGER_LABEL, "documentation"]);
+    }
+
+    #[test]
+    fn test_push_decision_forks_without_push_access() {
+        let upstream = |push| Repository {
+            name: "repo".to_string(),
+            owner: Account {
+                login: "owner".to_string(),
+            },
+            default_branch: "main".to_string(),
+            permissions: Some(Permissions { push }),
+        };
+        let repo_config = RepoConfig {
+            fork_organization: Some("bots".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            push_decision(&upstream(true), &repo_config),
+            PushDecision::Upstream
+        );
+        assert_eq!(
+            push_decision(&upstream(false), &repo_config),
+            PushDecision::Fork {
+                organization: Some("bots".to_string())
+            }
+        );
+        assert_eq!(
+            push_decision(&upstream(false), &RepoConfig::default()),
+            PushDecision::Fork { organization: None }
+        );
+    }
+
+    #[test]
+    fn test_dry_run_only_allows_reads() {
+        let url = "https://api.github.com/repos/owner/repo/git/refs";
+
+        assert!(guard_dry_run(true, &reqwest::Method::GET, url).is_ok());
+        assert!(guard_dry_run(true, &reqwest::Method::POST, url).is_err());
+        assert!(guard_dry_run(true, &reqwest::Method::PATCH, url).is_err());
+        assert!(guard_dry_run(true, &reqwest::Method::DELETE, url).is_err());
+        assert!(guard_dry_run(false, &reqwest::Method::POST, url).is_ok());
+    }
 }
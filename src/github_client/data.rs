@@ -1,33 +1,133 @@
-use serde::Deserialize;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RepoContent {
     pub name: String,
     pub path: String,
+    pub sha: String,             // Blob SHA for files, tree SHA for dirs
     pub r#type: String,          // "file" or "dir"
     pub content: Option<String>, // Only present in single file requests
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct Repository {
+    pub name: String,
+    pub owner: Account,
+    pub default_branch: String,
+    /// Only present when the request was authenticated
+    pub permissions: Option<Permissions>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Account {
+    pub login: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Permissions {
+    pub push: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PullRequest {
+    pub number: u64,
+    pub html_url: String,
+    pub title: String,
+    pub body: Option<String>,
+    pub state: String, // "open" or "closed"
+    pub merged_at: Option<DateTime<Utc>>,
+    pub head: PullRequestRef,
+    pub base: PullRequestRef,
+    #[serde(default)]
+    pub labels: Vec<Label>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PullRequestRef {
+    #[serde(rename = "ref")]
+    pub branch: String,
+    pub sha: String,
+    pub label: String, // "owner:branch"
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Label {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PullRequestFile {
+    pub filename: String,
+    pub status: String,        // "added", "modified", "removed", ...
+    pub patch: Option<String>, // Missing for binary or very large diffs
+}
+
+/// A pull request `publish_docs_pr` opened or refreshed
+#[derive(Debug, Clone)]
+pub struct PublishedPr {
+    pub url: String,
+    /// Labels, assignees or milestone that couldn't be applied to it
+    pub metadata_failures: Vec<String>,
+}
+
+/// A branch Dredger pushed on some earlier run
+#[derive(Debug, Clone)]
+pub struct DredgerBranch {
+    pub name: String,
+    pub sha: String,
+    pub last_commit_at: DateTime<Utc>,
+}
+
+/// A single inline comment of a pull request review
+#[derive(Debug, Clone, Serialize)]
+pub struct ReviewComment {
+    pub path: String,
+    pub body: String,
+    /// Last line of the commented range, in the PR's version of the file
+    pub line: usize,
+    /// First line of the range, for comments spanning several lines
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_line: Option<usize>,
+    pub side: String, // "RIGHT" comments on the PR's version of the file
+}
+
+/// A line-level note attached to a check run
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckAnnotation {
+    pub path: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub annotation_level: String, // "notice", "warning" or "failure"
+    pub message: String,
+}
+
 #[derive(Debug, Clone)]
 pub enum RepoNode {
     File {
         name: String,
         path: String,
+        sha: String,
         content: String,
         token_count: usize,
+        /// What the file is for, see `core::summaries::summarize_repo`
+        summary: Option<String>,
     },
     Directory {
         name: String,
         path: String,
         children: Vec<RepoNode>,
         token_count: usize,
+        /// What the directory is for, summarized from its children
+        summary: Option<String>,
     },
 }
 
 // Define the recursive iterator to sum token counts
 impl RepoNode {
-    pub fn iter(&self) -> RepoNodeIter {
+    pub fn iter(&self) -> RepoNodeIter<'_> {
         RepoNodeIter::new(self)
     }
 
@@ -37,6 +137,14 @@ impl RepoNode {
             RepoNode::Directory { token_count, .. } => *token_count,
         }
     }
+
+    pub fn summary(&self) -> Option<&str> {
+        match self {
+            RepoNode::File { summary, .. } | RepoNode::Directory { summary, .. } => {
+                summary.as_deref()
+            }
+        }
+    }
 }
 
 pub struct RepoNodeIter<'a> {
@@ -67,16 +175,16 @@ impl<'a> Iterator for RepoNodeIter<'a> {
     }
 }
 
-impl ToString for RepoNode {
-    fn to_string(&self) -> String {
+impl fmt::Display for RepoNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         fn format_node(node: &RepoNode, depth: usize) -> String {
             let indent = "  ".repeat(depth);
             match node {
                 RepoNode::File {
                     name,
                     path,
-                    content: _,
                     token_count,
+                    ..
                 } => {
                     format!(
                         "{}📄 {} ({}) - Token count={:?}\n",
@@ -88,6 +196,7 @@ impl ToString for RepoNode {
                     path,
                     children,
                     token_count,
+                    ..
                 } => {
                     let mut output = format!(
                         "{}📁 {} ({}) - Token count={:?}\n",
@@ -100,6 +209,29 @@ impl ToString for RepoNode {
                 }
             }
         }
-        format_node(self, 0)
+        write!(f, "{}", format_node(self, 0))
+    }
+}
+
+/// A single file to be written as part of a multi-file commit
+#[derive(Debug, Clone)]
+pub struct FileChange {
+    pub path: String,
+    pub content: String,
+    /// Blob SHA of the file on the base branch, `None` for new files
+    pub base_sha: Option<String>,
+}
+
+impl FileChange {
+    /// One line per file, e.g. "Update src/lib.rs", for use in commit messages
+    pub fn describe(changes: &[FileChange]) -> String {
+        changes
+            .iter()
+            .map(|change| match change.base_sha {
+                Some(_) => format!("- Update {}", change.path),
+                None => format!("- Add {}", change.path),
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
     }
 }
@@ -0,0 +1,868 @@
+use crate::languages::lexer::{Brackets, Lexer};
+use crate::languages::strategy::{
+    outside_code_fences, verify_code_tokens, LanguageStrategy, ParsedFile, Prompt, SourceItem,
+};
+use crate::ollama_client::client::DocAnchor;
+use crate::utils::config::RepoConfig;
+
+/// Go: godoc comments, `// Package foo ...` above the package clause and
+/// `// Name ...` lines above each exported declaration
+pub struct GoStrategy;
+
+impl LanguageStrategy for GoStrategy {
+    fn name(&self) -> &'static str {
+        "go"
+    }
+
+    /// Test files aren't part of a package's documented API
+    fn matches(&self, path: &str) -> bool {
+        path.ends_with(".go") && !path.ends_with("_test.go")
+    }
+
+    /// Lists the exported top-level functions, types, variables and
+    /// constants, and the exported methods of exported types. Specs of a
+    /// grouped `type ( ... )` are listed one by one, and so are those of
+    /// a `var` or `const` group without docs of its own, since golint
+    /// takes a comment on the group for all of its values.
+    fn parse(&self, content: &str) -> Result<ParsedFile, String> {
+        let tokens = tokenize(content)?;
+        let lines: Vec<&str> = content.lines().collect();
+        let code: Vec<&Token> = tokens.iter().filter(|token| !token.is_comment()).collect();
+
+        let items = declarations(&code)
+            .into_iter()
+            .map(|decl| {
+                let line = code[decl.first].line;
+                let end_line = code[decl.last].end_line.min(lines.len());
+                let first = lines[line - 1];
+
+                SourceItem {
+                    kind: decl.kind,
+                    name: decl.name.to_string(),
+                    line,
+                    indent: first[..first.len() - first.trim_start().len()].to_string(),
+                    source: lines[line - 1..end_line].join("\n"),
+                    // A documented group documents its specs, which
+                    // don't get comments of their own then
+                    existing_docs: doc_text(&comment_group(&tokens, code[decl.first]))
+                        .or_else(|| doc_text(&comment_group(&tokens, code[decl.group?]))),
+                    required_sections: Vec::new(),
+                    params: Vec::new(),
+                }
+            })
+            .collect();
+
+        let module_docs = package_clause(&code)
+            .and_then(|package| doc_text(&comment_group(&tokens, code[package])));
+        Ok(ParsedFile { module_docs, items })
+    }
+
+    fn module_prompt(
+        &self,
+        file_path: &str,
+        project_context: &str,
+        _repo_config: &RepoConfig,
+    ) -> Prompt {
+        Prompt {
+            system: format!("You are an AI that writes Go doc comments following godoc conventions, using `//` line comments.
+             Given a Go file, write a concise package comment that explains the package's purpose, usage, and important details, in full sentences.
+             It MUST start with `// Package <name>`, where <name> is the name in the file's package clause. For `package main`, start with the name of the command instead.
+             Only write the `//` comment lines that go above the package clause, NOT the code.
+             This file is found at {}.\n
+             Lastly, here is a project overview to help you generate docs. DO NOT include this summary, or any variation, in your docs!: {}", file_path, project_context),
+            examples: vec![
+                (
+                    "package geometry\n\nfunc CircleArea(radius float64) float64 {\n\treturn math.Pi * radius * radius\n}".to_string(),
+                    "// Package geometry computes areas of common shapes.\n//\n// Lengths are in meters and areas in square meters.".to_string()
+                ),
+            ],
+        }
+    }
+
+    fn item_prompt(
+        &self,
+        item: &SourceItem,
+        file_path: &str,
+        project_context: &str,
+        _missing_sections: Option<&[&str]>,
+        _repo_config: &RepoConfig,
+    ) -> Prompt {
+        Prompt {
+            system: format!("You are an AI that writes Go doc comments following godoc conventions, using `//` line comments.
+             Given a single exported Go {}, write a concise doc comment for it alone: what it does, what it returns, and when it errors or panics, in full sentences.
+             The first sentence MUST start with its name, `{}`. Godoc has no sections for arguments or return values, so mention them in the prose.
+             Only write the `//` comment lines that go above it, NOT the code.
+             This {} is found at {}.\n
+             Lastly, here is a project overview to help you generate docs. DO NOT include this summary, or any variation, in your docs!: {}", item.kind, item.name, item.kind, file_path, project_context),
+            examples: vec![
+                (
+                    "func CircleArea(radius float64) float64 {\n\tif radius < 0 {\n\t\tpanic(\"negative radius\")\n\t}\n\treturn math.Pi * radius * radius\n}".to_string(),
+                    "// CircleArea returns the area of a circle with the given radius.\n// It panics if radius is negative.".to_string()
+                ),
+                (
+                    "type Config struct {\n\tTimeout time.Duration\n\tVerbose bool\n}".to_string(),
+                    "// Config holds the settings of the application, such as its timeout\n// and verbosity.".to_string()
+                ),
+            ],
+        }
+    }
+
+    /// Takes the first run of `//` lines in the model's output. Godoc
+    /// expects item docs to start with the item's name (after an
+    /// optional "A", "An" or "The"), so docs that don't are dropped.
+    fn extract_comments(&self, output: &str, anchor: &DocAnchor) -> String {
+        let lines: Vec<String> = output
+            .lines()
+            .map(str::trim)
+            .skip_while(|line| !line.starts_with("//"))
+            .take_while(|line| line.starts_with("//"))
+            .map(|line| {
+                let text = line.trim_start_matches('/');
+                let text = text.strip_prefix(' ').unwrap_or(text).trim_end();
+                match text.is_empty() {
+                    true => "//".to_string(),
+                    false => format!("// {}", text),
+                }
+            })
+            .collect();
+
+        if let (DocAnchor::Item { name, .. }, Some(first)) = (anchor, lines.first()) {
+            let text = first.trim_start_matches("// ");
+            let text = ["A ", "An ", "The "]
+                .iter()
+                .find_map(|article| text.strip_prefix(article))
+                .unwrap_or(text);
+            if text != name && !text.starts_with(&format!("{} ", name)) {
+                return String::new();
+            }
+        }
+        lines.join("\n")
+    }
+
+    fn extract_rationale(&self, output: &str) -> String {
+        outside_code_fences(output)
+            .lines()
+            .filter(|line| !line.trim().starts_with("//"))
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim()
+            .to_string()
+    }
+
+    /// Puts the package comment right above the package clause, where
+    /// godoc looks for it, replacing the text of an existing one.
+    ///
+    /// Outside of `package main`, the comment has to start with
+    /// `// Package <name>`. A wrong name is corrected; a comment that
+    /// doesn't start with "Package" at all isn't spliced.
+    fn splice_module_docs(&self, content: &str, comments: &str) -> (String, usize, usize) {
+        let Ok(tokens) = tokenize(content) else {
+            return (content.to_string(), 0, 0);
+        };
+        let code: Vec<&Token> = tokens.iter().filter(|token| !token.is_comment()).collect();
+        let Some(package) = package_clause(&code) else {
+            return (content.to_string(), 0, 0);
+        };
+
+        let mut docs: Vec<String> = comments.lines().map(str::to_string).collect();
+        let package_name = code[package + 1].text;
+        if let Some(first) = docs.first_mut() {
+            if package_name != "main" {
+                let Some(rest) = first.strip_prefix("// Package ") else {
+                    return (content.to_string(), 0, 0);
+                };
+                let named = rest.split_once(' ').map_or("", |(_, text)| text);
+                *first = format!("// Package {} {}", package_name, named)
+                    .trim_end()
+                    .to_string();
+            }
+        } else {
+            return (content.to_string(), 0, 0);
+        }
+
+        let lines: Vec<&str> = content.lines().collect();
+        let group = comment_group(&tokens, code[package]);
+        let text_comments: Vec<&&Token> = group
+            .iter()
+            .filter(|comment| !is_directive(comment.text))
+            .collect();
+        // Replaces the existing docs, but not the directives below them
+        let (start, end) = match (text_comments.first(), text_comments.last()) {
+            (Some(first), Some(last)) => (first.line - 1, last.end_line),
+            _ => {
+                let insert_at = group.first().map_or(code[package].line, |c| c.line) - 1;
+                (insert_at, insert_at)
+            }
+        };
+        if lines[start..end] == docs[..] {
+            return (content.to_string(), 0, 0);
+        }
+
+        let mut new_lines: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+        let lines_added = docs.len();
+        new_lines.splice(start..end, docs);
+
+        let mut new_content = new_lines.join("\n");
+        if content.ends_with('\n') {
+            new_content.push('\n');
+        }
+        (new_content, lines_added, end - start)
+    }
+
+    /// Puts the doc comment above the declaration on `line`, and above
+    /// any directives like `//go:noinline` right before it, which go
+    /// after the docs by convention
+    fn splice_item_docs(
+        &self,
+        content: &str,
+        line: usize,
+        name: &str,
+        _indent: &str,
+        comments: &str,
+    ) -> Option<(String, usize)> {
+        let tokens = tokenize(content).ok()?;
+        let code: Vec<&Token> = tokens.iter().filter(|token| !token.is_comment()).collect();
+        let decl = declarations(&code)
+            .into_iter()
+            .find(|decl| code[decl.first].line == line && decl.name == name)?;
+
+        let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+        let first = &lines[line - 1];
+        let indent = first[..first.len() - first.trim_start().len()].to_string();
+        let insert_at = comment_group(&tokens, code[decl.first])
+            .first()
+            .map_or(line, |comment| comment.line)
+            - 1;
+
+        let docs: Vec<String> = comments
+            .lines()
+            .map(|doc_line| format!("{}{}", indent, doc_line))
+            .collect();
+        let lines_added = docs.len();
+        lines.splice(insert_at..insert_at, docs);
+
+        let mut new_content = lines.join("\n");
+        if content.ends_with('\n') {
+            new_content.push('\n');
+        }
+        Some((new_content, lines_added))
+    }
+
+    /// Godoc comments are prose, so items don't get required sections
+    fn section_separator(&self, _indent: &str) -> String {
+        String::new()
+    }
+
+    /// A package's files share one package comment
+    fn shares_module_docs(&self) -> bool {
+        true
+    }
+
+    /// A package comment is about the package, which is the directory
+    fn describes_directory(&self, _path: &str) -> bool {
+        true
+    }
+
+    /// The modified file has to still tokenize (if the original did), and
+    /// its tokens other than comments have to match the original's.
+    fn verify(&self, original: &str, modified: &str) -> Result<(), String> {
+        verify_code_tokens(original, modified, |content| {
+            Ok(tokenize(content)?
+                .iter()
+                .filter(|token| !token.is_comment())
+                .map(|token| (token.kind, token.text.to_string()))
+                .collect())
+        })
+    }
+}
+
+/// Whether godoc shows `name` to a package's users: it starts with an
+/// upper-case letter
+fn is_exported(name: &str) -> bool {
+    name.chars().next().is_some_and(char::is_uppercase)
+}
+
+/// Whether a comment is a directive like `//go:generate` or `//nolint:x`
+/// rather than text, by the same rule as `go/ast`
+fn is_directive(comment: &str) -> bool {
+    let Some(rest) = comment.strip_prefix("//") else {
+        return false;
+    };
+    if ["line ", "extern ", "export "]
+        .iter()
+        .any(|prefix| rest.starts_with(prefix))
+    {
+        return true;
+    }
+
+    let is_word = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit();
+    let word_len = rest.find(|c: char| !is_word(c)).unwrap_or(rest.len());
+    word_len > 0
+        && rest[word_len..]
+            .strip_prefix(':')
+            .is_some_and(|after| after.starts_with(is_word))
+}
+
+/// The comments on the lines right before `first`, each on a line of its
+/// own, with no blank line between them: what godoc reads as its docs
+fn comment_group<'a, 'b>(tokens: &'b [Token<'a>], first: &Token) -> Vec<&'b Token<'a>> {
+    let Some(index) = tokens.iter().position(|token| std::ptr::eq(token, first)) else {
+        return Vec::new();
+    };
+
+    let mut group = Vec::new();
+    let mut next_line = first.line;
+    for i in (0..index).rev() {
+        let comment = &tokens[i];
+        let own_line = i == 0 || tokens[i - 1].end_line < comment.line;
+        if !comment.is_comment() || comment.end_line + 1 != next_line || !own_line {
+            break;
+        }
+        group.push(comment);
+        next_line = comment.line;
+    }
+    group.reverse();
+    group
+}
+
+/// The text of a comment group, directives left out, or `None` if it
+/// has none
+fn doc_text(group: &[&Token]) -> Option<String> {
+    let lines: Vec<&str> = group
+        .iter()
+        .filter(|comment| !is_directive(comment.text))
+        .flat_map(|comment| match comment.text.strip_prefix("//") {
+            Some(text) => vec![text.strip_prefix(' ').unwrap_or(text)],
+            None => comment
+                .text
+                .trim_start_matches("/*")
+                .trim_end_matches("*/")
+                .lines()
+                .map(str::trim)
+                .collect(),
+        })
+        .collect();
+
+    let text = lines.join("\n").trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+/// Index of the `package` keyword, which has to be the file's first token
+fn package_clause(code: &[&Token]) -> Option<usize> {
+    let is_clause = code.first()?.text == "package"
+        && code.get(1).is_some_and(|name| name.kind == TokenKind::Name);
+    is_clause.then_some(0)
+}
+
+/// An exported declaration, as indexes into the file's non-comment tokens
+struct Declaration<'a> {
+    /// "func", "method", "type", "var" or "const"
+    kind: &'static str,
+    name: &'a str,
+    /// The keyword, or the spec's name within a group
+    first: usize,
+    last: usize,
+    /// The keyword of the parenthesized group the spec is in, whose
+    /// comment documents every spec in it
+    group: Option<usize>,
+}
+
+fn declarations<'a>(code: &[&Token<'a>]) -> Vec<Declaration<'a>> {
+    let mut found = Vec::new();
+    let mut depth = 0;
+
+    for i in 0..code.len() {
+        if depth == 0 && starts_line(code, i) {
+            match code[i].text {
+                "func" => found.extend(function_at(code, i)),
+                "type" | "var" | "const" => found.extend(general_declarations(code, i)),
+                _ => {}
+            }
+        }
+
+        if code[i].kind == TokenKind::Punct {
+            match code[i].text {
+                "(" | "[" | "{" => depth += 1,
+                ")" | "]" | "}" => depth -= 1,
+                _ => {}
+            }
+        }
+    }
+
+    found
+}
+
+fn starts_line(code: &[&Token], i: usize) -> bool {
+    i == 0 || code[i - 1].end_line < code[i].line
+}
+
+fn is_punct(token: &Token, text: &str) -> bool {
+    token.kind == TokenKind::Punct && token.text == text
+}
+
+fn name_at<'a>(code: &[&Token<'a>], i: usize) -> Option<&'a str> {
+    code.get(i)
+        .filter(|token| token.kind == TokenKind::Name)
+        .map(|token| token.text)
+}
+
+/// The exported function or method whose `func` keyword is token `i`.
+/// Methods only count when their receiver's type is exported too.
+fn function_at<'a>(code: &[&Token<'a>], i: usize) -> Option<Declaration<'a>> {
+    let mut name_index = i + 1;
+    let mut receiver = None;
+    if is_punct(code.get(name_index)?, "(") {
+        let close = matching_close(code, name_index)?;
+        receiver = Some(receiver_type(&code[name_index + 1..close])?);
+        name_index = close + 1;
+    }
+
+    let name = name_at(code, name_index)?;
+    if !is_exported(name) || !receiver.is_none_or(is_exported) {
+        return None;
+    }
+    Some(Declaration {
+        kind: if receiver.is_some() { "method" } else { "func" },
+        name,
+        first: i,
+        last: declaration_end(code, i),
+        group: None,
+    })
+}
+
+/// The type name of a receiver like `r *Cache[K, V]`
+fn receiver_type<'a>(receiver: &[&Token<'a>]) -> Option<&'a str> {
+    let mut depth = 0;
+    let mut name = None;
+    for token in receiver {
+        match token.text {
+            "[" | "(" => depth += 1,
+            "]" | ")" => depth -= 1,
+            _ if depth == 0 && token.kind == TokenKind::Name => name = Some(token.text),
+            _ => {}
+        }
+    }
+    name
+}
+
+/// The exported specs of the `type`, `var` or `const` declaration whose
+/// keyword is token `i`
+fn general_declarations<'a>(code: &[&Token<'a>], i: usize) -> Vec<Declaration<'a>> {
+    let kind = match code[i].text {
+        "type" => "type",
+        "var" => "var",
+        _ => "const",
+    };
+
+    if !code.get(i + 1).is_some_and(|token| is_punct(token, "(")) {
+        return name_at(code, i + 1)
+            .filter(|name| is_exported(name))
+            .map(|name| Declaration {
+                kind,
+                name,
+                first: i,
+                last: declaration_end(code, i),
+                group: None,
+            })
+            .into_iter()
+            .collect();
+    }
+    let Some(close) = matching_close(code, i + 1) else {
+        return Vec::new();
+    };
+
+    // Where each spec starts: a name at the start of a line, directly
+    // inside the group
+    let mut starts = Vec::new();
+    let mut depth = 0;
+    for j in i + 2..close {
+        if depth == 0 && starts_line(code, j) && code[j].kind == TokenKind::Name {
+            starts.push(j);
+        }
+        match code[j].text {
+            "(" | "[" | "{" if code[j].kind == TokenKind::Punct => depth += 1,
+            ")" | "]" | "}" if code[j].kind == TokenKind::Punct => depth -= 1,
+            _ => {}
+        }
+    }
+
+    starts
+        .iter()
+        .enumerate()
+        .filter(|(_, &start)| is_exported(code[start].text))
+        .map(|(n, &start)| Declaration {
+            kind,
+            name: code[start].text,
+            first: start,
+            last: starts.get(n + 1).map_or(close, |&next| next) - 1,
+            group: Some(i),
+        })
+        .collect()
+}
+
+/// Index of the last token of the top-level declaration starting at
+/// token `start`: the one before the next top-level declaration
+fn declaration_end(code: &[&Token], start: usize) -> usize {
+    let mut depth = 0;
+    for i in start + 1..code.len() {
+        if depth == 0
+            && starts_line(code, i)
+            && matches!(code[i].text, "func" | "type" | "var" | "const" | "import")
+        {
+            return i - 1;
+        }
+        if code[i].kind == TokenKind::Punct {
+            match code[i].text {
+                "(" | "[" | "{" => depth += 1,
+                ")" | "]" | "}" => depth -= 1,
+                _ => {}
+            }
+        }
+    }
+    code.len() - 1
+}
+
+/// Index of the bracket closing the one at `open`
+fn matching_close(code: &[&Token], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (i, token) in code.iter().enumerate().skip(open) {
+        if token.kind != TokenKind::Punct {
+            continue;
+        }
+        match token.text {
+            "(" | "[" | "{" => depth += 1,
+            ")" | "]" | "}" => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TokenKind {
+    Name,
+    Number,
+    /// String and rune literals, raw strings included
+    String,
+    Punct,
+    Comment,
+}
+
+#[derive(Debug)]
+struct Token<'a> {
+    kind: TokenKind,
+    text: &'a str,
+    /// 1-based lines the token starts and ends on
+    line: usize,
+    end_line: usize,
+}
+
+impl Token<'_> {
+    fn is_comment(&self) -> bool {
+        self.kind == TokenKind::Comment
+    }
+}
+
+/// Operators longer than one character, longest first
+const OPERATORS: [&str; 25] = [
+    "<<=", ">>=", "&^=", "...", "&^", "&&", "||", "<-", "++", "--", "==", "!=", "<=", ">=", ":=",
+    "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=", "<<", ">>",
+];
+
+/// Splits Go source into tokens, comments included.
+///
+/// Fails on what would keep the file from parsing at all: unterminated
+/// strings, runes and comments, unbalanced brackets and stray characters.
+fn tokenize(content: &str) -> Result<Vec<Token<'_>>, String> {
+    let mut lexer = Lexer::new(content);
+    let mut tokens = Vec::new();
+
+    // A byte order mark isn't code
+    if content.starts_with('\u{feff}') {
+        lexer.advance('\u{feff}'.len_utf8());
+    }
+
+    let mut brackets = Brackets::default();
+    while let Some(token) = lexer.next_token()? {
+        if token.kind == TokenKind::Punct {
+            brackets.push(token.text, token.line)?;
+        }
+        tokens.push(token);
+    }
+
+    brackets.finish()?;
+    Ok(tokens)
+}
+
+/// Go's tokens, read off a `Lexer`
+trait GoLexer<'a> {
+    fn next_token(&mut self) -> Result<Option<Token<'a>>, String>;
+    fn quoted(&mut self, quote: char) -> Result<(), String>;
+}
+
+impl<'a> GoLexer<'a> for Lexer<'a> {
+    fn next_token(&mut self) -> Result<Option<Token<'a>>, String> {
+        self.skip_whitespace();
+        let rest = self.rest();
+        let Some(c) = rest.chars().next() else {
+            return Ok(None);
+        };
+        let start = self.pos;
+        let line = self.line;
+
+        let kind = if rest.starts_with("//") {
+            self.advance(rest.find('\n').unwrap_or(rest.len()));
+            TokenKind::Comment
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            let end = comment
+                .find("*/")
+                .ok_or_else(|| format!("unterminated comment (line {})", line))?;
+            self.advance(end + 4);
+            TokenKind::Comment
+        } else if c == '"' || c == '\'' {
+            self.quoted(c)?;
+            TokenKind::String
+        } else if c == '`' {
+            let end = rest[1..]
+                .find('`')
+                .ok_or_else(|| format!("unterminated raw string literal (line {})", line))?;
+            self.advance(end + 2);
+            TokenKind::String
+        } else if c.is_alphabetic() || c == '_' {
+            let len = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            self.advance(len);
+            TokenKind::Name
+        } else if c.is_ascii_digit()
+            || (c == '.' && rest[1..].starts_with(|c: char| c.is_ascii_digit()))
+        {
+            // Hex floats take their exponent after a `p`, since `e` is a digit
+            let exponent = match rest.starts_with("0x") || rest.starts_with("0X") {
+                true => ['p', 'P'],
+                false => ['e', 'E'],
+            };
+            let mut len = 0;
+            let mut prev_char = ' ';
+            for c in rest.chars() {
+                let exponent_sign = matches!(c, '+' | '-') && exponent.contains(&prev_char);
+                if !(c.is_ascii_alphanumeric() || c == '.' || c == '_' || exponent_sign) {
+                    break;
+                }
+                len += 1;
+                prev_char = c;
+            }
+            self.advance(len);
+            TokenKind::Number
+        } else if let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(**op)) {
+            self.advance(op.len());
+            TokenKind::Punct
+        } else if "{}()[];,<>+-*/%&|^!~:=.".contains(c) {
+            self.advance(1);
+            TokenKind::Punct
+        } else {
+            return Err(format!("invalid character '{}' (line {})", c, line));
+        };
+
+        Ok(Some(Token {
+            kind,
+            text: &self.src[start..self.pos],
+            line,
+            end_line: self.line,
+        }))
+    }
+
+    /// Moves past an interpreted string or rune literal, which can't span
+    /// lines
+    fn quoted(&mut self, quote: char) -> Result<(), String> {
+        let line = self.line;
+        let what = match quote {
+            '"' => "string",
+            _ => "rune",
+        };
+        self.advance(1);
+        loop {
+            let Some(c) = self.rest().chars().next() else {
+                return Err(format!("unterminated {} literal (line {})", what, line));
+            };
+            match c {
+                '\n' => return Err(format!("unterminated {} literal (line {})", what, line)),
+                '\\' => self.skip_escape(),
+                _ if c == quote => {
+                    self.advance(1);
+                    return Ok(());
+                }
+                _ => self.advance(c.len_utf8()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT: &str = "\
+// Copyright (c) Example
+
+package cache
+
+import (
+\t\"sync\"
+)
+
+// Existing docs.
+type Cache[K comparable, V any] struct {
+\tmu    sync.Mutex
+\titems map[K]V
+}
+
+//go:noinline
+func (c *Cache[K, V]) Get(key K) (V, bool) {
+\tc.mu.Lock()
+\tdefer c.mu.Unlock()
+\tv, ok := c.items[key]
+\treturn v, ok
+}
+
+func (c *Cache[K, V]) evict() {}
+
+type (
+\tKey   string
+\tentry struct{ raw []byte }
+)
+
+// Size limits.
+const (
+\tMaxSize = 1 << 10
+\tminSize = 0x1p-2
+)
+
+var Default = New[string, int](`raw
+string`, '\\'')
+
+func New[K comparable, V any](name string, r rune) *Cache[K, V] {
+\treturn &Cache[K, V]{items: map[K]V{}}
+}
+";
+
+    #[test]
+    fn test_parse_finds_exported_declarations() {
+        let parsed = GoStrategy.parse(CONTENT).unwrap();
+        let summary: Vec<(&str, &str, usize, Option<&str>)> = parsed
+            .items
+            .iter()
+            .map(|item| {
+                let docs = item.existing_docs.as_deref();
+                (item.kind, item.name.as_str(), item.line, docs)
+            })
+            .collect();
+
+        assert_eq!(parsed.module_docs, None);
+        assert_eq!(
+            summary,
+            vec![
+                ("type", "Cache", 10, Some("Existing docs.")),
+                ("method", "Get", 16, None),
+                ("type", "Key", 26, None),
+                ("const", "MaxSize", 32, Some("Size limits.")),
+                ("var", "Default", 36, None),
+                ("func", "New", 39, None),
+            ]
+        );
+        assert_eq!(parsed.items[2].indent, "\t");
+        assert_eq!(parsed.items[2].source, "\tKey   string");
+        assert_eq!(
+            parsed.items[4].source,
+            "var Default = New[string, int](`raw\nstring`, '\\'')"
+        );
+    }
+
+    #[test]
+    fn test_splice_docs_above_directives() {
+        let anchor = DocAnchor::Item {
+            name: "Get".to_string(),
+            line: 16,
+            indent: String::new(),
+        };
+        let docs = GoStrategy.extract_comments(
+            "Here you go:\n```go\n// Get returns the value stored under key,\n//and whether there is one.\nfunc (c *Cache[K, V]) Get(key K) (V, bool) {\n\t// Locked\n```",
+            &anchor,
+        );
+        assert_eq!(
+            docs,
+            "// Get returns the value stored under key,\n// and whether there is one."
+        );
+        assert_eq!(
+            GoStrategy.extract_comments("// Returns the value.", &anchor),
+            ""
+        );
+
+        let (with_item_docs, added) = GoStrategy
+            .splice_item_docs(CONTENT, 16, "Get", "", &docs)
+            .unwrap();
+        assert_eq!(added, 2);
+        assert!(with_item_docs.contains(
+            "}\n\n// Get returns the value stored under key,\n// and whether there is one.\n//go:noinline\nfunc (c"
+        ));
+        assert_eq!(
+            GoStrategy.splice_item_docs(CONTENT, 26, "Key", "\t", "// Key names an entry."),
+            Some((
+                CONTENT.replace("(\n\tKey", "(\n\t// Key names an entry.\n\tKey"),
+                1
+            ))
+        );
+
+        let (with_module_docs, added, replaced) =
+            GoStrategy.splice_module_docs(&with_item_docs, "// Package caching keeps values.");
+        assert_eq!((added, replaced), (1, 0));
+        assert!(with_module_docs.starts_with(
+            "// Copyright (c) Example\n\n// Package cache keeps values.\npackage cache\n"
+        ));
+        assert_eq!(GoStrategy.verify(CONTENT, &with_module_docs), Ok(()));
+
+        let parsed = GoStrategy.parse(&with_module_docs).unwrap();
+        assert_eq!(
+            parsed.module_docs.as_deref(),
+            Some("Package cache keeps values.")
+        );
+        assert_eq!(
+            parsed.items[1].existing_docs.as_deref(),
+            Some("Get returns the value stored under key,\nand whether there is one.")
+        );
+        assert_eq!(
+            GoStrategy.splice_module_docs(&with_module_docs, "// Package cache is new."),
+            (with_module_docs.replace("keeps values.", "is new."), 1, 1)
+        );
+        assert_eq!(
+            GoStrategy.splice_module_docs(CONTENT, "// Caches values."),
+            (CONTENT.to_string(), 0, 0)
+        );
+    }
+
+    #[test]
+    fn test_verify_rejects_broken_or_changed_code() {
+        let unterminated = CONTENT.replace("string`", "string");
+        assert!(GoStrategy
+            .verify(CONTENT, &unterminated)
+            .unwrap_err()
+            .starts_with("no longer tokenizes: unterminated raw string literal"));
+
+        let unbalanced = CONTENT.replace("func (c *Cache[K, V]) evict() {}", "func evict() {");
+        assert!(GoStrategy
+            .verify(CONTENT, &unbalanced)
+            .unwrap_err()
+            .contains("was never closed"));
+
+        let changed = CONTENT.replace("\treturn v, ok", "\t// Docs.\n\treturn v, true");
+        assert_eq!(
+            GoStrategy.verify(CONTENT, &changed),
+            Err("changed code outside of doc comments".to_string())
+        );
+    }
+}
@@ -0,0 +1,79 @@
+/// A position in source code being split into tokens, for the
+/// hand-written tokenizers of languages Dredger has no parser for. Each
+/// language reads its own tokens off it through a trait of its own.
+pub struct Lexer<'a> {
+    pub src: &'a str,
+    /// Byte offset of the next character
+    pub pos: usize,
+    /// 1-based line `pos` is on
+    pub line: usize,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(src: &'a str) -> Self {
+        Lexer {
+            src,
+            pos: 0,
+            line: 1,
+        }
+    }
+
+    pub fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    /// Moves `len` bytes ahead, counting the lines passed
+    pub fn advance(&mut self, len: usize) {
+        self.line += self.src[self.pos..self.pos + len].matches('\n').count();
+        self.pos += len;
+    }
+
+    /// Moves past a backslash and the character it escapes
+    pub fn skip_escape(&mut self) {
+        self.advance(1);
+        if let Some(c) = self.rest().chars().next() {
+            self.advance(c.len_utf8());
+        }
+    }
+
+    pub fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.advance(rest.len() - rest.trim_start().len());
+    }
+}
+
+/// The brackets a tokenizer has seen opened and not yet closed, to
+/// reject files whose brackets don't match
+#[derive(Default)]
+pub struct Brackets<'a> {
+    open: Vec<(&'a str, usize)>,
+}
+
+impl<'a> Brackets<'a> {
+    /// Takes in a punctuation token on `line`, failing on a closing
+    /// bracket that doesn't match the last one opened
+    pub fn push(&mut self, punct: &'a str, line: usize) -> Result<(), String> {
+        let expected = match punct {
+            "(" | "[" | "{" => {
+                self.open.push((punct, line));
+                return Ok(());
+            }
+            ")" => "(",
+            "]" => "[",
+            "}" => "{",
+            _ => return Ok(()),
+        };
+        if self.open.pop().map(|(open, _)| open) != Some(expected) {
+            return Err(format!("unmatched '{}' (line {})", punct, line));
+        }
+        Ok(())
+    }
+
+    /// Fails if a bracket was never closed
+    pub fn finish(mut self) -> Result<(), String> {
+        match self.open.pop() {
+            Some((open, line)) => Err(format!("'{}' was never closed (line {})", open, line)),
+            None => Ok(()),
+        }
+    }
+}
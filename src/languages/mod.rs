@@ -0,0 +1,7 @@
+pub mod go;
+pub mod lexer;
+pub mod python;
+pub mod registry;
+pub mod rust;
+pub mod strategy;
+pub mod typescript;
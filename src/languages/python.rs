@@ -0,0 +1,877 @@
+use crate::languages::strategy::{
+    outside_code_fences, LanguageStrategy, ParsedFile, Prompt, SourceItem,
+};
+use crate::ollama_client::client::DocAnchor;
+use crate::utils::config::{DocstringStyle, RepoConfig};
+
+/// Python: PEP 257 docstrings for modules, classes and functions, placed
+/// as the first statement of whatever they document
+pub struct PythonStrategy;
+
+impl LanguageStrategy for PythonStrategy {
+    fn name(&self) -> &'static str {
+        "python"
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        path.ends_with(".py")
+    }
+
+    fn describes_directory(&self, path: &str) -> bool {
+        path.rsplit('/').next() == Some("__init__.py")
+    }
+
+    /// Lists the public classes and functions at module level and the
+    /// public methods of those classes (`__init__` included), in file
+    /// order. One-line definitions like `def f(): pass` have nowhere to
+    /// put a docstring and are left out.
+    ///
+    /// Python items don't get required sections, since new sections
+    /// can't be appended below an existing docstring the way they can
+    /// below `///` lines.
+    fn parse(&self, content: &str) -> Result<ParsedFile, String> {
+        let tokens = tokenize(content)?;
+        let lines: Vec<&str> = content.lines().collect();
+
+        let mut items = Vec::new();
+        // Enclosing definitions: whether each is a class whose methods get
+        // documented, and the indentation depth of its body
+        let mut scopes: Vec<(bool, usize)> = Vec::new();
+        let mut depth = 0;
+
+        for i in 0..tokens.len() {
+            match tokens[i].kind {
+                TokenKind::Indent => depth += 1,
+                TokenKind::Dedent => {
+                    depth -= 1;
+                    while scopes.last().is_some_and(|(_, body)| *body > depth) {
+                        scopes.pop();
+                    }
+                }
+                _ => {}
+            }
+
+            let Some((kind, name)) = definition_at(&tokens, i) else {
+                continue;
+            };
+            let Some(body) = body_start(&tokens, i) else {
+                continue;
+            };
+
+            let (kind, documented) = match (kind, scopes.last()) {
+                ("class", None) => ("class", true),
+                ("def", None) => ("function", true),
+                ("class", Some(&(true, _))) => ("class", true),
+                ("def", Some(&(true, _))) => ("method", true),
+                (kind, _) => (kind, false),
+            };
+            let is_public = !name.starts_with('_') || name == "__init__";
+            scopes.push((kind == "class" && documented && is_public, depth + 1));
+            if !documented || !is_public {
+                continue;
+            }
+
+            let line = tokens[i].line;
+            let end_line = body_end_line(&tokens, body).min(lines.len());
+            let first = lines[line - 1];
+            items.push(SourceItem {
+                kind,
+                name: name.to_string(),
+                line,
+                indent: first[..first.len() - first.trim_start().len()].to_string(),
+                source: lines[line - 1..end_line].join("\n"),
+                existing_docs: docstring_at(&tokens, body + 1).map(|(text, _)| text),
+                required_sections: Vec::new(),
+                params: Vec::new(),
+            });
+        }
+
+        Ok(ParsedFile {
+            module_docs: docstring_at(&tokens, 0).map(|(text, _)| text),
+            items,
+        })
+    }
+
+    fn module_prompt(
+        &self,
+        file_path: &str,
+        project_context: &str,
+        _repo_config: &RepoConfig,
+    ) -> Prompt {
+        Prompt {
+            system: format!("You are an AI that writes Python docstrings following PEP 257.
+             Given a Python module, write a concise module docstring that explains its purpose, usage, and important details: a one-line summary ending in a period, a blank line, then the details.
+             Only write the docstring, wrapped in triple double quotes (\"\"\"), NOT the code.
+             This file is found at {}.\n
+             Lastly, here is a project overview to help you generate docs. DO NOT include this summary, or any variation, in your docs!: {}", file_path, project_context),
+            examples: vec![
+                (
+                    "import math\n\ndef circle_area(radius):\n    return math.pi * radius ** 2".to_string(),
+                    "\"\"\"Geometry helpers.\n\nComputes areas of common shapes, such as circles.\n\"\"\"".to_string()
+                ),
+            ],
+        }
+    }
+
+    fn item_prompt(
+        &self,
+        item: &SourceItem,
+        file_path: &str,
+        project_context: &str,
+        _missing_sections: Option<&[&str]>,
+        repo_config: &RepoConfig,
+    ) -> Prompt {
+        let style = repo_config.python.docstring_style;
+        let (conventions, example) = match style {
+            DocstringStyle::Google => (
+                "Use Google style: arguments under `Args:`, the return value under `Returns:` and exceptions under `Raises:`, each entry indented by four spaces.",
+                "\"\"\"Computes the area of a circle.\n\nArgs:\n    radius: The radius of the circle.\n\nReturns:\n    The computed area.\n\nRaises:\n    ValueError: If the radius is negative.\n\"\"\"",
+            ),
+            DocstringStyle::Numpy => (
+                "Use NumPy style: `Parameters`, `Returns` and `Raises` headings, each underlined with dashes, with entries as `name : type` followed by an indented description.",
+                "\"\"\"Computes the area of a circle.\n\nParameters\n----------\nradius : float\n    The radius of the circle.\n\nReturns\n-------\nfloat\n    The computed area.\n\nRaises\n------\nValueError\n    If the radius is negative.\n\"\"\"",
+            ),
+            DocstringStyle::Rest => (
+                "Use reStructuredText (Sphinx) style: `:param name:`, `:returns:` and `:raises Error:` fields.",
+                "\"\"\"Computes the area of a circle.\n\n:param radius: The radius of the circle.\n:returns: The computed area.\n:raises ValueError: If the radius is negative.\n\"\"\"",
+            ),
+        };
+
+        Prompt {
+            system: format!("You are an AI that writes Python docstrings following PEP 257.
+             Given a single Python {}, write a concise docstring for it alone: a one-line summary ending in a period, then what it does, its arguments, what it returns, and what it raises.
+             {}
+             Only write the docstring, wrapped in triple double quotes (\"\"\"), NOT the code.
+             This {} is found at {}.\n
+             Lastly, here is a project overview to help you generate docs. DO NOT include this summary, or any variation, in your docs!: {}", item.kind, conventions, item.kind, file_path, project_context),
+            examples: vec![
+                (
+                    "def circle_area(radius):\n    if radius < 0:\n        raise ValueError(\"negative radius\")\n    return math.pi * radius ** 2".to_string(),
+                    example.to_string()
+                ),
+            ],
+        }
+    }
+
+    /// Takes the first triple-quoted string of the model's output and
+    /// formats it as a docstring literal, with PEP 257's indentation
+    /// trimming applied. Splicing indents it to where it goes.
+    fn extract_comments(&self, output: &str, _anchor: &DocAnchor) -> String {
+        let Some((_, text, _)) = triple_quoted(output) else {
+            return String::new();
+        };
+
+        let lines = trim_docstring(text);
+        if lines.is_empty() {
+            return String::new();
+        }
+
+        // Backslashes would be read as escapes otherwise
+        let prefix = if text.contains('\\') { "r" } else { "" };
+        if let [summary] = lines.as_slice() {
+            return format!("{}\"\"\"{}\"\"\"", prefix, summary);
+        }
+        format!("{}\"\"\"{}\n\"\"\"", prefix, lines.join("\n"))
+    }
+
+    fn extract_rationale(&self, output: &str) -> String {
+        let output = match triple_quoted(output) {
+            Some((start, _, end)) => format!("{}{}", &output[..start], &output[end..]),
+            None => output.to_string(),
+        };
+
+        outside_code_fences(&output).trim().to_string()
+    }
+
+    /// Puts the module docstring below a shebang, an encoding line and a
+    /// leading comment header, and above everything else, `from
+    /// __future__` imports included. An existing module docstring is
+    /// replaced in place.
+    fn splice_module_docs(&self, content: &str, comments: &str) -> (String, usize, usize) {
+        let Ok(tokens) = tokenize(content) else {
+            return (content.to_string(), 0, 0);
+        };
+        let docs: Vec<&str> = comments.lines().collect();
+        if docs.is_empty() {
+            return (content.to_string(), 0, 0);
+        }
+
+        let lines: Vec<&str> = content.lines().collect();
+        let mut new_lines: Vec<&str> = Vec::with_capacity(lines.len() + docs.len() + 2);
+        let lines_replaced = match docstring_at(&tokens, 0) {
+            Some((_, (start, end))) => {
+                if lines[start - 1..end] == docs[..] {
+                    return (content.to_string(), 0, 0);
+                }
+                new_lines.extend(&lines[..start - 1]);
+                new_lines.extend(&docs);
+                new_lines.extend(&lines[end..]);
+                end - start + 1
+            }
+            None => {
+                // Shebang, encoding line and license header: comments
+                // before the first statement
+                let first_code = tokens.first().map_or(lines.len(), |token| token.line - 1);
+                let insert_at = lines[..first_code]
+                    .iter()
+                    .take_while(|line| line.trim().is_empty() || line.starts_with('#'))
+                    .enumerate()
+                    .filter(|(_, line)| line.starts_with('#'))
+                    .last()
+                    .map_or(0, |(i, _)| i + 1);
+
+                let rest = &lines[insert_at..];
+                let blank_lines = rest
+                    .iter()
+                    .take_while(|line| line.trim().is_empty())
+                    .count();
+
+                new_lines.extend(&lines[..insert_at]);
+                if insert_at > 0 {
+                    new_lines.push("");
+                }
+                new_lines.extend(&docs);
+                if blank_lines < rest.len() {
+                    new_lines.push("");
+                }
+                new_lines.extend(&rest[blank_lines..]);
+                0
+            }
+        };
+
+        let mut new_content = new_lines.join("\n");
+        if content.ends_with('\n') || content.is_empty() {
+            new_content.push('\n');
+        }
+        (new_content, docs.len(), lines_replaced)
+    }
+
+    /// Puts the docstring right below the `def` or `class` header that
+    /// starts on `line`, indented like the first statement of its body.
+    fn splice_item_docs(
+        &self,
+        content: &str,
+        line: usize,
+        name: &str,
+        _indent: &str,
+        comments: &str,
+    ) -> Option<(String, usize)> {
+        let tokens = tokenize(content).ok()?;
+        let start = (0..tokens.len()).find(|&i| {
+            tokens[i].line == line
+                && definition_at(&tokens, i).is_some_and(|(_, found)| found == name)
+        })?;
+        let body = body_start(&tokens, start)?;
+
+        let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+        let body_line = &lines[tokens[body + 1].line - 1];
+        let body_indent = body_line[..body_line.len() - body_line.trim_start().len()].to_string();
+
+        let docs: Vec<String> = comments
+            .lines()
+            .map(|doc_line| {
+                if doc_line.trim().is_empty() {
+                    String::new()
+                } else {
+                    format!("{}{}", body_indent, doc_line)
+                }
+            })
+            .collect();
+        let lines_added = docs.len();
+        // Below the header's last line, which ends with the `:`
+        let header_end = tokens[body - 1].line;
+        lines.splice(header_end..header_end, docs);
+
+        let mut new_content = lines.join("\n");
+        if content.ends_with('\n') {
+            new_content.push('\n');
+        }
+        Some((new_content, lines_added))
+    }
+
+    /// Docstrings can't be extended with new sections, see `parse`
+    fn section_separator(&self, _indent: &str) -> String {
+        String::new()
+    }
+
+    /// The modified file has to still tokenize (if the original did), and
+    /// its tokens with every docstring-only statement taken out have to
+    /// match the original's.
+    fn verify(&self, original: &str, modified: &str) -> Result<(), String> {
+        let Ok(original_tokens) = tokenize(original) else {
+            // Nothing to compare against
+            return Ok(());
+        };
+        let modified_tokens =
+            tokenize(modified).map_err(|e| format!("no longer tokenizes: {}", e))?;
+
+        if strip_docstrings(&original_tokens) != strip_docstrings(&modified_tokens) {
+            return Err("changed code outside of docstrings".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+/// The docstring's lines with PEP 257's trimming: common indentation of
+/// all but the first line removed, and blank lines at either end dropped
+fn trim_docstring(text: &str) -> Vec<String> {
+    let lines: Vec<&str> = text.lines().collect();
+    let indent = lines
+        .iter()
+        .skip(1)
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    let mut trimmed: Vec<String> = lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| match i {
+            0 => line.trim().to_string(),
+            _ if line.trim().is_empty() => String::new(),
+            _ => line[indent..].trim_end().to_string(),
+        })
+        .collect();
+
+    while trimmed.last().is_some_and(String::is_empty) {
+        trimmed.pop();
+    }
+    let leading_blank = trimmed.iter().take_while(|line| line.is_empty()).count();
+    trimmed.drain(..leading_blank);
+    trimmed
+}
+
+/// Finds the first `"""` or `'''` string of `text`: its start offset,
+/// its content and the offset right after it
+fn triple_quoted(text: &str) -> Option<(usize, &str, usize)> {
+    let start = [text.find("\"\"\""), text.find("'''")]
+        .into_iter()
+        .flatten()
+        .min()?;
+    let quotes = &text[start..start + 3];
+    let inner = &text[start + 3..];
+    let end = inner.find(quotes)?;
+    Some((start, &inner[..end], start + 3 + end + 3))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TokenKind {
+    Name,
+    Number,
+    String,
+    Op,
+    /// End of a logical line
+    Newline,
+    Indent,
+    Dedent,
+}
+
+#[derive(Debug)]
+struct Token<'a> {
+    kind: TokenKind,
+    text: &'a str,
+    /// 1-based lines the token starts and ends on
+    line: usize,
+    end_line: usize,
+}
+
+/// Python's 3 and 2 character operators, longest first
+const OPERATORS: [&str; 24] = [
+    "**=", "//=", ">>=", "<<=", "...", "**", "//", ">>", "<<", "<=", ">=", "==", "!=", "->", "+=",
+    "-=", "*=", "/=", "%=", "&=", "|=", "^=", "@=", ":=",
+];
+
+/// Splits Python source into tokens the way Python's own tokenizer does,
+/// as far as docstrings are concerned: comments and blank lines dropped,
+/// `NEWLINE` only at the end of logical lines, and `INDENT`/`DEDENT`
+/// around blocks.
+///
+/// Fails on what would make Python reject the file before parsing it:
+/// unterminated strings, unbalanced brackets, stray characters and
+/// dedents that match no outer indentation level.
+fn tokenize(content: &str) -> Result<Vec<Token<'_>>, String> {
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut indents: Vec<&str> = vec![""];
+    let mut brackets: Vec<(char, usize)> = Vec::new();
+    let mut line = 1;
+    let mut pos = 0;
+    let mut at_line_start = true;
+
+    while pos < content.len() {
+        if at_line_start && brackets.is_empty() {
+            let rest = &content[pos..];
+            let indent_len = rest.len() - rest.trim_start_matches([' ', '\t', '\x0c']).len();
+            let after_indent = &rest[indent_len..];
+
+            // Blank and comment-only lines don't count for indentation
+            if after_indent.is_empty() || after_indent.starts_with(['\n', '\r', '#']) {
+                pos += rest.find('\n').map_or(rest.len(), |end| end + 1);
+                line += 1;
+                continue;
+            }
+
+            let indent = &rest[..indent_len];
+            let top = *indents.last().unwrap_or(&"");
+            if indent.len() > top.len() && indent.starts_with(top) {
+                indents.push(indent);
+                tokens.push(Token {
+                    kind: TokenKind::Indent,
+                    text: indent,
+                    line,
+                    end_line: line,
+                });
+            } else if indent != top {
+                while indents.last().is_some_and(|top| top.len() > indent.len()) {
+                    indents.pop();
+                    tokens.push(Token {
+                        kind: TokenKind::Dedent,
+                        text: "",
+                        line,
+                        end_line: line,
+                    });
+                }
+                if indents.last() != Some(&indent) {
+                    return Err(format!(
+                        "unindent does not match any outer indentation level (line {})",
+                        line
+                    ));
+                }
+            }
+
+            pos += indent_len;
+            at_line_start = false;
+        }
+
+        let rest = &content[pos..];
+        let Some(c) = rest.chars().next() else {
+            break;
+        };
+        let start = pos;
+        let start_line = line;
+
+        let kind = match c {
+            ' ' | '\t' | '\x0c' | '\r' => {
+                pos += 1;
+                continue;
+            }
+            '\n' => {
+                pos += 1;
+                line += 1;
+                if brackets.is_empty() {
+                    at_line_start = true;
+                    TokenKind::Newline
+                } else {
+                    continue;
+                }
+            }
+            '#' => {
+                pos += rest.find('\n').unwrap_or(rest.len());
+                continue;
+            }
+            '\\' => {
+                let continuation = rest[1..].trim_start_matches('\r');
+                if !continuation.starts_with('\n') {
+                    return Err(format!(
+                        "unexpected character after line continuation character (line {})",
+                        line
+                    ));
+                }
+                pos = content.len() - continuation.len() + 1;
+                line += 1;
+                continue;
+            }
+            '"' | '\'' => {
+                pos += string_len(rest, &mut line)?;
+                TokenKind::String
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let name_len = rest
+                    .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                    .unwrap_or(rest.len());
+                let name = &rest[..name_len];
+                let is_prefix = name.len() <= 2
+                    && name.chars().all(|c| "rRbBuUfF".contains(c))
+                    && rest[name_len..].starts_with(['"', '\'']);
+                if is_prefix {
+                    pos += name_len + string_len(&rest[name_len..], &mut line)?;
+                    TokenKind::String
+                } else {
+                    pos += name_len;
+                    TokenKind::Name
+                }
+            }
+            c if c.is_ascii_digit()
+                || (c == '.' && rest[1..].starts_with(|c: char| c.is_ascii_digit())) =>
+            {
+                let mut len = 0;
+                let mut prev = ' ';
+                for c in rest.chars() {
+                    let exponent_sign = matches!(c, '+' | '-') && matches!(prev, 'e' | 'E');
+                    if !(c.is_ascii_alphanumeric() || c == '.' || c == '_' || exponent_sign) {
+                        break;
+                    }
+                    len += 1;
+                    prev = c;
+                }
+                pos += len;
+                TokenKind::Number
+            }
+            '(' | '[' | '{' => {
+                brackets.push((c, line));
+                pos += 1;
+                TokenKind::Op
+            }
+            ')' | ']' | '}' => {
+                let expected = match c {
+                    ')' => '(',
+                    ']' => '[',
+                    _ => '{',
+                };
+                if brackets.pop().map(|(open, _)| open) != Some(expected) {
+                    return Err(format!("unmatched '{}' (line {})", c, line));
+                }
+                pos += 1;
+                TokenKind::Op
+            }
+            _ => {
+                if let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(**op)) {
+                    pos += op.len();
+                } else if "+-*/%@&|^~<>,:;.=!".contains(c) {
+                    pos += 1;
+                } else {
+                    return Err(format!("invalid character '{}' (line {})", c, line));
+                }
+                TokenKind::Op
+            }
+        };
+
+        // Logical lines are never empty
+        if kind == TokenKind::Newline
+            && tokens
+                .last()
+                .is_none_or(|token| token.kind == TokenKind::Newline)
+        {
+            continue;
+        }
+        tokens.push(Token {
+            kind,
+            text: &content[start..pos],
+            line: start_line,
+            end_line: line,
+        });
+    }
+
+    if let Some((open, open_line)) = brackets.pop() {
+        return Err(format!("'{}' was never closed (line {})", open, open_line));
+    }
+    if tokens
+        .last()
+        .is_some_and(|token| token.kind != TokenKind::Newline)
+    {
+        tokens.push(Token {
+            kind: TokenKind::Newline,
+            text: "",
+            line,
+            end_line: line,
+        });
+    }
+    for _ in 1..indents.len() {
+        tokens.push(Token {
+            kind: TokenKind::Dedent,
+            text: "",
+            line,
+            end_line: line,
+        });
+    }
+
+    Ok(tokens)
+}
+
+/// Length of the string literal (prefix excluded) at the start of
+/// `text`, counting the lines it spans into `line`
+fn string_len(text: &str, line: &mut usize) -> Result<usize, String> {
+    let start_line = *line;
+    let quote = &text[..1];
+    let triple = text.starts_with(&quote.repeat(3));
+    let (closing, mut i) = if triple {
+        (quote.repeat(3), 3)
+    } else {
+        (quote.to_string(), 1)
+    };
+
+    let bytes = text.as_bytes();
+    while i < text.len() {
+        match bytes[i] {
+            b'\\' => {
+                if bytes.get(i + 1) == Some(&b'\n') {
+                    *line += 1;
+                }
+                i += 2;
+                continue;
+            }
+            b'\n' if !triple => {
+                return Err(format!("unterminated string literal (line {})", start_line));
+            }
+            b'\n' => *line += 1,
+            _ if bytes[i..].starts_with(closing.as_bytes()) => return Ok(i + closing.len()),
+            _ => {}
+        }
+        i += 1;
+    }
+
+    Err(match triple {
+        true => format!(
+            "unterminated triple-quoted string literal (line {})",
+            start_line
+        ),
+        false => format!("unterminated string literal (line {})", start_line),
+    })
+}
+
+/// Whether token `i` starts a statement
+fn starts_statement(tokens: &[Token], i: usize) -> bool {
+    i == 0
+        || matches!(
+            tokens[i - 1].kind,
+            TokenKind::Newline | TokenKind::Indent | TokenKind::Dedent
+        )
+}
+
+/// The keyword ("def" or "class") and name of the definition starting
+/// at token `i`, if one does
+fn definition_at<'a>(tokens: &[Token<'a>], i: usize) -> Option<(&'static str, &'a str)> {
+    if !starts_statement(tokens, i) {
+        return None;
+    }
+    let mut keyword = tokens.get(i)?;
+    let mut name_index = i + 1;
+    if keyword.text == "async" {
+        keyword = tokens.get(i + 1)?;
+        name_index += 1;
+    }
+    let kind = match (keyword.kind, keyword.text) {
+        (TokenKind::Name, "def") => "def",
+        (TokenKind::Name, "class") => "class",
+        _ => return None,
+    };
+
+    let name = tokens.get(name_index)?;
+    (name.kind == TokenKind::Name).then_some((kind, name.text))
+}
+
+/// Index of the `INDENT` opening the body of the definition starting at
+/// token `i`, or `None` for a definition on a single line
+fn body_start(tokens: &[Token], i: usize) -> Option<usize> {
+    let newline = i + tokens[i..]
+        .iter()
+        .position(|token| token.kind == TokenKind::Newline)?;
+    let indent = newline + 1;
+    (tokens.get(indent)?.kind == TokenKind::Indent).then_some(indent)
+}
+
+/// The last line of the block whose `INDENT` is token `indent`
+fn body_end_line(tokens: &[Token], indent: usize) -> usize {
+    let mut depth = 0;
+    for (i, token) in tokens.iter().enumerate().skip(indent) {
+        match token.kind {
+            TokenKind::Indent => depth += 1,
+            TokenKind::Dedent => {
+                depth -= 1;
+                if depth == 0 {
+                    // The `NEWLINE` ending the block's last statement
+                    return tokens[i - 1].line;
+                }
+            }
+            _ => {}
+        }
+    }
+    tokens.last().map_or(1, |token| token.end_line)
+}
+
+/// The text and line range of the docstring statement at token `i`: a
+/// statement made up of nothing but string literals
+fn docstring_at(tokens: &[Token], i: usize) -> Option<(String, (usize, usize))> {
+    let strings = tokens[i.min(tokens.len())..]
+        .iter()
+        .take_while(|token| token.kind == TokenKind::String)
+        .count();
+    if strings == 0 || tokens.get(i + strings)?.kind != TokenKind::Newline {
+        return None;
+    }
+
+    let literals = &tokens[i..i + strings];
+    let text = literals
+        .iter()
+        .map(|token| string_value(token.text))
+        .collect::<Vec<_>>()
+        .join("");
+    Some((text, (literals[0].line, literals[strings - 1].end_line)))
+}
+
+/// A string literal's content, without its prefix and quotes
+fn string_value(literal: &str) -> &str {
+    let unprefixed = literal.trim_start_matches(|c: char| c.is_alphabetic());
+    let quotes = if unprefixed.starts_with("\"\"\"") || unprefixed.starts_with("'''") {
+        3
+    } else {
+        1
+    };
+    &unprefixed[quotes..unprefixed.len() - quotes]
+}
+
+/// Kind and text of every token, minus docstring-only statements
+fn strip_docstrings<'a>(tokens: &[Token<'a>]) -> Vec<(TokenKind, &'a str)> {
+    let mut stripped = Vec::with_capacity(tokens.len());
+    let mut i = 0;
+
+    while i < tokens.len() {
+        if starts_statement(tokens, i) && docstring_at(tokens, i).is_some() {
+            // The string literals and their NEWLINE
+            while tokens[i].kind == TokenKind::String {
+                i += 1;
+            }
+            i += 1;
+            continue;
+        }
+
+        stripped.push((tokens[i].kind, tokens[i].text));
+        i += 1;
+    }
+
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT: &str = "\
+#!/usr/bin/env python3
+# Copyright (c) Example
+
+from __future__ import annotations
+
+
+class Config:
+    \"\"\"Existing docs.\"\"\"
+
+    def __init__(self, path):
+        self.path = path
+
+    @property
+    def name(self):
+        # The file name
+        return (
+            self.path
+        )
+
+    def _private(self): pass
+
+
+async def load(path: str) -> Config:
+    def helper():
+        pass
+    return Config(path)
+
+
+def one_liner(): pass
+";
+
+    #[test]
+    fn test_parse_finds_public_definitions() {
+        let parsed = PythonStrategy.parse(CONTENT).unwrap();
+        let summary: Vec<(&str, &str, usize, Option<&str>)> = parsed
+            .items
+            .iter()
+            .map(|item| {
+                let docs = item.existing_docs.as_deref();
+                (item.kind, item.name.as_str(), item.line, docs)
+            })
+            .collect();
+
+        assert_eq!(parsed.module_docs, None);
+        assert_eq!(
+            summary,
+            vec![
+                ("class", "Config", 7, Some("Existing docs.")),
+                ("method", "__init__", 10, None),
+                ("method", "name", 14, None),
+                ("function", "load", 23, None),
+            ]
+        );
+        assert_eq!(parsed.items[2].indent, "    ");
+        assert_eq!(
+            parsed.items[2].source,
+            "    def name(self):\n        # The file name\n        return (\n            self.path\n        )"
+        );
+    }
+
+    #[test]
+    fn test_splice_docs_as_first_statement() {
+        let docs = PythonStrategy.extract_comments(
+            "Here you go:\n```python\ndef name(self):\n    \"\"\"Returns the name.\n\n    Returns:\n        The file name.\n    \"\"\"\n```",
+            &DocAnchor::Module,
+        );
+        assert_eq!(
+            docs,
+            "\"\"\"Returns the name.\n\nReturns:\n    The file name.\n\"\"\""
+        );
+
+        let (with_item_docs, added) = PythonStrategy
+            .splice_item_docs(CONTENT, 14, "name", "    ", &docs)
+            .unwrap();
+        assert_eq!(added, 5);
+        assert!(with_item_docs.contains(
+            "    def name(self):\n        \"\"\"Returns the name.\n\n        Returns:\n            The file name.\n        \"\"\"\n        # The file name\n"
+        ));
+        assert_eq!(
+            PythonStrategy.splice_item_docs(CONTENT, 29, "one_liner", "", &docs),
+            None
+        );
+
+        let (with_module_docs, added, replaced) =
+            PythonStrategy.splice_module_docs(&with_item_docs, "\"\"\"Config loading.\"\"\"");
+        assert_eq!((added, replaced), (1, 0));
+        assert!(with_module_docs.starts_with(
+            "#!/usr/bin/env python3\n# Copyright (c) Example\n\n\"\"\"Config loading.\"\"\"\n\nfrom __future__"
+        ));
+        assert_eq!(PythonStrategy.verify(CONTENT, &with_module_docs), Ok(()));
+
+        let parsed = PythonStrategy.parse(&with_module_docs).unwrap();
+        assert_eq!(parsed.module_docs.as_deref(), Some("Config loading."));
+        assert_eq!(
+            PythonStrategy.splice_module_docs(&with_module_docs, "\"\"\"New.\"\"\""),
+            (
+                with_module_docs.replace("\"\"\"Config loading.\"\"\"", "\"\"\"New.\"\"\""),
+                1,
+                1
+            )
+        );
+    }
+
+    #[test]
+    fn test_verify_rejects_broken_or_changed_code() {
+        let unterminated = CONTENT.replace("\"\"\"Existing docs.\"\"\"", "\"\"\"Existing docs.");
+        assert!(PythonStrategy
+            .verify(CONTENT, &unterminated)
+            .unwrap_err()
+            .starts_with("no longer tokenizes: unterminated triple-quoted string"));
+
+        let misindented = CONTENT.replace("        self.path = path", "  self.path = path");
+        assert!(PythonStrategy
+            .verify(CONTENT, &misindented)
+            .unwrap_err()
+            .contains("unindent does not match"));
+
+        let changed = CONTENT.replace(
+            "    return Config(path)",
+            "    \"\"\"Docs.\"\"\"\n    return None",
+        );
+        assert_eq!(
+            PythonStrategy.verify(CONTENT, &changed),
+            Err("changed code outside of docstrings".to_string())
+        );
+    }
+}
@@ -0,0 +1,84 @@
+use crate::languages::go::GoStrategy;
+use crate::languages::python::PythonStrategy;
+use crate::languages::rust::RustStrategy;
+use crate::languages::strategy::LanguageStrategy;
+use crate::languages::typescript::TypeScriptStrategy;
+use std::sync::OnceLock;
+
+/// The languages Dredger documents, each handled by its own strategy
+pub struct LanguageRegistry {
+    strategies: Vec<Box<dyn LanguageStrategy>>,
+}
+
+impl LanguageRegistry {
+    /// A registry without any languages
+    pub fn empty() -> Self {
+        LanguageRegistry {
+            strategies: Vec::new(),
+        }
+    }
+
+    /// Adds a language. Strategies registered earlier win when several
+    /// match the same file.
+    pub fn register(&mut self, strategy: Box<dyn LanguageStrategy>) {
+        self.strategies.push(strategy);
+    }
+
+    /// The strategy for the file at `path`, if any language handles it
+    pub fn for_path(&self, path: &str) -> Option<&dyn LanguageStrategy> {
+        self.strategies
+            .iter()
+            .find(|strategy| strategy.matches(path))
+            .map(Box::as_ref)
+    }
+
+    /// Names of the registered languages, in registration order
+    pub fn names(&self) -> Vec<&'static str> {
+        self.strategies
+            .iter()
+            .map(|strategy| strategy.name())
+            .collect()
+    }
+}
+
+impl Default for LanguageRegistry {
+    /// Every language Dredger ships with
+    fn default() -> Self {
+        let mut registry = LanguageRegistry::empty();
+        registry.register(Box::new(RustStrategy));
+        registry.register(Box::new(PythonStrategy));
+        registry.register(Box::new(TypeScriptStrategy));
+        registry.register(Box::new(GoStrategy));
+        registry
+    }
+}
+
+/// The registry of built-in languages
+pub fn registry() -> &'static LanguageRegistry {
+    static REGISTRY: OnceLock<LanguageRegistry> = OnceLock::new();
+    REGISTRY.get_or_init(LanguageRegistry::default)
+}
+
+/// The built-in strategy for the file at `path`, if any
+pub fn for_path(path: &str) -> Option<&'static dyn LanguageStrategy> {
+    registry().for_path(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_for_path_picks_the_matching_language() {
+        assert_eq!(for_path("src/lib.rs").map(|s| s.name()), Some("rust"));
+        assert_eq!(for_path("pkg/models.py").map(|s| s.name()), Some("python"));
+        assert_eq!(
+            for_path("web/App.tsx").map(|s| s.name()),
+            Some("typescript")
+        );
+        assert_eq!(for_path("cmd/main.go").map(|s| s.name()), Some("go"));
+        assert!(for_path("cmd/main_test.go").is_none());
+        assert!(for_path("README.md").is_none());
+        assert!(LanguageRegistry::empty().for_path("src/lib.rs").is_none());
+    }
+}
@@ -0,0 +1,183 @@
+use crate::core::{items, splice, verify};
+use crate::languages::strategy::{
+    outside_code_fences, LanguageStrategy, ParsedFile, Prompt, SourceItem,
+};
+use crate::ollama_client::client::DocAnchor;
+use crate::utils::config::RepoConfig;
+
+/// Rust: `//!` module docs and `///` item docs, parsed with syn
+pub struct RustStrategy;
+
+impl LanguageStrategy for RustStrategy {
+    fn name(&self) -> &'static str {
+        "rust"
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        path.ends_with(".rs")
+    }
+
+    fn parse(&self, content: &str) -> Result<ParsedFile, String> {
+        items::parse_file(content).map_err(|e| e.to_string())
+    }
+
+    /// Falls back to scanning the file's header when it doesn't parse,
+    /// so docs in a file `syn` can't read aren't mistaken for missing
+    fn has_module_docs(&self, content: &str) -> bool {
+        match self.parse(content) {
+            Ok(parsed_file) => parsed_file.module_docs.is_some(),
+            Err(_) => splice::has_module_docs(content),
+        }
+    }
+
+    fn checks_intra_doc_links(&self) -> bool {
+        true
+    }
+
+    /// Also counts the items Dredger doesn't document, like constants
+    /// and statics
+    fn coverage_items(&self, content: &str) -> Result<Vec<SourceItem>, String> {
+        items::public_items(content).map_err(|e| e.to_string())
+    }
+
+    fn describes_directory(&self, path: &str) -> bool {
+        matches!(path.rsplit('/').next(), Some("mod.rs" | "lib.rs"))
+    }
+
+    fn module_prompt(
+        &self,
+        file_path: &str,
+        project_context: &str,
+        _repo_config: &RepoConfig,
+    ) -> Prompt {
+        Prompt {
+            system: format!("You are an AI that generates Rust doc comments using `//!` style. It's very important that you use //! for comments.
+             Given a file or section of a file, write concise, idiomatic Rust documentation that explains its purpose, usage, and important details.
+             This file is found at {}.\n
+             Lastly, here is a project overview to help you generate docs. DO NOT include this summary, or any variation, in your docs!: {}", file_path, project_context),
+            examples: vec![
+                (
+                    "fn calculate_area(radius: f64) -> f64 { std::f64::consts::PI * radius * radius }".to_string(),
+                    "//! Computes the area of a circle.\n//! \n//! # Arguments\n//! * `radius` - The radius of the circle.\n//! \n//! # Returns\n//! The computed area.".to_string()
+                ),
+                (
+                    "struct Config { timeout: u32, verbose: bool }".to_string(),
+                    "//! Holds configuration settings for the application.\n//! \n//! Includes parameters for timeout and verbosity.".to_string()
+                )
+            ],
+        }
+    }
+
+    fn item_prompt(
+        &self,
+        item: &SourceItem,
+        file_path: &str,
+        project_context: &str,
+        missing_sections: Option<&[&str]>,
+        repo_config: &RepoConfig,
+    ) -> Prompt {
+        let with_examples = repo_config.examples.generate && matches!(item.kind, "fn" | "method");
+        let task = match missing_sections {
+            None if with_examples => format!("Given a single Rust {}, write concise, idiomatic documentation for it alone: what it does, its arguments, what it returns, and when it errors or panics.
+             End with a `/// # Examples` section holding a short ```rust code block that calls it, importing what it needs from this crate by its full path.", item.kind),
+            None => format!("Given a single Rust {}, write concise, idiomatic documentation for it alone: what it does, its arguments, what it returns, and when it errors or panics.", item.kind),
+            Some(sections) => format!("This Rust {} is already documented, but its docs lack these sections: {}. Write ONLY those sections, each starting with a `/// # <Section>` heading line. Do NOT rewrite or repeat the existing docs.", item.kind, sections.join(", ")),
+        };
+
+        Prompt {
+            system: format!("You are an AI that generates Rust doc comments using `///` style. It's very important that you use /// for comments.
+             {}
+             Do NOT repeat the code, only write the /// comment lines that go above it.
+             This {} is found at {}.\n
+             Lastly, here is a project overview to help you generate docs. DO NOT include this summary, or any variation, in your docs!: {}", task, item.kind, file_path, project_context),
+            examples: vec![
+                (
+                    "pub fn calculate_area(radius: f64) -> f64 { std::f64::consts::PI * radius * radius }".to_string(),
+                    "/// Computes the area of a circle.\n///\n/// # Arguments\n/// * `radius` - The radius of the circle.\n///\n/// # Returns\n/// The computed area.".to_string()
+                ),
+                (
+                    "pub struct Config { timeout: u32, verbose: bool }".to_string(),
+                    "/// Configuration settings for the application, such as the timeout and verbosity.".to_string()
+                )
+            ],
+        }
+    }
+
+    /// Takes the `///` and `//!` lines of the output, so plain `//`
+    /// comments in code the model echoes back don't become docs. The
+    /// model doesn't always stick to the style it was asked for, so
+    /// `//!` lines of item docs (and the other way around) are rewritten
+    /// rather than dropped.
+    fn extract_comments(&self, output: &str, anchor: &DocAnchor) -> String {
+        let prefix = match anchor {
+            DocAnchor::Module => "//!",
+            DocAnchor::Item { .. } => "///",
+        };
+
+        output
+            .lines()
+            .map(str::trim)
+            .filter_map(|line| {
+                line.strip_prefix("//!").or_else(|| {
+                    line.strip_prefix("///")
+                        .filter(|text| !text.starts_with('/'))
+                })
+            })
+            .map(|text| format!("{}{}", prefix, text))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn extract_rationale(&self, output: &str) -> String {
+        outside_code_fences(output)
+            .lines()
+            .filter(|line| !line.trim().starts_with("//"))
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim()
+            .to_string()
+    }
+
+    fn splice_module_docs(&self, content: &str, comments: &str) -> (String, usize, usize) {
+        splice::splice_module_docs(content, comments)
+    }
+
+    fn splice_item_docs(
+        &self,
+        content: &str,
+        line: usize,
+        name: &str,
+        indent: &str,
+        comments: &str,
+    ) -> Option<(String, usize)> {
+        splice::splice_item_docs(content, line, name, indent, comments)
+    }
+
+    fn section_separator(&self, _indent: &str) -> String {
+        "///".to_string()
+    }
+
+    fn verify(&self, original: &str, modified: &str) -> Result<(), String> {
+        verify::verify_edit(original, modified)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_comments_skips_code_comments_in_echoed_code() {
+        let anchor = DocAnchor::Item {
+            name: "add".to_string(),
+            line: 3,
+            indent: String::new(),
+        };
+        let docs = RustStrategy.extract_comments(
+            "Here you go:\n```rust\n/// Adds two numbers.\n//! Wrapping on overflow.\npub fn add(a: u8, b: u8) -> u8 {\n    // Wraps\n    a.wrapping_add(b)\n}\n//// Not docs\n```",
+            &anchor,
+        );
+
+        assert_eq!(docs, "/// Adds two numbers.\n/// Wrapping on overflow.");
+    }
+}
@@ -0,0 +1,206 @@
+use crate::ollama_client::client::DocAnchor;
+use crate::utils::config::RepoConfig;
+
+/// What Dredger needs to know about a parsed source file
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedFile {
+    /// Text of the file's existing module-level docs, if any
+    pub module_docs: Option<String>,
+    pub items: Vec<SourceItem>,
+}
+
+/// An item of a source file that can carry its own docs: a function,
+/// type, class, method, ...
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceItem {
+    /// Kind of item in the language's own terms, e.g. "fn" or "class"
+    pub kind: &'static str,
+    pub name: String,
+    /// 1-based line the item's declaration starts on, after its
+    /// attributes or decorators
+    pub line: usize,
+    /// Whitespace the declaration is indented with
+    pub indent: String,
+    /// Signature and body, as written in the file
+    pub source: String,
+    /// Text of the item's existing docs, if any
+    pub existing_docs: Option<String>,
+    /// Doc sections the language's conventions expect for this item,
+    /// e.g. "Errors" for a Rust function returning a `Result`
+    pub required_sections: Vec<&'static str>,
+    /// Parameter names, for languages whose doc tags name them, like
+    /// TSDoc's `@param`
+    pub params: Vec<String>,
+}
+
+impl SourceItem {
+    /// Required sections the existing docs don't have a `# Heading` for
+    pub fn missing_sections(&self) -> Vec<&'static str> {
+        let docs = self.existing_docs.as_deref().unwrap_or_default();
+        self.required_sections
+            .iter()
+            .filter(|section| {
+                !docs
+                    .lines()
+                    .any(|line| line.trim() == format!("# {}", section))
+            })
+            .copied()
+            .collect()
+    }
+}
+
+/// The system prompt and few-shot examples of one doc-generation request
+pub struct Prompt {
+    pub system: String,
+    pub examples: Vec<(String, String)>,
+}
+
+/// Everything Dredger does differently from one language to the next:
+/// which files it handles, how its doc comments look, what the model is
+/// asked for, and where the docs go in a file.
+///
+/// Strategies are looked up by file path in the `LanguageRegistry`.
+pub trait LanguageStrategy: Send + Sync {
+    /// Short lowercase name, e.g. "rust"
+    fn name(&self) -> &'static str;
+
+    /// Whether this strategy handles the file at `path`
+    fn matches(&self, path: &str) -> bool;
+
+    /// Finds the file's module docs and documentable items
+    fn parse(&self, content: &str) -> Result<ParsedFile, String>;
+
+    /// Whether the file has module docs a human wrote, which must be kept
+    fn has_module_docs(&self, content: &str) -> bool {
+        self.parse(content)
+            .is_ok_and(|parsed_file| parsed_file.module_docs.is_some())
+    }
+
+    /// Prompt for the docs of a whole file
+    fn module_prompt(
+        &self,
+        file_path: &str,
+        project_context: &str,
+        repo_config: &RepoConfig,
+    ) -> Prompt;
+
+    /// Prompt for the docs of a single item. With `missing_sections`, the
+    /// item is documented already and only those sections are asked for.
+    fn item_prompt(
+        &self,
+        item: &SourceItem,
+        file_path: &str,
+        project_context: &str,
+        missing_sections: Option<&[&str]>,
+        repo_config: &RepoConfig,
+    ) -> Prompt;
+
+    /// Keeps only the docs of the model's output, formatted as the doc
+    /// comments that go at `anchor`
+    fn extract_comments(&self, output: &str, anchor: &DocAnchor) -> String;
+
+    /// Whatever the model said besides the docs
+    fn extract_rationale(&self, output: &str) -> String;
+
+    /// Puts module docs into `content`, replacing any the file has.
+    ///
+    /// Returns the new content along with how many doc lines were added
+    /// and how many existing doc lines were replaced.
+    fn splice_module_docs(&self, content: &str, comments: &str) -> (String, usize, usize);
+
+    /// Puts the docs of the item `name`, declared on `line`, into
+    /// `content`. Returns the new content and how many doc lines were
+    /// added, or `None` if `line` doesn't declare `name` anymore.
+    fn splice_item_docs(
+        &self,
+        content: &str,
+        line: usize,
+        name: &str,
+        indent: &str,
+        comments: &str,
+    ) -> Option<(String, usize)>;
+
+    /// Docs to put between an item's existing docs and the sections
+    /// added to them, like an empty `///` line
+    fn section_separator(&self, indent: &str) -> String;
+
+    /// Whether the files of a directory share one set of module docs,
+    /// like a Go package's comment, so only one of them needs any
+    fn shares_module_docs(&self) -> bool {
+        false
+    }
+
+    /// Whether the module docs of the file at `path` describe its whole
+    /// directory, like a Rust `mod.rs`, so its prompt gets the summaries
+    /// of everything in that directory
+    fn describes_directory(&self, _path: &str) -> bool {
+        false
+    }
+
+    /// Whether generated docs get checked against the crate's items the
+    /// way rustdoc would: examples compiled, intra-doc links resolved
+    fn checks_intra_doc_links(&self) -> bool {
+        false
+    }
+
+    /// The public items doc coverage counts (see
+    /// `coverage::compute_coverage`): by default, the items `parse` finds
+    fn coverage_items(&self, content: &str) -> Result<Vec<SourceItem>, String> {
+        self.parse(content).map(|parsed_file| parsed_file.items)
+    }
+
+    /// Checks that `modified` only differs from `original` in its docs
+    fn verify(&self, original: &str, modified: &str) -> Result<(), String>;
+}
+
+/// `verify` for languages that check a file by its tokens: `modified`
+/// has to tokenize, and `code`, its tokens minus comments, has to be
+/// the same as `original`'s. An `original` that doesn't tokenize has
+/// nothing to compare against, so anything goes.
+pub fn verify_code_tokens<T: PartialEq>(
+    original: &str,
+    modified: &str,
+    code: impl Fn(&str) -> Result<Vec<T>, String>,
+) -> Result<(), String> {
+    let Ok(original_code) = code(original) else {
+        return Ok(());
+    };
+    let modified_code = code(modified).map_err(|e| format!("no longer tokenizes: {}", e))?;
+    if original_code != modified_code {
+        return Err("changed code outside of doc comments".to_string());
+    }
+
+    Ok(())
+}
+
+/// The model's text without any fenced code blocks, fences included,
+/// so the code it echoes back doesn't end up in a rationale. An
+/// unclosed fence drops everything after it.
+pub fn outside_code_fences(output: &str) -> String {
+    let mut in_fence = false;
+    output
+        .lines()
+        .filter(|line| {
+            if line.trim_start().starts_with("```") {
+                in_fence = !in_fence;
+                return false;
+            }
+            !in_fence
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outside_code_fences_drops_fenced_code() {
+        let output = "Here are the docs:\n```rust\n/// Adds.\npub fn add() {}\n```\nThey cover `add`.\n```\nunclosed";
+        assert_eq!(
+            outside_code_fences(output),
+            "Here are the docs:\nThey cover `add`."
+        );
+    }
+}
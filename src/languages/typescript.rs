@@ -0,0 +1,1311 @@
+use crate::languages::lexer::{Brackets, Lexer};
+use crate::languages::strategy::{
+    outside_code_fences, verify_code_tokens, LanguageStrategy, ParsedFile, Prompt, SourceItem,
+};
+use crate::ollama_client::client::DocAnchor;
+use crate::utils::config::RepoConfig;
+
+/// TypeScript and JavaScript: `/** ... */` TSDoc (or JSDoc) blocks above
+/// exported functions, classes, interfaces and React components
+pub struct TypeScriptStrategy;
+
+/// Tags that make a doc comment the file's own docs
+const MODULE_TAGS: [&str; 4] = ["@packageDocumentation", "@file", "@fileoverview", "@module"];
+
+impl LanguageStrategy for TypeScriptStrategy {
+    fn name(&self) -> &'static str {
+        "typescript"
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        let is_source = [".ts", ".tsx", ".js", ".jsx", ".mjs", ".cjs"]
+            .iter()
+            .any(|extension| path.ends_with(extension));
+        is_source && !path.ends_with(".min.js")
+    }
+
+    /// Lists the top-level exported functions, classes and interfaces,
+    /// plus exported `const`s holding a function. Functions named in
+    /// PascalCase are React components.
+    fn parse(&self, content: &str) -> Result<ParsedFile, String> {
+        let tokens = tokenize(content)?;
+        let lines: Vec<&str> = content.lines().collect();
+        let code: Vec<&Token> = tokens.iter().filter(|token| !token.is_comment()).collect();
+
+        let items = declarations(&code)
+            .into_iter()
+            .map(|decl| {
+                let line = code[decl.export].line;
+                let first_line = code[decl.first].line;
+                let end_line = code[decl.last].end_line.min(lines.len());
+                let first = lines[line - 1];
+
+                SourceItem {
+                    kind: decl.kind,
+                    name: decl.name.to_string(),
+                    line,
+                    indent: first[..first.len() - first.trim_start().len()].to_string(),
+                    source: lines[first_line - 1..end_line].join("\n"),
+                    existing_docs: leading_doc_comment(&tokens, code[decl.first])
+                        .map(|comment| doc_text(comment.text)),
+                    required_sections: Vec::new(),
+                    params: decl.params,
+                }
+            })
+            .collect();
+
+        Ok(ParsedFile {
+            module_docs: module_doc_comment(&tokens).map(|comment| doc_text(comment.text)),
+            items,
+        })
+    }
+
+    fn module_prompt(
+        &self,
+        file_path: &str,
+        project_context: &str,
+        _repo_config: &RepoConfig,
+    ) -> Prompt {
+        Prompt {
+            system: format!("You are an AI that writes {} comments, using `/** ... */` blocks.
+             Given a file, write one concise file-level doc comment that explains its purpose, usage, and important details, ending with a `@packageDocumentation` tag.
+             Only write the `/** ... */` block, NOT the code.
+             This file is found at {}.\n
+             Lastly, here is a project overview to help you generate docs. DO NOT include this summary, or any variation, in your docs!: {}", doc_flavor(file_path), file_path, project_context),
+            examples: vec![
+                (
+                    "export function circleArea(radius: number): number {\n  return Math.PI * radius ** 2;\n}".to_string(),
+                    "/**\n * Geometry helpers, such as the area of a circle.\n *\n * @packageDocumentation\n */".to_string()
+                ),
+            ],
+        }
+    }
+
+    fn item_prompt(
+        &self,
+        item: &SourceItem,
+        file_path: &str,
+        project_context: &str,
+        _missing_sections: Option<&[&str]>,
+        _repo_config: &RepoConfig,
+    ) -> Prompt {
+        let flavor = doc_flavor(file_path);
+        let param_format = match flavor {
+            "JSDoc" => "`@param {type} name - Description`",
+            _ => "`@param name - Description`",
+        };
+        let tags = match item.kind {
+            "class" | "interface" => "Do NOT use `@param` or `@returns` tags.".to_string(),
+            _ if item.params.is_empty() => "It takes no parameters, so do NOT use `@param` tags. Describe what it returns with a `@returns` tag.".to_string(),
+            _ => format!("Document each parameter with a {} tag, using exactly these names: {}. Describe what it returns with a `@returns` tag.", param_format, item.params.join(", ")),
+        };
+
+        Prompt {
+            system: format!("You are an AI that writes {} comments, using `/** ... */` blocks.
+             Given a single exported {}, write a concise doc comment for it alone: a one-sentence summary, then what it does.
+             {}
+             Only write the `/** ... */` block that goes above it, NOT the code.
+             This {} is found at {}.\n
+             Lastly, here is a project overview to help you generate docs. DO NOT include this summary, or any variation, in your docs!: {}", flavor, item.kind, tags, item.kind, file_path, project_context),
+            examples: vec![
+                (
+                    "export function circleArea(radius: number): number {\n  return Math.PI * radius ** 2;\n}".to_string(),
+                    "/**\n * Computes the area of a circle.\n *\n * @param radius - The radius of the circle.\n * @returns The computed area.\n */".to_string()
+                ),
+            ],
+        }
+    }
+
+    /// Takes the first `/** ... */` block of the model's output and
+    /// re-formats it, one ` * ` line per line of text. Module docs get a
+    /// `@packageDocumentation` tag if the model left it out.
+    fn extract_comments(&self, output: &str, anchor: &DocAnchor) -> String {
+        let Some((_, inner, _)) = doc_block(output) else {
+            return String::new();
+        };
+
+        let mut lines: Vec<String> = inner
+            .lines()
+            .map(|line| {
+                let text = line.trim_start();
+                let text = text.strip_prefix('*').unwrap_or(text);
+                text.strip_prefix(' ')
+                    .unwrap_or(text)
+                    .trim_end()
+                    .to_string()
+            })
+            .collect();
+        while lines.last().is_some_and(String::is_empty) {
+            lines.pop();
+        }
+        let leading_blank = lines.iter().take_while(|line| line.is_empty()).count();
+        lines.drain(..leading_blank);
+
+        let is_module = *anchor == DocAnchor::Module;
+        if lines.is_empty() {
+            return String::new();
+        }
+        if is_module && !lines.iter().any(|line| has_module_tag(line)) {
+            lines.push(String::new());
+            lines.push("@packageDocumentation".to_string());
+        }
+        if let ([summary], false) = (lines.as_slice(), is_module) {
+            return format!("/** {} */", summary);
+        }
+
+        let body: Vec<String> = lines
+            .iter()
+            .map(|line| match line.is_empty() {
+                true => " *".to_string(),
+                false => format!(" * {}", line),
+            })
+            .collect();
+        format!("/**\n{}\n */", body.join("\n"))
+    }
+
+    fn extract_rationale(&self, output: &str) -> String {
+        let output = match doc_block(output) {
+            Some((start, _, end)) => format!("{}{}", &output[..start], &output[end..]),
+            None => output.to_string(),
+        };
+
+        outside_code_fences(&output).trim().to_string()
+    }
+
+    /// Puts the file's doc comment below a shebang and any leading plain
+    /// comments (like a license header), and above everything else,
+    /// directives like `"use client"` included. An existing file doc
+    /// comment is replaced in place.
+    fn splice_module_docs(&self, content: &str, comments: &str) -> (String, usize, usize) {
+        let Ok(tokens) = tokenize(content) else {
+            return (content.to_string(), 0, 0);
+        };
+        let docs: Vec<&str> = comments.lines().collect();
+        if docs.is_empty() {
+            return (content.to_string(), 0, 0);
+        }
+
+        let lines: Vec<&str> = content.lines().collect();
+        let mut new_lines: Vec<&str> = Vec::with_capacity(lines.len() + docs.len() + 2);
+        let lines_replaced = match module_doc_comment(&tokens) {
+            Some(existing) => {
+                let (start, end) = (existing.line, existing.end_line);
+                if lines[start - 1..end] == docs[..] {
+                    return (content.to_string(), 0, 0);
+                }
+                new_lines.extend(&lines[..start - 1]);
+                new_lines.extend(&docs);
+                new_lines.extend(&lines[end..]);
+                end - start + 1
+            }
+            None => {
+                let insert_at = tokens
+                    .iter()
+                    .take_while(|token| token.kind == TokenKind::Comment)
+                    .last()
+                    .map_or(0, |token| token.end_line);
+
+                let rest = &lines[insert_at..];
+                let blank_lines = rest
+                    .iter()
+                    .take_while(|line| line.trim().is_empty())
+                    .count();
+
+                new_lines.extend(&lines[..insert_at]);
+                if insert_at > 0 {
+                    new_lines.push("");
+                }
+                new_lines.extend(&docs);
+                if blank_lines < rest.len() {
+                    new_lines.push("");
+                }
+                new_lines.extend(&rest[blank_lines..]);
+                0
+            }
+        };
+
+        let mut new_content = new_lines.join("\n");
+        if content.ends_with('\n') || content.is_empty() {
+            new_content.push('\n');
+        }
+        (new_content, docs.len(), lines_replaced)
+    }
+
+    /// Puts the doc comment above the declaration exported on `line`,
+    /// and above its decorators, so they keep applying to it.
+    ///
+    /// `@param` tags naming parameters the declaration doesn't have are
+    /// dropped, as are all `@param` and `@returns` tags of classes and
+    /// interfaces.
+    fn splice_item_docs(
+        &self,
+        content: &str,
+        line: usize,
+        name: &str,
+        _indent: &str,
+        comments: &str,
+    ) -> Option<(String, usize)> {
+        let tokens = tokenize(content).ok()?;
+        let code: Vec<&Token> = tokens.iter().filter(|token| !token.is_comment()).collect();
+        let decl = declarations(&code)
+            .into_iter()
+            .find(|decl| code[decl.export].line == line && decl.name == name)?;
+
+        let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+        let insert_at = code[decl.first].line - 1;
+        let first = &lines[insert_at];
+        let indent = first[..first.len() - first.trim_start().len()].to_string();
+
+        let docs: Vec<String> = checked_tags(comments, &decl.params, decl.kind)
+            .iter()
+            .map(|doc_line| format!("{}{}", indent, doc_line))
+            .collect();
+        let lines_added = docs.len();
+        lines.splice(insert_at..insert_at, docs);
+
+        let mut new_content = lines.join("\n");
+        if content.ends_with('\n') {
+            new_content.push('\n');
+        }
+        Some((new_content, lines_added))
+    }
+
+    /// Doc blocks can't be extended with new sections, since items don't
+    /// get required sections
+    fn section_separator(&self, _indent: &str) -> String {
+        String::new()
+    }
+
+    /// The modified file has to still tokenize (if the original did), and
+    /// its tokens other than comments have to match the original's.
+    fn verify(&self, original: &str, modified: &str) -> Result<(), String> {
+        verify_code_tokens(original, modified, |content| {
+            Ok(tokenize(content)?
+                .iter()
+                .filter(|token| !token.is_comment())
+                .map(|token| (token.kind, token.text.to_string()))
+                .collect())
+        })
+    }
+}
+
+/// "JSDoc" for JavaScript files, which carry types in their tags, and
+/// "TSDoc" for TypeScript ones
+fn doc_flavor(file_path: &str) -> &'static str {
+    if [".js", ".jsx", ".mjs", ".cjs"]
+        .iter()
+        .any(|extension| file_path.ends_with(extension))
+    {
+        "JSDoc"
+    } else {
+        "TSDoc"
+    }
+}
+
+fn has_module_tag(text: &str) -> bool {
+    MODULE_TAGS.iter().any(|tag| text.contains(tag))
+}
+
+/// Finds the first `/** ... */` block of `text`: its start offset, its
+/// content and the offset right after it
+fn doc_block(text: &str) -> Option<(usize, &str, usize)> {
+    let start = text.find("/**")?;
+    let end = start + 3 + text[start + 3..].find("*/")?;
+    Some((start, &text[start + 3..end], end + 2))
+}
+
+/// The text of a `/** ... */` comment, without the delimiters and the
+/// `*` starting each line
+fn doc_text(comment: &str) -> String {
+    let inner = comment.trim_start_matches("/**").trim_end_matches("*/");
+    inner
+        .lines()
+        .map(|line| {
+            let text = line.trim();
+            text.strip_prefix('*').unwrap_or(text).trim()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+/// The lines of a generated doc block minus the `@param` tags that don't
+/// name one of `params`, and minus `@returns` for classes and interfaces.
+/// A dropped tag takes its continuation lines with it.
+fn checked_tags(comments: &str, params: &[String], kind: &str) -> Vec<String> {
+    let is_type = matches!(kind, "class" | "interface");
+    let mut kept = Vec::new();
+    let mut dropping = false;
+
+    for line in comments.lines() {
+        let text = line.trim();
+        let text = text.strip_prefix('*').unwrap_or(text).trim();
+        if let Some(tag) = text.strip_prefix("@param") {
+            dropping = is_type || !params.iter().any(|param| *param == param_name(tag));
+        } else if text.starts_with("@returns") || text.starts_with("@return ") {
+            dropping = is_type;
+        } else if text.starts_with('@') || text.is_empty() || text.starts_with('/') {
+            dropping = false;
+        }
+
+        if !dropping {
+            kept.push(line.trim().to_string());
+        }
+    }
+
+    // Along with the blank line that led into the dropped tags
+    while kept.len() > 2 && kept[kept.len() - 2] == "*" && kept[kept.len() - 1] == "*/" {
+        kept.remove(kept.len() - 2);
+    }
+
+    // Keep the ` */` of multi-line blocks lined up with the `/**`
+    kept.into_iter()
+        .map(|line| match line.starts_with('*') {
+            true => format!(" {}", line),
+            false => line,
+        })
+        .collect()
+}
+
+/// The parameter name of a `@param` tag, e.g. `radius` for
+/// `{number} [radius=1] - The radius`
+fn param_name(tag: &str) -> &str {
+    let mut rest = tag.trim();
+    if rest.starts_with('{') {
+        rest = rest.find('}').map_or("", |end| rest[end + 1..].trim());
+    }
+    let name = rest.split_whitespace().next().unwrap_or_default();
+    let name = name.trim_start_matches('[').trim_end_matches(']');
+    name.split('=')
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches('-')
+}
+
+/// The file's own doc comment: a `/** ... */` block with a module tag
+/// ahead of any code
+fn module_doc_comment<'a, 'b>(tokens: &'b [Token<'a>]) -> Option<&'b Token<'a>> {
+    tokens
+        .iter()
+        .take_while(|token| token.is_comment())
+        .find(|token| token.kind == TokenKind::DocComment && has_module_tag(token.text))
+}
+
+/// The doc comment right before `first`, unless it's the file's own
+fn leading_doc_comment<'a, 'b>(tokens: &'b [Token<'a>], first: &Token) -> Option<&'b Token<'a>> {
+    let index = tokens.iter().position(|token| std::ptr::eq(token, first))?;
+    let comment = tokens.get(index.checked_sub(1)?)?;
+    (comment.kind == TokenKind::DocComment && !has_module_tag(comment.text)).then_some(comment)
+}
+
+/// An exported declaration, as indexes into the file's non-comment tokens
+struct Declaration<'a> {
+    kind: &'static str,
+    name: &'a str,
+    /// The first decorator, or `export` when there are none
+    first: usize,
+    export: usize,
+    last: usize,
+    params: Vec<String>,
+}
+
+fn declarations<'a>(code: &[&Token<'a>]) -> Vec<Declaration<'a>> {
+    let mut found = Vec::new();
+    let mut depth = 0;
+
+    for (i, token) in code.iter().enumerate() {
+        match (token.kind, token.text) {
+            (TokenKind::Punct, "(" | "[" | "{") => depth += 1,
+            (TokenKind::Punct, ")" | "]" | "}") => depth -= 1,
+            (TokenKind::Name, "export") if depth == 0 => {
+                if let Some(decl) = declaration_at(code, i) {
+                    found.push(decl);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    found
+}
+
+fn text_at<'a>(code: &[&Token<'a>], i: usize) -> &'a str {
+    code.get(i).map_or("", |token| token.text)
+}
+
+fn name_at<'a>(code: &[&Token<'a>], i: usize) -> Option<&'a str> {
+    code.get(i)
+        .filter(|token| token.kind == TokenKind::Name)
+        .map(|token| token.text)
+}
+
+fn is_punct(token: &Token, text: &str) -> bool {
+    token.kind == TokenKind::Punct && token.text == text
+}
+
+/// React components are functions named in PascalCase
+fn function_kind(name: &str) -> &'static str {
+    match name.starts_with(|c: char| c.is_ascii_uppercase()) {
+        true => "component",
+        false => "function",
+    }
+}
+
+/// The declaration exported by the `export` at `code[export]`
+fn declaration_at<'a>(code: &[&Token<'a>], export: usize) -> Option<Declaration<'a>> {
+    let mut i = export + 1;
+    if text_at(code, i) == "default" {
+        i += 1;
+    }
+    while matches!(text_at(code, i), "declare" | "abstract" | "async") {
+        i += 1;
+    }
+
+    let (kind, name, params) = match text_at(code, i) {
+        "function" => {
+            i += 1;
+            if text_at(code, i) == "*" {
+                i += 1;
+            }
+            let name = name_at(code, i)?;
+            let open = (i..code.len()).find(|&j| is_punct(code[j], "("))?;
+            let kind = function_kind(name);
+            (kind, name, params(code, open, kind))
+        }
+        // `export default class extends Base {}` has no name to document
+        "class" => match name_at(code, i + 1)? {
+            "extends" | "implements" => return None,
+            name => ("class", name, Vec::new()),
+        },
+        "interface" => ("interface", name_at(code, i + 1)?, Vec::new()),
+        "const" | "let" | "var" => {
+            let name = name_at(code, i + 1)?;
+            let kind = function_kind(name);
+            let value = initializer(code, i + 2)?;
+            (kind, name, function_params(code, value, kind)?)
+        }
+        _ => return None,
+    };
+
+    Some(Declaration {
+        kind,
+        name,
+        first: decorators_start(code, export),
+        export,
+        last: statement_end(code, export),
+        params,
+    })
+}
+
+/// The index right after the `=` of a variable declaration whose name is
+/// before `i`, skipping its type annotation
+fn initializer(code: &[&Token], i: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (j, token) in code.iter().enumerate().skip(i) {
+        match (token.kind, token.text) {
+            (TokenKind::Punct, "(" | "[" | "{") => depth += 1,
+            (TokenKind::Punct, ")" | "]" | "}") => depth -= 1,
+            (TokenKind::Punct, "=") if depth == 0 => return Some(j + 1),
+            (TokenKind::Punct, ";") if depth == 0 => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Parameters of the function expression starting at `code[i]`: an
+/// arrow function, a `function` expression, or either of those wrapped
+/// in `memo(...)`, `forwardRef(...)` or `observer(...)`. `None` when the
+/// value isn't a function.
+fn function_params(code: &[&Token], mut i: usize, kind: &str) -> Option<Vec<String>> {
+    loop {
+        if text_at(code, i) == "async" {
+            i += 1;
+        }
+
+        // `React.memo(`, `forwardRef(`, ...
+        let mut callee = i;
+        while name_at(code, callee).is_some() && text_at(code, callee + 1) == "." {
+            callee += 2;
+        }
+        let is_wrapper = matches!(
+            name_at(code, callee),
+            Some("memo" | "forwardRef" | "observer")
+        ) && text_at(code, callee + 1) == "(";
+        if !is_wrapper {
+            break;
+        }
+        i = callee + 2;
+    }
+
+    match text_at(code, i) {
+        "function" => {
+            let open = (i..code.len()).find(|&j| is_punct(code[j], "("))?;
+            Some(params(code, open, kind))
+        }
+        "(" => {
+            let close = matching_close(code, i)?;
+            matches!(text_at(code, close + 1), "=>" | ":").then(|| params(code, i, kind))
+        }
+        _ => {
+            let name = name_at(code, i)?;
+            (text_at(code, i + 1) == "=>").then(|| vec![name.to_string()])
+        }
+    }
+}
+
+fn matching_close(code: &[&Token], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (j, token) in code.iter().enumerate().skip(open) {
+        match (token.kind, token.text) {
+            (TokenKind::Punct, "(" | "[" | "{") => depth += 1,
+            (TokenKind::Punct, ")" | "]" | "}") => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(j);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Names of the parameters in the list opening at `code[open]`, as
+/// `@param` tags name them. A destructured object parameter is named
+/// `props` for components and `options` otherwise, followed by its
+/// properties, e.g. `props.title`.
+fn params(code: &[&Token], open: usize, kind: &str) -> Vec<String> {
+    let Some(close) = matching_close(code, open) else {
+        return Vec::new();
+    };
+
+    // Split at top-level commas; generics like `Map<K, V>` hold commas too
+    let mut groups: Vec<&[&Token]> = Vec::new();
+    let mut depth = 0;
+    let mut start = open + 1;
+    for j in open + 1..close {
+        match (code[j].kind, code[j].text) {
+            (TokenKind::Punct, "(" | "[" | "{" | "<") => depth += 1,
+            (TokenKind::Punct, ")" | "]" | "}" | ">") => depth -= 1,
+            (TokenKind::Punct, ">>") => depth -= 2,
+            (TokenKind::Punct, ",") if depth == 0 => {
+                groups.push(&code[start..j]);
+                start = j + 1;
+            }
+            _ => {}
+        }
+    }
+    groups.push(&code[start..close]);
+
+    let mut names = Vec::new();
+    for group in groups {
+        let mut k = 0;
+        // Parameter decorators and properties, like `@Inject() private x`
+        while let Some(token) = group.get(k) {
+            if is_punct(token, "@") {
+                k += 1;
+                while group
+                    .get(k)
+                    .is_some_and(|t| t.kind == TokenKind::Name || is_punct(t, "."))
+                {
+                    k += 1;
+                }
+                if group.get(k).is_some_and(|t| is_punct(t, "(")) {
+                    k += matching_close(&group[k..], 0).map_or(1, |end| end + 1);
+                }
+            } else if matches!(
+                token.text,
+                "public" | "private" | "protected" | "readonly" | "override"
+            ) && group.get(k + 1).is_some_and(|t| t.kind == TokenKind::Name)
+            {
+                k += 1;
+            } else {
+                break;
+            }
+        }
+        if group.get(k).is_some_and(|t| is_punct(t, "...")) {
+            k += 1;
+        }
+
+        match group.get(k) {
+            Some(token) if token.kind == TokenKind::Name && token.text != "this" => {
+                names.push(token.text.to_string());
+            }
+            Some(token) if is_punct(token, "{") => {
+                let base = if kind == "component" {
+                    "props"
+                } else {
+                    "options"
+                };
+                names.push(base.to_string());
+                let end = matching_close(&group[k..], 0).map_or(group.len(), |end| k + end);
+                let mut depth = 0;
+                for m in k..end {
+                    match group[m].text {
+                        "{" | "[" | "(" => depth += 1,
+                        "}" | "]" | ")" => depth -= 1,
+                        _ => {}
+                    }
+                    let is_key = depth == 1
+                        && group[m].kind == TokenKind::Name
+                        && matches!(group[m - 1].text, "{" | "," | "...")
+                        && matches!(
+                            group.get(m + 1).map(|t| t.text),
+                            Some("," | "}" | ":" | "=")
+                        );
+                    if is_key {
+                        names.push(format!("{}.{}", base, group[m].text));
+                    }
+                }
+            }
+            Some(token) if is_punct(token, "[") => names.push("values".to_string()),
+            _ => {}
+        }
+    }
+
+    names
+}
+
+/// Where the decorators of the declaration exported at `code[export]`
+/// start, e.g. at `@Injectable()` on the line above
+fn decorators_start(code: &[&Token], export: usize) -> usize {
+    let mut start = export;
+    loop {
+        let mut i = start;
+        // Arguments, like the `({ ... })` of `@Component({ ... })`
+        if i > 0 && is_punct(code[i - 1], ")") {
+            let mut depth = 0;
+            let open = (0..i).rev().find(|&j| {
+                match code[j].text {
+                    ")" | "]" | "}" if code[j].kind == TokenKind::Punct => depth += 1,
+                    "(" | "[" | "{" if code[j].kind == TokenKind::Punct => depth -= 1,
+                    _ => {}
+                }
+                depth == 0
+            });
+            let Some(open) = open else {
+                return start;
+            };
+            i = open;
+        }
+
+        // A dotted name, like `Angular.Component`
+        if i == 0 || code[i - 1].kind != TokenKind::Name {
+            return start;
+        }
+        i -= 1;
+        while i >= 2 && is_punct(code[i - 1], ".") && code[i - 2].kind == TokenKind::Name {
+            i -= 2;
+        }
+
+        if i == 0 || !is_punct(code[i - 1], "@") {
+            return start;
+        }
+        start = i - 1;
+    }
+}
+
+/// Statements a new line can start with, ending the one before it when
+/// it has no `;`
+const STATEMENT_KEYWORDS: [&str; 12] = [
+    "export",
+    "import",
+    "const",
+    "let",
+    "var",
+    "function",
+    "class",
+    "interface",
+    "type",
+    "enum",
+    "declare",
+    "@",
+];
+
+/// The last token of the statement starting at `code[start]`
+fn statement_end(code: &[&Token], start: usize) -> usize {
+    let mut depth = 0;
+    for i in start..code.len() {
+        let token = code[i];
+        let next = code.get(i + 1);
+        let next_on_new_line = next.is_none_or(|next| next.line > token.end_line);
+
+        match (token.kind, token.text) {
+            (TokenKind::Punct, "(" | "[" | "{") => depth += 1,
+            (TokenKind::Punct, ")" | "]" | "}") => {
+                depth -= 1;
+                if depth == 0 && next_on_new_line {
+                    return i;
+                }
+            }
+            (TokenKind::Punct, ";") if depth == 0 => return i,
+            _ => {}
+        }
+
+        let next_starts_statement =
+            next.is_some_and(|next| STATEMENT_KEYWORDS.contains(&next.text));
+        if depth == 0 && next_on_new_line && next_starts_statement {
+            return i;
+        }
+    }
+    code.len().saturating_sub(1)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TokenKind {
+    Name,
+    Number,
+    String,
+    Template,
+    Regex,
+    /// A whole JSX element, from its opening `<` to its closing tag
+    Jsx,
+    Punct,
+    Comment,
+    /// A `/** ... */` comment
+    DocComment,
+}
+
+#[derive(Debug)]
+struct Token<'a> {
+    kind: TokenKind,
+    text: &'a str,
+    /// 1-based lines the token starts and ends on
+    line: usize,
+    end_line: usize,
+}
+
+impl Token<'_> {
+    fn is_comment(&self) -> bool {
+        matches!(self.kind, TokenKind::Comment | TokenKind::DocComment)
+    }
+}
+
+/// Operators longer than one character, longest first
+const OPERATORS: [&str; 34] = [
+    ">>>=", "...", "===", "!==", "**=", "<<=", ">>=", ">>>", "&&=", "||=", "??=", "=>", "==", "!=",
+    "<=", ">=", "&&", "||", "??", "?.", "++", "--", "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=",
+    "**", "<<", ">>", "::",
+];
+
+/// Words after which a `/` starts a regex and a `<` starts JSX
+const EXPRESSION_KEYWORDS: [&str; 15] = [
+    "return",
+    "typeof",
+    "instanceof",
+    "in",
+    "of",
+    "new",
+    "delete",
+    "void",
+    "throw",
+    "case",
+    "do",
+    "else",
+    "yield",
+    "await",
+    "default",
+];
+
+/// Splits JavaScript or TypeScript source into tokens, comments included.
+///
+/// Fails on what would keep the file from parsing at all: unterminated
+/// strings, templates, regexes and comments, unbalanced brackets and
+/// stray characters. Whether a `/` or `<` starts a regex or JSX element
+/// is decided from the token before it, the way parsers do; a `<` that
+/// doesn't turn out to open a well-formed element is an operator.
+fn tokenize(content: &str) -> Result<Vec<Token<'_>>, String> {
+    let mut lexer = Lexer::new(content);
+    let mut tokens = Vec::new();
+
+    // A byte order mark isn't code
+    if content.starts_with('\u{feff}') {
+        lexer.advance('\u{feff}'.len_utf8());
+    }
+    let shebang = lexer.rest();
+    if shebang.starts_with("#!") {
+        let start = lexer.pos;
+        lexer.advance(shebang.find('\n').unwrap_or(shebang.len()));
+        tokens.push(Token {
+            kind: TokenKind::Comment,
+            text: &content[start..lexer.pos],
+            line: 1,
+            end_line: 1,
+        });
+    }
+
+    let mut brackets = Brackets::default();
+    let mut prev = None;
+    while let Some(token) = lexer.next_token(prev)? {
+        if token.kind == TokenKind::Punct {
+            brackets.push(token.text, token.line)?;
+        }
+        if !token.is_comment() {
+            prev = Some((token.kind, token.text));
+        }
+        tokens.push(token);
+    }
+
+    brackets.finish()?;
+    Ok(tokens)
+}
+
+/// JavaScript and TypeScript tokens, read off a `Lexer`
+trait TypeScriptLexer<'a> {
+    fn next_token(&mut self, prev: Option<(TokenKind, &str)>) -> Result<Option<Token<'a>>, String>;
+    fn string(&mut self) -> Result<(), String>;
+    fn template(&mut self) -> Result<(), String>;
+    fn braced(&mut self) -> Result<(), String>;
+    fn regex(&mut self) -> Result<(), String>;
+    fn jsx_element(&mut self) -> Result<(), String>;
+    fn jsx_name(&mut self) -> &'a str;
+}
+
+impl<'a> TypeScriptLexer<'a> for Lexer<'a> {
+    fn next_token(&mut self, prev: Option<(TokenKind, &str)>) -> Result<Option<Token<'a>>, String> {
+        self.skip_whitespace();
+        let rest = self.rest();
+        let Some(c) = rest.chars().next() else {
+            return Ok(None);
+        };
+        let start = self.pos;
+        let line = self.line;
+
+        let kind = if rest.starts_with("//") {
+            self.advance(rest.find('\n').unwrap_or(rest.len()));
+            TokenKind::Comment
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            let end = comment
+                .find("*/")
+                .ok_or_else(|| format!("unterminated comment (line {})", line))?;
+            self.advance(end + 4);
+            match rest.starts_with("/**") && !rest.starts_with("/**/") {
+                true => TokenKind::DocComment,
+                false => TokenKind::Comment,
+            }
+        } else if c == '"' || c == '\'' {
+            self.string()?;
+            TokenKind::String
+        } else if c == '`' {
+            self.template()?;
+            TokenKind::Template
+        } else if c == '/' && expression_position(prev) {
+            self.regex()?;
+            TokenKind::Regex
+        } else if c == '<' && expression_position(prev) {
+            if self.jsx_element().is_ok() {
+                TokenKind::Jsx
+            } else {
+                (self.pos, self.line) = (start, line);
+                self.advance(1);
+                TokenKind::Punct
+            }
+        } else if c.is_alphabetic()
+            || c == '_'
+            || c == '$'
+            || (c == '#' && ident_len(&rest[1..]) > 0)
+        {
+            self.advance(c.len_utf8() + ident_len(&rest[c.len_utf8()..]));
+            TokenKind::Name
+        } else if c.is_ascii_digit()
+            || (c == '.' && rest[1..].starts_with(|c: char| c.is_ascii_digit()))
+        {
+            let mut len = 0;
+            let mut prev_char = ' ';
+            for c in rest.chars() {
+                let exponent_sign = matches!(c, '+' | '-') && matches!(prev_char, 'e' | 'E');
+                if !(c.is_ascii_alphanumeric() || c == '.' || c == '_' || exponent_sign) {
+                    break;
+                }
+                len += 1;
+                prev_char = c;
+            }
+            self.advance(len);
+            TokenKind::Number
+        } else if let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(**op)) {
+            self.advance(op.len());
+            TokenKind::Punct
+        } else if "{}()[];,<>+-*/%&|^!~?:=.@".contains(c) {
+            self.advance(1);
+            TokenKind::Punct
+        } else {
+            return Err(format!("invalid character '{}' (line {})", c, line));
+        };
+
+        Ok(Some(Token {
+            kind,
+            text: &self.src[start..self.pos],
+            line,
+            end_line: self.line,
+        }))
+    }
+
+    fn string(&mut self) -> Result<(), String> {
+        let line = self.line;
+        let quote = self.rest().as_bytes()[0];
+        self.advance(1);
+        loop {
+            match self.rest().as_bytes().first() {
+                None | Some(b'\n') => {
+                    return Err(format!("unterminated string literal (line {})", line))
+                }
+                Some(b'\\') => self.skip_escape(),
+                Some(&c) if c == quote => {
+                    self.advance(1);
+                    return Ok(());
+                }
+                _ => self.advance(self.rest().chars().next().map_or(1, char::len_utf8)),
+            }
+        }
+    }
+
+    fn template(&mut self) -> Result<(), String> {
+        let line = self.line;
+        self.advance(1);
+        loop {
+            let rest = self.rest();
+            let Some(special) = rest.find(['`', '\\', '$']) else {
+                return Err(format!("unterminated template literal (line {})", line));
+            };
+            self.advance(special);
+            let rest = self.rest();
+            if rest.starts_with('`') {
+                self.advance(1);
+                return Ok(());
+            } else if rest.starts_with('\\') {
+                self.skip_escape();
+            } else if rest.starts_with("${") {
+                self.advance(2);
+                self.braced()?;
+            } else {
+                self.advance(1);
+            }
+        }
+    }
+
+    /// Moves past the code of a `${...}` or `{...}` whose `{` was just
+    /// passed, up to and including its `}`
+    fn braced(&mut self) -> Result<(), String> {
+        let line = self.line;
+        let mut depth = 0;
+        let mut prev = Some((TokenKind::Punct, "{"));
+        loop {
+            let Some(token) = self.next_token(prev)? else {
+                return Err(format!("'{{' was never closed (line {})", line));
+            };
+            match (token.kind, token.text) {
+                (TokenKind::Punct, "{") => depth += 1,
+                (TokenKind::Punct, "}") if depth == 0 => return Ok(()),
+                (TokenKind::Punct, "}") => depth -= 1,
+                _ => {}
+            }
+            if !token.is_comment() {
+                prev = Some((token.kind, token.text));
+            }
+        }
+    }
+
+    fn regex(&mut self) -> Result<(), String> {
+        let line = self.line;
+        let mut in_class = false;
+        self.advance(1);
+        loop {
+            match self.rest().as_bytes().first() {
+                None | Some(b'\n') => {
+                    return Err(format!("unterminated regular expression (line {})", line))
+                }
+                Some(b'\\') => {
+                    self.skip_escape();
+                    continue;
+                }
+                Some(b'[') => in_class = true,
+                Some(b']') => in_class = false,
+                Some(b'/') if !in_class => {
+                    self.advance(1);
+                    self.advance(ident_len(self.rest()));
+                    return Ok(());
+                }
+                _ => {}
+            }
+            self.advance(self.rest().chars().next().map_or(1, char::len_utf8));
+        }
+    }
+
+    /// Moves past a JSX element or fragment starting at the `<` here
+    fn jsx_element(&mut self) -> Result<(), String> {
+        self.advance(1);
+        self.skip_whitespace();
+        let name = self.jsx_name();
+        if name.is_empty() && !self.rest().starts_with('>') {
+            return Err("not a JSX element".to_string());
+        }
+
+        // Attributes
+        loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+            if rest.starts_with("/>") {
+                self.advance(2);
+                return Ok(());
+            } else if rest.starts_with('>') {
+                self.advance(1);
+                break;
+            } else if rest.starts_with('{') {
+                self.advance(1);
+                self.braced()?;
+                continue;
+            }
+
+            if self.jsx_name().is_empty() {
+                return Err("not a JSX attribute".to_string());
+            }
+            self.skip_whitespace();
+            if !self.rest().starts_with('=') {
+                continue;
+            }
+            self.advance(1);
+            self.skip_whitespace();
+            let rest = self.rest();
+            match rest.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let end = rest[1..]
+                        .find(quote)
+                        .ok_or_else(|| "unterminated JSX attribute".to_string())?;
+                    self.advance(end + 2);
+                }
+                Some('{') => {
+                    self.advance(1);
+                    self.braced()?;
+                }
+                Some('<') => self.jsx_element()?,
+                _ => return Err("not a JSX attribute value".to_string()),
+            }
+        }
+
+        // Children, up to the closing tag
+        loop {
+            let rest = self.rest();
+            match rest.chars().next() {
+                None => return Err("unclosed JSX element".to_string()),
+                Some('{') => {
+                    self.advance(1);
+                    self.braced()?;
+                }
+                Some('<') if rest[1..].trim_start().starts_with('/') => {
+                    self.advance(1);
+                    self.skip_whitespace();
+                    self.advance(1);
+                    self.skip_whitespace();
+                    let closing = self.jsx_name();
+                    self.skip_whitespace();
+                    if closing != name || !self.rest().starts_with('>') {
+                        return Err("mismatched JSX closing tag".to_string());
+                    }
+                    self.advance(1);
+                    return Ok(());
+                }
+                Some('<') => self.jsx_element()?,
+                Some(_) => self.advance(rest.find(['<', '{']).unwrap_or(rest.len())),
+            }
+        }
+    }
+
+    /// Moves past a tag or attribute name, like `Foo.Bar` or `aria-label`
+    fn jsx_name(&mut self) -> &'a str {
+        let rest = self.rest();
+        let starts_name = rest.starts_with(|c: char| c.is_alphabetic() || c == '_' || c == '$');
+        let len = match starts_name {
+            true => rest
+                .find(|c: char| !(c.is_alphanumeric() || matches!(c, '_' | '$' | '.' | ':' | '-')))
+                .unwrap_or(rest.len()),
+            false => 0,
+        };
+        self.advance(len);
+        &rest[..len]
+    }
+}
+
+fn ident_len(text: &str) -> usize {
+    text.find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+        .unwrap_or(text.len())
+}
+
+/// Whether an expression can start after `prev`, making a `/` a regex
+/// rather than a division, and a `<` JSX rather than a comparison
+fn expression_position(prev: Option<(TokenKind, &str)>) -> bool {
+    match prev {
+        None => true,
+        Some((TokenKind::Punct, text)) => !matches!(text, ")" | "]" | "}"),
+        Some((TokenKind::Name, text)) => EXPRESSION_KEYWORDS.contains(&text),
+        Some(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT: &str = "\
+// Copyright (c) Example
+\"use client\";
+
+import { useState } from \"react\";
+
+const PATTERN = /[/\\]]+/g;
+const half = (a + b) / 2;
+
+/** Existing docs. */
+export interface Props {
+  title: string;
+}
+
+@Injectable({ providedIn: \"root\" })
+export class Store<T> {
+  private items: Map<string, T> = new Map();
+}
+
+export async function load<T>(path: string, options?: Map<string, T>): Promise<T> {
+  return fetch(`/api/${path.replace(/\\//g, \"\")}?q=${JSON.stringify({ a: 1 })}`);
+}
+
+export const Button = React.memo(({ title, onClick }: Props) => {
+  const [count] = useState<number>(0);
+  return (
+    <div className=\"button\" onClick={() => onClick(count < 1)}>
+      {title} isn't {count > 1 ? <b>many</b> : <></>}
+    </div>
+  );
+});
+
+export const LIMIT = 3;
+export default function* ids(...rest: number[]) {}
+";
+
+    #[test]
+    fn test_parse_finds_exported_declarations() {
+        let parsed = TypeScriptStrategy.parse(CONTENT).unwrap();
+        let summary: Vec<(&str, &str, usize, Vec<&str>)> = parsed
+            .items
+            .iter()
+            .map(|item| {
+                let params = item.params.iter().map(String::as_str).collect();
+                (item.kind, item.name.as_str(), item.line, params)
+            })
+            .collect();
+
+        assert_eq!(parsed.module_docs, None);
+        assert_eq!(
+            summary,
+            vec![
+                ("interface", "Props", 10, vec![]),
+                ("class", "Store", 15, vec![]),
+                ("function", "load", 19, vec!["path", "options"]),
+                (
+                    "component",
+                    "Button",
+                    23,
+                    vec!["props", "props.title", "props.onClick"]
+                ),
+                ("function", "ids", 33, vec!["rest"]),
+            ]
+        );
+        assert_eq!(
+            parsed.items[0].existing_docs.as_deref(),
+            Some("Existing docs.")
+        );
+        assert!(parsed.items[1]
+            .source
+            .starts_with("@Injectable({ providedIn: \"root\" })\nexport class Store<T> {"));
+        assert!(parsed.items[3].source.ends_with("  );\n});"));
+    }
+
+    #[test]
+    fn test_parse_skips_anonymous_default_exports() {
+        for content in [
+            "export default class extends Base {}\n",
+            "export default class implements Runnable {}\n",
+            "export default function () {}\n",
+            "export default async function* () {}\n",
+        ] {
+            let parsed = TypeScriptStrategy.parse(content).unwrap();
+            assert!(parsed.items.is_empty(), "found items in {}", content);
+        }
+
+        let parsed = TypeScriptStrategy
+            .parse("export default class App extends Base implements Runnable {}\n")
+            .unwrap();
+        assert_eq!(parsed.items.len(), 1);
+        assert_eq!(
+            (parsed.items[0].kind, parsed.items[0].name.as_str()),
+            ("class", "App")
+        );
+    }
+
+    #[test]
+    fn test_splice_docs_above_decorators() {
+        let docs = TypeScriptStrategy.extract_comments(
+            "Sure:\n```ts\n/**\n * Holds items.\n *\n * @param items - The items.\n * @returns A store.\n */\n```",
+            &DocAnchor::Item {
+                name: "Store".to_string(),
+                line: 15,
+                indent: String::new(),
+            },
+        );
+        let (with_class_docs, added) = TypeScriptStrategy
+            .splice_item_docs(CONTENT, 15, "Store", "", &docs)
+            .unwrap();
+        assert_eq!(added, 3);
+        assert!(with_class_docs.contains(
+            "}\n\n/**\n * Holds items.\n */\n@Injectable({ providedIn: \"root\" })\nexport class Store<T> {"
+        ));
+
+        let docs = "/**\n * Loads it.\n *\n * @param path - The path.\n * @param {string} [verbose=false] - Made up,\n *   over two lines.\n * @returns The value.\n */";
+        let (with_fn_docs, _) = TypeScriptStrategy
+            .splice_item_docs(&with_class_docs, 22, "load", "", docs)
+            .unwrap();
+        assert!(with_fn_docs.contains(
+            "/**\n * Loads it.\n *\n * @param path - The path.\n * @returns The value.\n */\nexport async function load"
+        ));
+
+        let module_docs =
+            TypeScriptStrategy.extract_comments("/** Buttons and stores. */", &DocAnchor::Module);
+        let (with_module_docs, added, replaced) =
+            TypeScriptStrategy.splice_module_docs(&with_fn_docs, &module_docs);
+        assert_eq!((added, replaced), (5, 0));
+        assert!(with_module_docs.starts_with(
+            "// Copyright (c) Example\n\n/**\n * Buttons and stores.\n *\n * @packageDocumentation\n */\n\n\"use client\";"
+        ));
+        assert_eq!(
+            TypeScriptStrategy.verify(CONTENT, &with_module_docs),
+            Ok(())
+        );
+        assert_eq!(
+            TypeScriptStrategy
+                .parse(&with_module_docs)
+                .unwrap()
+                .module_docs
+                .as_deref(),
+            Some("Buttons and stores.\n\n@packageDocumentation")
+        );
+    }
+
+    #[test]
+    fn test_verify_rejects_broken_or_changed_code() {
+        let unterminated = CONTENT.replace("/** Existing docs. */", "/** Existing docs.");
+        assert!(TypeScriptStrategy
+            .verify(CONTENT, &unterminated)
+            .unwrap_err()
+            .starts_with("no longer tokenizes"));
+
+        let changed = CONTENT.replace(
+            "export const LIMIT = 3;",
+            "/** Docs */\nexport const LIMIT = 4;",
+        );
+        assert_eq!(
+            TypeScriptStrategy.verify(CONTENT, &changed),
+            Err("changed code outside of doc comments".to_string())
+        );
+    }
+}
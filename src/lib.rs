@@ -1,4 +1,6 @@
 pub mod core;
 pub mod github_client;
+pub mod languages;
+pub mod local_client;
 pub mod ollama_client;
 pub mod utils;
@@ -0,0 +1,278 @@
+use crate::github_client::data::{FileChange, RepoNode};
+use crate::utils::errors::DredgerError;
+use crate::utils::tokens::{count_tokens, load_tokenizer};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Runs `git` in `repo_root`, returning its trimmed stdout
+fn git(repo_root: &Path, args: &[&str]) -> Result<String, DredgerError> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_root)
+        .args(args)
+        .output()
+        .map_err(DredgerError::IoError)?;
+
+    if !output.status.success() {
+        return Err(DredgerError::OtherError(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Lists the files of a local checkout as (path, blob sha) pairs.
+///
+/// Inside a git repo this is whatever git tracks, so ignored files
+/// (build output, .env, ...) are skipped. Anywhere else the directory
+/// is walked, skipping hidden entries and `target/`, and no SHAs are known.
+fn list_files(repo_root: &Path) -> Result<Vec<(String, String)>, DredgerError> {
+    if let Ok(index) = git(repo_root, &["ls-files", "--stage"]) {
+        // "<mode> <sha> <stage>\t<path>"
+        return Ok(index
+            .lines()
+            .filter_map(|line| {
+                let (meta, path) = line.split_once('\t')?;
+                let sha = meta.split_whitespace().nth(1)?;
+                Some((path.to_string(), sha.to_string()))
+            })
+            .collect());
+    }
+
+    let mut files = Vec::new();
+    let mut stack = vec![repo_root.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        for entry in fs::read_dir(&dir).map_err(DredgerError::IoError)? {
+            let path = entry.map_err(DredgerError::IoError)?.path();
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            if name.starts_with('.') || name == "target" {
+                continue;
+            }
+
+            if path.is_dir() {
+                stack.push(path);
+            } else if let Ok(relative) = path.strip_prefix(repo_root) {
+                files.push((relative.to_string_lossy().to_string(), String::new()));
+            }
+        }
+    }
+    files.sort();
+
+    Ok(files)
+}
+
+/// Same as `github_client::client::read_repo`, but for a checkout on
+/// disk: reads the files under `repo_root` into a `RepoNode` tree,
+/// counting the language model tokens of each.
+pub fn read_repo(repo_root: &Path, tokenizer_path: &str) -> Result<RepoNode, Box<DredgerError>> {
+    let tokenizer =
+        load_tokenizer(tokenizer_path).map_err(|e| Box::new(DredgerError::TokenizerError(e)))?;
+
+    let mut files = Vec::new();
+    for (path, sha) in list_files(repo_root).map_err(Box::new)? {
+        // Binary files can't be documented anyway
+        let Ok(content) = fs::read_to_string(repo_root.join(&path)) else {
+            continue;
+        };
+        let token_count = count_tokens(&content, &tokenizer)
+            .map_err(|e| Box::new(DredgerError::TokenizerError(e)))?;
+
+        files.push(RepoNode::File {
+            name: path.rsplit('/').next().unwrap_or(&path).to_string(),
+            path,
+            sha,
+            content,
+            token_count,
+            summary: None,
+        });
+    }
+
+    Ok(build_tree("", files))
+}
+
+/// Nests flat file nodes into directory nodes, the way GitHub's
+/// contents API lays them out
+fn build_tree(dir_path: &str, files: Vec<RepoNode>) -> RepoNode {
+    let mut children = Vec::new();
+    let mut subdirs: Vec<(String, Vec<RepoNode>)> = Vec::new();
+
+    for file in files {
+        let RepoNode::File { path, .. } = &file else {
+            continue;
+        };
+        let relative = path
+            .strip_prefix(dir_path)
+            .unwrap_or(path)
+            .trim_start_matches('/');
+
+        match relative.split_once('/') {
+            None => children.push(file),
+            Some((subdir, _)) => {
+                let subdir_path = if dir_path.is_empty() {
+                    subdir.to_string()
+                } else {
+                    format!("{}/{}", dir_path, subdir)
+                };
+                match subdirs.iter_mut().find(|(path, _)| *path == subdir_path) {
+                    Some((_, subdir_files)) => subdir_files.push(file),
+                    None => subdirs.push((subdir_path, vec![file])),
+                }
+            }
+        }
+    }
+
+    for (subdir_path, subdir_files) in subdirs {
+        children.push(build_tree(&subdir_path, subdir_files));
+    }
+
+    RepoNode::Directory {
+        name: dir_path.to_string(),
+        path: dir_path.to_string(),
+        token_count: children.iter().map(|child| child.token_count()).sum(),
+        summary: None,
+        children,
+    }
+}
+
+/// Writes each change into its file under `repo_root`
+pub fn write_changes(
+    repo_root: &Path,
+    changes: &[FileChange],
+) -> Result<Vec<PathBuf>, DredgerError> {
+    let mut written = Vec::new();
+    for change in changes {
+        let path = repo_root.join(&change.path);
+        fs::write(&path, &change.content).map_err(DredgerError::IoError)?;
+        written.push(path);
+    }
+
+    Ok(written)
+}
+
+/// SHA of the commit checked out in `repo_root`
+pub fn head_sha(repo_root: &Path) -> Result<String, DredgerError> {
+    git(repo_root, &["rev-parse", "HEAD"])
+}
+
+/// Creates and checks out `branch`, starting from the current HEAD
+pub fn create_branch(repo_root: &Path, branch: &str) -> Result<(), DredgerError> {
+    git(repo_root, &["checkout", "-b", branch]).map(|_| ())
+}
+
+/// Commits the changed files as the given author, returning the new
+/// commit's SHA. Only `changes` go into the commit; anything else the
+/// working tree or the index holds, staged changes included, is left
+/// alone.
+pub fn commit_changes(
+    repo_root: &Path,
+    changes: &[FileChange],
+    message: &str,
+    author_name: &str,
+    author_email: &str,
+) -> Result<String, DredgerError> {
+    let mut add_args = vec!["add", "--"];
+    add_args.extend(changes.iter().map(|change| change.path.as_str()));
+    git(repo_root, &add_args)?;
+
+    // Author and committer are both the configured identity, so this works
+    // even where git has no user configured
+    let name_config = format!("user.name={}", author_name);
+    let email_config = format!("user.email={}", author_email);
+    // `--only` keeps whatever the user had staged out of the commit
+    let mut commit_args = vec![
+        "-c",
+        &name_config,
+        "-c",
+        &email_config,
+        "commit",
+        "--only",
+        "-m",
+        message,
+        "--",
+    ];
+    commit_args.extend(changes.iter().map(|change| change.path.as_str()));
+    git(repo_root, &commit_args)?;
+
+    head_sha(repo_root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str) -> RepoNode {
+        RepoNode::File {
+            name: path.rsplit('/').next().unwrap().to_string(),
+            path: path.to_string(),
+            sha: String::new(),
+            content: String::new(),
+            token_count: 1,
+            summary: None,
+        }
+    }
+
+    #[test]
+    fn test_commit_changes_leaves_staged_files_out() {
+        let repo = tempfile::TempDir::new().unwrap();
+        let root = repo.path();
+        let identity = ["-c", "user.name=Test", "-c", "user.email=test@example.com"];
+        git(root, &["init", "-q"]).unwrap();
+        fs::write(root.join("lib.rs"), "pub fn a() {}\n").unwrap();
+        fs::write(root.join("other.rs"), "pub fn b() {}\n").unwrap();
+        git(root, &["add", "."]).unwrap();
+        git(
+            root,
+            &[&identity[..], &["commit", "-q", "-m", "Initial"]].concat(),
+        )
+        .unwrap();
+
+        // Something the user staged before running Dredger
+        fs::write(root.join("other.rs"), "pub fn c() {}\n").unwrap();
+        git(root, &["add", "other.rs"]).unwrap();
+
+        let content = "/// Docs.\npub fn a() {}\n".to_string();
+        fs::write(root.join("lib.rs"), &content).unwrap();
+        let changes = vec![FileChange {
+            path: "lib.rs".to_string(),
+            content,
+            base_sha: None,
+        }];
+        commit_changes(root, &changes, "Add docs", "Dredger", "dredger@example.com").unwrap();
+
+        assert_eq!(
+            git(root, &["show", "--name-only", "--format=", "HEAD"]).unwrap(),
+            "lib.rs"
+        );
+        assert_eq!(
+            git(root, &["diff", "--cached", "--name-only"]).unwrap(),
+            "other.rs"
+        );
+    }
+
+    #[test]
+    fn test_build_tree_nests_directories() {
+        let tree = build_tree(
+            "",
+            vec![
+                file("Cargo.toml"),
+                file("src/lib.rs"),
+                file("src/core/mod.rs"),
+            ],
+        );
+
+        assert_eq!(tree.token_count(), 3);
+        assert_eq!(
+            tree.to_string(),
+            "📁  () - Token count=3\n\
+             \x20 📄 Cargo.toml (Cargo.toml) - Token count=1\n\
+             \x20 📁 src (src) - Token count=2\n\
+             \x20   📄 lib.rs (src/lib.rs) - Token count=1\n\
+             \x20   📁 src/core (src/core) - Token count=1\n\
+             \x20     📄 mod.rs (src/core/mod.rs) - Token count=1\n"
+        );
+    }
+}
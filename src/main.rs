@@ -2,10 +2,13 @@ use clap::{Arg, Command};
 use colored::*;
 use dotenv::dotenv;
 use dredger::core;
+use dredger::core::actions::DredgeOutcome;
+use dredger::core::plan::ActionPlan;
 use dredger::github_client::client as github_client;
 use dredger::utils::cli::{get_token_from_env, setup_token};
+use dredger::utils::config::{DredgerConfig, OutputMode};
+use std::path::PathBuf;
 use std::{env, process::exit};
-use tokio;
 
 // TODO: Constantize/enum-ize the environments (prod, test) and .env file paths
 fn load_env() {
@@ -34,17 +37,168 @@ async fn main() {
                 .help("Run in quiet mode (minimal output)")
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("dry-run")
+                .long("dry-run")
+                .help("Run the whole pipeline, but only print what would be written")
+                .action(clap::ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .help("With --dry-run, print the plan as JSON")
+                .action(clap::ArgAction::SetTrue)
+                .requires("dry-run"),
+        )
+        .arg(
+            Arg::new("skip-llm")
+                .long("skip-llm")
+                .help("With --dry-run, don't call the language model")
+                .action(clap::ArgAction::SetTrue)
+                .requires("dry-run"),
+        )
+        .arg(
+            Arg::new("review")
+                .long("review")
+                .value_name("PR_NUMBER")
+                .help("Post docs as suggestions on an open pull request instead of opening a PR")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("local")
+                .long("local")
+                .value_name("PATH")
+                .help("Dredge a local checkout and write the docs straight into its files")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("patch-dir")
+                .long("patch-dir")
+                .value_name("DIR")
+                .help("Write the docs as patch files into DIR instead of opening a PR")
+                .takes_value(true),
+        )
+        .subcommand(
+            Command::new("architecture")
+                .about("Write an ARCHITECTURE.md describing the repo's layout and subsystems")
+                .arg(
+                    Arg::new("output")
+                        .long("output")
+                        .value_name("FILE")
+                        .help("Where to write it, by default ARCHITECTURE.md in the --local checkout or the current directory")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            Command::new("cleanup")
+                .about("Close superseded Dredger PRs and delete stale Dredger branches")
+                .arg(
+                    Arg::new("days")
+                        .long("days")
+                        .value_name("N")
+                        .help("Only delete branches whose last commit is older than N days")
+                        .takes_value(true)
+                        .default_value("14"),
+                ),
+        )
         .get_matches();
 
-    let quiet = matches.get_flag("quiet");
+    let dry_run = matches.get_flag("dry-run");
+    let json = matches.get_flag("json");
+    let skip_llm = matches.get_flag("skip-llm");
+    // Keep stdout clean for the JSON plan
+    let quiet = matches.get_flag("quiet") || json;
+
+    // Belt and braces: besides never calling them, make the GitHub client
+    // refuse any write request for the rest of the run
+    github_client::set_dry_run(dry_run);
+    let review_pr_number = matches.value_of("review").map(|number| {
+        number.parse::<u64>().unwrap_or_else(|_| {
+            eprintln!("Error: --review expects a pull request number.");
+            exit(1);
+        })
+    });
 
     if !quiet {
         println!("{}", "\nRunning Dredger...\n".bold().cyan());
     }
 
+    // TODO: Implement multiple models, update this based on selected open source model
+    let tokenizer_path = "tokenizers/llama.json".to_string(); // or "deepseek-tokenizer.json"
+
+    let config = DredgerConfig::load().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        exit(1);
+    });
+
+    let architecture_matches = matches.subcommand_matches("architecture");
+    let architecture_output = architecture_matches
+        .and_then(|architecture_matches| architecture_matches.value_of("output"))
+        .map(PathBuf::from);
+
+    // A local checkout needs no GitHub token, so it skips the token setup
+    if let Some(local_path) = matches.value_of("local") {
+        if architecture_matches.is_some() {
+            let written = core::actions::architecture_local_repo(
+                quiet,
+                PathBuf::from(local_path),
+                tokenizer_path,
+                architecture_output,
+                dry_run,
+            )
+            .await
+            .unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                exit(1);
+            });
+
+            print_architecture(written);
+            return;
+        }
+
+        let mut repo_config = config.defaults.clone();
+        if let Some(patch_dir) = matches.value_of("patch-dir") {
+            repo_config.output = OutputMode::Patch;
+            repo_config.patch.dir = patch_dir.to_string();
+        }
+
+        if dry_run {
+            let plan = core::actions::plan_local_repo(
+                PathBuf::from(local_path),
+                tokenizer_path,
+                &repo_config,
+                skip_llm,
+            )
+            .await
+            .unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                exit(1);
+            });
+
+            print_plan(&plan, json);
+            return;
+        }
+
+        let outcome = core::actions::dredge_local_repo(
+            quiet,
+            PathBuf::from(local_path),
+            tokenizer_path,
+            &repo_config,
+        )
+        .await
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            exit(1);
+        });
+
+        print_outcome(outcome);
+        return;
+    }
+
     loop {
         // Check for existing GitHub token setup
-        if let Err(_) = get_token_from_env(None) {
+        if get_token_from_env(None).is_err() {
             if quiet {
                 eprintln!("Error: No valid GitHub token found.");
                 exit(1);
@@ -54,7 +208,7 @@ async fn main() {
         }
 
         // Validate token
-        if let Err(_) = github_client::validate_token().await {
+        if github_client::validate_token().await.is_err() {
             if quiet {
                 eprintln!("Error: Invalid GitHub token.");
                 exit(1);
@@ -84,12 +238,158 @@ async fn main() {
     let repo_owner = "nickagliano".to_string();
     let repo_name = "dredger".to_string();
 
-    // TODO: Implement multiple models, update this based on selected open source model
-    let tokenizer_path = "tokenizers/llama.json".to_string(); // or "deepseek-tokenizer.json"
+    if architecture_matches.is_some() {
+        let written = core::actions::architecture_repo(
+            quiet,
+            repo_owner,
+            repo_name,
+            tokenizer_path,
+            architecture_output.unwrap_or_else(|| PathBuf::from("ARCHITECTURE.md")),
+            dry_run,
+        )
+        .await
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            exit(1);
+        });
+
+        print_architecture(written);
+        return;
+    }
 
-    core::actions::dredge_repo(quiet, repo_owner, repo_name, tokenizer_path)
+    if let Some(cleanup_matches) = matches.subcommand_matches("cleanup") {
+        let max_age_days = cleanup_matches
+            .value_of("days")
+            .and_then(|days| days.parse::<i64>().ok())
+            .unwrap_or_else(|| {
+                eprintln!("Error: --days expects a number of days.");
+                exit(1);
+            });
+
+        let repo_config = config.for_repo(&repo_owner, &repo_name);
+        core::actions::cleanup_repo(
+            quiet,
+            repo_owner,
+            repo_name,
+            &repo_config,
+            max_age_days,
+            dry_run,
+        )
+        .await
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            exit(1);
+        });
+        return;
+    }
+
+    let mut repo_config = config.for_repo(&repo_owner, &repo_name);
+
+    if let Some(pr_number) = review_pr_number {
+        let review_url = core::actions::review_pr(
+            quiet,
+            repo_owner,
+            repo_name,
+            pr_number,
+            tokenizer_path,
+            &repo_config,
+            dry_run,
+        )
         .await
-        .unwrap();
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            exit(1);
+        });
+
+        if let Some(review_url) = review_url {
+            println!("{} {}", "Review posted:".bold().green(), review_url);
+        }
+        return;
+    }
+
+    if let Some(patch_dir) = matches.value_of("patch-dir") {
+        repo_config.output = OutputMode::Patch;
+        repo_config.patch.dir = patch_dir.to_string();
+    }
+
+    if dry_run {
+        let plan = core::actions::plan_repo(
+            repo_owner,
+            repo_name,
+            tokenizer_path,
+            &repo_config,
+            skip_llm,
+        )
+        .await
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            exit(1);
+        });
+
+        print_plan(&plan, json);
+        return;
+    }
+
+    let outcome =
+        core::actions::dredge_repo(quiet, repo_owner, repo_name, tokenizer_path, &repo_config)
+            .await
+            .unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                exit(1);
+            });
+
+    print_outcome(outcome);
+}
+
+fn print_plan(plan: &ActionPlan, json: bool) {
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(plan).expect("Failed to serialize plan")
+        );
+    } else {
+        println!("{}", plan);
+    }
+}
+
+fn print_architecture(written: Option<PathBuf>) {
+    if let Some(path) = written {
+        println!(
+            "{} {}",
+            "Architecture written to".bold().green(),
+            path.display()
+        );
+    }
+}
+
+fn print_outcome(outcome: DredgeOutcome) {
+    match outcome {
+        DredgeOutcome::NoChanges => {}
+        DredgeOutcome::PullRequest {
+            url,
+            metadata_failures,
+        } => {
+            println!("{} {}", "Pull request opened:".bold().green(), url);
+            for failure in metadata_failures {
+                println!("  {} {}", "Warning:".yellow(), failure);
+            }
+        }
+        DredgeOutcome::Patches(paths) => {
+            println!("{}", "Patches written:".bold().green());
+            for path in paths {
+                println!("  {}", path.display());
+            }
+        }
+        DredgeOutcome::Applied { files, commit } => {
+            println!("{}", "Docs written to:".bold().green());
+            for path in files {
+                println!("  {}", path.display());
+            }
+            if let Some(commit) = commit {
+                println!("{} {}", "Committed as".bold().green(), commit);
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -1,8 +1,21 @@
+use crate::core::summaries;
 use crate::github_client::data::RepoNode;
+use crate::languages::registry;
+use crate::languages::strategy::Prompt;
+use crate::utils::config::{ExistingDocs, RepoConfig};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::error::Error;
 
+/// Model every prompt is sent to
+// TODO: Implement multiple models, make this configurable
+pub const MODEL: &str = "llama3.1";
+
+/// Bumped whenever the doc-generation prompt changes, so generated docs
+/// can be traced back to the prompt that produced them
+pub const PROMPT_VERSION: &str = "1";
+
 // TODO: Use this! Keep track of context window size by model, and current prompt. Actually use tokenizer.
 // const MAX_TOKENS: usize = 128000; // Estimated... (maybe set this lower, keep a buffer..)
 
@@ -19,43 +32,34 @@ struct OllamaChunk {
     response: Option<String>, // Some chunks might not contain "response"
 }
 
+/// Where in its file a `DredgerDoc` belongs
+#[derive(Debug, Clone, PartialEq)]
+pub enum DocAnchor {
+    /// `//!` docs at the top of the file
+    Module,
+    /// `///` docs for the item whose declaration starts on `line`
+    /// (1-based, after its attributes)
+    Item {
+        name: String,
+        line: usize,
+        indent: String,
+    },
+}
+
 #[derive(Debug)]
 pub struct DredgerDoc {
     pub file_path: String,
-    pub comments: String, // Only extracted comments
+    pub anchor: DocAnchor,
+    pub comments: String,  // Only extracted comments
+    pub rationale: String, // Whatever the model said outside of the comments
 }
 
-// FIXME: Consolidate with  query_ollama_for_project_overview, share some abstractions
-async fn query_ollama_for_doc(
-    project_context: &str,
-    file_path: &str, // FIXME: Use the file path in the prompt
-    prompt: &str,
-) -> Result<String, Box<dyn Error>> {
+/// Sends a request to Ollama and collects its streamed response
+async fn generate(req_body: &OllamaRequest) -> Result<String, Box<dyn Error>> {
     let client = Client::new();
     let url = "http://localhost:11434/api/generate";
 
-    let system_prompt = format!("You are an AI that generates Rust doc comments using `//!` style. It's very important that you use //! for comments.
-             Given a file or section of a file, write concise, idiomatic Rust documentation that explains its purpose, usage, and important details.
-             This file is found at {}.\n
-             Lastly, here is a project overview to help you generate docs. DO NOT include this summary, or any variation, in your docs!: {}", file_path, project_context);
-
-    let req_body = OllamaRequest {
-        model: "llama3.1".to_string(),
-        prompt: prompt.to_string(),
-        system: system_prompt,
-        examples: vec![
-            (
-                "fn calculate_area(radius: f64) -> f64 { std::f64::consts::PI * radius * radius }".to_string(),
-                "//! Computes the area of a circle.\n//! \n//! # Arguments\n//! * `radius` - The radius of the circle.\n//! \n//! # Returns\n//! The computed area.".to_string()
-            ),
-            (
-                "struct Config { timeout: u32, verbose: bool }".to_string(),
-                "//! Holds configuration settings for the application.\n//! \n//! Includes parameters for timeout and verbosity.".to_string()
-            )
-        ],
-    };
-
-    let mut res = client.post(url).json(&req_body).send().await?;
+    let mut res = client.post(url).json(req_body).send().await?;
 
     let mut full_response = String::new();
 
@@ -73,124 +77,175 @@ async fn query_ollama_for_doc(
     Ok(full_response)
 }
 
-// FIXME: Consolidate with  query_ollama_for_doc, share some abstractions
-async fn query_ollama_for_project_overview(prompt: &str) -> Result<String, Box<dyn Error>> {
-    let client = Client::new();
-    let url = "http://localhost:11434/api/generate";
-
+/// Sends a doc-generation prompt, as built by a `LanguageStrategy`,
+/// along with the code to document
+pub async fn query_ollama_for_doc(prompt: Prompt, code: &str) -> Result<String, Box<dyn Error>> {
     let req_body = OllamaRequest {
-        model: "llama3.1".to_string(),
-        prompt: prompt.to_string(),
-        system: "You are an impersonal AI that summarizes the first 10 lines of a GitHub project's README. You should be very succinct and to the point, and just return a brief summary."
-                 .to_string(),
-        examples: vec![
-            (
-                "# RustyWeb - A Minimal Rust Web Server\n\nRustyWeb is a simple, lightweight web server built using Actix Web.  \nIt serves HTTP requests efficiently and is designed for ease of use.\n\n## Features\n- 🚀 Fast and lightweight\n- 🔧 Built with Actix Web\n- 📦 Supports JSON API responses\n\n## Installation\nTo install dependencies, run:".to_string(),
-                "A lightweight web server built with Actix Web, designed for fast HTTP handling and JSON API support.".to_string()
-            ),
-            (
-                "# RustyTodo - A Simple CLI Todo List\n\nRustyTodo is a minimalistic command-line todo list manager written in Rust.\nIt saves tasks to a file and allows easy task management.\n## Features\n- 📝 Add, remove, and list tasks\n- 💾 Persistent storage in a text file\n- 🦀 Built in Rust for speed and safety  \n\n## Usage\nRun the following command to start:".to_string(),
-                "A simple CLI-based todo list manager in Rust with persistent text file storage and task management commands.".to_string()
-            )
-        ],
+        model: MODEL.to_string(),
+        prompt: code.to_string(),
+        system: prompt.system,
+        examples: prompt.examples,
     };
 
-    let mut res = client.post(url).json(&req_body).send().await?;
-
-    let mut full_response = String::new();
-
-    while let Some(chunk) = res.chunk().await? {
-        let chunk_str = String::from_utf8_lossy(&chunk);
-        for line in chunk_str.lines() {
-            if let Ok(parsed) = serde_json::from_str::<OllamaChunk>(line) {
-                if let Some(text) = parsed.response {
-                    full_response.push_str(&text);
-                }
-            }
-        }
-    }
-
-    Ok(full_response)
+    generate(&req_body).await
 }
 
 // FIXME: This is sort of a mess in terms of abstractions.
-pub async fn process_repo(root_node: &RepoNode) -> Result<Vec<DredgerDoc>, Box<dyn Error>> {
-    let mut stack: Vec<&RepoNode> = vec![root_node];
+/// Generates module and item docs for every file of the repo in a
+/// language Dredger knows (see `languages::registry`). The summaries
+/// stored on `root_node` (see `summaries::summarize_repo`) go into each
+/// file's prompts as context, see `summaries::prompt_context`.
+///
+/// Docs a human already wrote are never touched: files with module docs
+/// don't get new ones, and documented items are skipped. In languages
+/// whose files share module docs (see
+/// `LanguageStrategy::shares_module_docs`), only one file per directory
+/// gets them. With
+/// `ExistingDocs::Improve`, documented items whose docs lack a required
+/// section (see `SourceItem::missing_sections`) get just those sections,
+/// appended below the existing text.
+///
+/// Items get an `# Examples` section as well when `examples.generate`
+/// is on and their language supports it.
+pub async fn process_repo(
+    root_node: &RepoNode,
+    repo_config: &RepoConfig,
+) -> Result<Vec<DredgerDoc>, Box<dyn Error>> {
+    // Each node is paired with the directory holding it
+    let mut stack: Vec<(&RepoNode, Option<&RepoNode>)> = vec![(root_node, None)];
     let mut doc_results = Vec::new();
-    let mut project_context = String::new();
+    // Directories whose files share module docs that one of them has
+    let mut documented_directories: HashSet<&str> = HashSet::new();
 
-    // Step 1: Extract Project-Level Context from README
-    // FIXME: Abstract this out
-    while let Some(node) = stack.pop() {
+    // Process source files with what the summaries say about their
+    // directory and the repo as context
+    while let Some((node, directory_node)) = stack.pop() {
         match node {
             RepoNode::File { path, content, .. } => {
-                if path.ends_with("README") || path.ends_with("README.md") {
-                    project_context = extract_project_context(content);
-                    break; // Stop after finding the README
-                }
-            }
-            RepoNode::Directory { children, .. } => {
-                for child in children {
-                    stack.push(child);
-                }
-            }
-        }
-    }
+                // TODO: Could probably learn invaluable info if we read non-language files
+                let Some(language) = registry::for_path(path) else {
+                    eprintln!("\n⏩ Skipping file in no known language: {}", path);
+                    continue;
+                };
 
-    // Step 1.5: Summarize the project context
-    // FIXME: Abstract this out!
-    let project_summary = if !project_context.is_empty() {
-        match query_ollama_for_project_overview(&project_context).await {
-            Ok(summary) => summary,
-            Err(e) => {
-                eprintln!("Error summarizing project context: {}", e);
-                String::new()
-            }
-        }
-    } else {
-        String::new()
-    };
+                let parsed_file = match language.parse(content) {
+                    Ok(parsed_file) => Some(parsed_file),
+                    Err(e) => {
+                        eprintln!("Could not parse {}, skipping its items: {}", path, e);
+                        None
+                    }
+                };
 
-    if !project_summary.is_empty() {
-        println!("📄 Project Summary:\n{}", project_summary);
-        // Replace project_context with summary if present; otherwise we will just
-        // use the raw first N lines of the README
-        project_context = project_summary
-    }
+                let directory = parent_directory(path);
+                let project_context = summaries::prompt_context(
+                    root_node,
+                    directory_node,
+                    language.describes_directory(path),
+                );
+                if language.has_module_docs(content) {
+                    eprintln!("\nKeeping the existing module docs of {}", path);
+                } else if language.shares_module_docs()
+                    && documented_directories.contains(directory)
+                {
+                    eprintln!(
+                        "\nKeeping the module docs {} shares with {}",
+                        path, directory
+                    );
+                } else {
+                    let prompt = language.module_prompt(path, &project_context, repo_config);
+                    match query_ollama_for_doc(prompt, content).await {
+                        Ok(response) => {
+                            let comments = language.extract_comments(&response, &DocAnchor::Module);
+                            // Docs that don't splice or verify would leave the
+                            // file without any, and a package that shares its
+                            // docs with it, too, if it was marked documented
+                            let (spliced, added, _) =
+                                language.splice_module_docs(content, &comments);
+                            if added == 0 {
+                                eprintln!("\nNo module docs to splice into {}", path);
+                            } else if let Err(reason) = language.verify(content, &spliced) {
+                                eprintln!("\nRejected the module docs for {}: {}", path, reason);
+                            } else {
+                                eprintln!("\n\nFound comments for {}:\n{}", path.clone(), comments);
+                                if language.shares_module_docs() {
+                                    documented_directories.insert(directory);
+                                }
+                                doc_results.push(DredgerDoc {
+                                    file_path: path.clone(),
+                                    anchor: DocAnchor::Module,
+                                    comments,
+                                    rationale: language.extract_rationale(&response),
+                                });
+                            }
+                        }
+                        Err(e) => eprintln!("Error querying Ollama for {}: {}", path, e),
+                    }
+                }
 
-    // Reinitialize stack
-    stack = vec![root_node];
+                // Then each public item on its own, so the model sees one
+                // signature and body at a time
+                let file_items = parsed_file
+                    .map(|parsed_file| parsed_file.items)
+                    .unwrap_or_default();
 
-    // Step 2: Process Rust files with project context
-    while let Some(node) = stack.pop() {
-        match node {
-            RepoNode::File { path, content, .. } => {
-                // Skip non-Rust files
-                // TODO: Could probably learn invaluable info if we read non-language files
-                // TODO: Handle non-rust repo (.rb files)
+                for item in file_items {
+                    let missing_sections = item.missing_sections();
+                    let missing_sections = match (&item.existing_docs, repo_config.existing_docs) {
+                        (None, _) => None,
+                        (Some(_), ExistingDocs::Improve) if !missing_sections.is_empty() => {
+                            Some(missing_sections.as_slice())
+                        }
+                        (Some(_), _) => continue,
+                    };
 
-                if !path.ends_with(".rs") {
-                    println!("\n⏩ Skipping non-Rust file: {}", path);
-                    continue;
-                }
+                    let prompt = language.item_prompt(
+                        &item,
+                        path,
+                        &project_context,
+                        missing_sections,
+                        repo_config,
+                    );
+                    let anchor = DocAnchor::Item {
+                        name: item.name.clone(),
+                        line: item.line,
+                        indent: item.indent.clone(),
+                    };
 
-                match query_ollama_for_doc(&project_context, &path, content).await {
-                    Ok(response) => {
-                        let comments = extract_comments(&response);
-                        if !comments.is_empty() {
-                            println!("\n\nFound comments for {}:\n{}", path.clone(), comments);
-                            doc_results.push(DredgerDoc {
-                                file_path: path.clone(),
-                                comments,
-                            });
+                    match query_ollama_for_doc(prompt, &item.source).await {
+                        Ok(response) => {
+                            let mut comments = language.extract_comments(&response, &anchor);
+                            if !comments.is_empty() {
+                                eprintln!("\nFound comments for {} in {}", item.name, path);
+                                if missing_sections.is_some() {
+                                    // Sets the new sections apart from the existing docs
+                                    let separator = language.section_separator(&item.indent);
+                                    comments.insert_str(0, &format!("{}\n", separator));
+                                }
+                                doc_results.push(DredgerDoc {
+                                    file_path: path.clone(),
+                                    anchor,
+                                    comments,
+                                    rationale: language.extract_rationale(&response),
+                                });
+                            }
+                        }
+                        Err(e) => {
+                            eprintln!("Error querying Ollama for {} in {}: {}", item.name, path, e)
                         }
                     }
-                    Err(e) => eprintln!("Error querying Ollama for {}: {}", path, e),
                 }
             }
             RepoNode::Directory { children, .. } => {
                 for child in children {
-                    stack.push(child);
+                    if let RepoNode::File { path, content, .. } = child {
+                        let has_shared_docs = registry::for_path(path)
+                            .filter(|language| language.shares_module_docs())
+                            .is_some_and(|language| language.has_module_docs(content));
+                        if has_shared_docs {
+                            documented_directories.insert(parent_directory(path));
+                        }
+                    }
+                    stack.push((child, Some(node)));
                 }
             }
         }
@@ -199,18 +254,7 @@ pub async fn process_repo(root_node: &RepoNode) -> Result<Vec<DredgerDoc>, Box<d
     Ok(doc_results)
 }
 
-fn extract_comments(output: &str) -> String {
-    output
-        .lines()
-        .filter(|line| line.trim().starts_with("//")) // Only keep comments (FIXME: Once prompt is better change this to //!)
-        .collect::<Vec<_>>()
-        .join("\n")
-}
-
-fn extract_project_context(readme: &str) -> String {
-    readme
-        .lines()
-        .take(10) // Extract first 10 lines (adjust as needed)
-        .collect::<Vec<&str>>()
-        .join("\n")
+/// The directory part of a repo path, empty at the root
+fn parent_directory(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(directory, _)| directory)
 }
@@ -0,0 +1,294 @@
+use super::errors::DredgerError;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+/// Default location of the Dredger config file, overridable via DREDGER_CONFIG
+const DEFAULT_CONFIG_PATH: &str = "dredger.json";
+
+/// Metadata applied to every pull request Dredger opens
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PullRequestConfig {
+    pub draft: bool,
+    pub labels: Vec<String>,
+    pub assignees: Vec<String>,
+    /// Title of an open milestone to put the PR into
+    pub milestone: Option<String>,
+}
+
+/// Where the docs generated by a run end up
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputMode {
+    /// Commit to a Dredger branch and open (or refresh) a pull request
+    #[default]
+    PullRequest,
+    /// Write patch files to a local directory, without any GitHub writes
+    Patch,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PatchFormat {
+    /// Plain unified diffs, for `git apply` or `patch`
+    #[default]
+    Diff,
+    /// `git format-patch` style mbox files, for `git am`
+    Mbox,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct PatchConfig {
+    pub dir: String,
+    pub format: PatchFormat,
+    /// One patch per file instead of a single combined patch
+    pub per_file: bool,
+}
+
+impl Default for PatchConfig {
+    fn default() -> Self {
+        PatchConfig {
+            dir: "dredger-patches".to_string(),
+            format: PatchFormat::default(),
+            per_file: true,
+        }
+    }
+}
+
+/// What to do with the docs when dredging a local checkout
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct LocalConfig {
+    /// Create a branch and commit the docs, instead of only writing them
+    pub commit: bool,
+    /// Branch to create, defaults to a unique Dredger branch name
+    pub branch: Option<String>,
+    pub author_name: String,
+    pub author_email: String,
+}
+
+impl Default for LocalConfig {
+    fn default() -> Self {
+        LocalConfig {
+            commit: false,
+            branch: None,
+            author_name: "Dredger".to_string(),
+            author_email: "dredger@users.noreply.github.com".to_string(),
+        }
+    }
+}
+
+/// What to do with items that already have docs. Either way, text a
+/// human wrote is never rewritten.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExistingDocs {
+    /// Leave documented items alone
+    #[default]
+    Skip,
+    /// Add the sections a documented item's docs lack, like `# Errors`
+    /// for a function returning a `Result`, below its existing docs
+    Improve,
+}
+
+/// What happens to a generated example that fails its checks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailedExample {
+    /// Keep it, fenced as ```` ```ignore ```` so doctests skip it
+    #[default]
+    Ignore,
+    Remove,
+}
+
+/// `# Examples` sections for public functions and methods
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ExamplesConfig {
+    pub generate: bool,
+    /// Also compile each example against the crate, when a Rust
+    /// toolchain is available. Off by default: compiling executes the
+    /// repo's own code (build scripts and proc macros).
+    pub compile: bool,
+    pub on_failure: FailedExample,
+}
+
+/// Docstring conventions for Python sections like arguments and return
+/// values
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DocstringStyle {
+    /// `Args:` / `Returns:` / `Raises:` blocks
+    #[default]
+    Google,
+    /// Underlined `Parameters` / `Returns` / `Raises` headings
+    Numpy,
+    /// Sphinx `:param x:` / `:returns:` / `:raises X:` fields
+    Rest,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PythonConfig {
+    pub docstring_style: DocstringStyle,
+}
+
+/// A README section Dredger can write
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadmeSection {
+    Overview,
+    Installation,
+    Usage,
+    Architecture,
+}
+
+impl ReadmeSection {
+    pub const ALL: [ReadmeSection; 4] = [
+        ReadmeSection::Overview,
+        ReadmeSection::Installation,
+        ReadmeSection::Usage,
+        ReadmeSection::Architecture,
+    ];
+
+    /// Name used in the section's markers, e.g. "usage"
+    pub fn name(&self) -> &'static str {
+        match self {
+            ReadmeSection::Overview => "overview",
+            ReadmeSection::Installation => "installation",
+            ReadmeSection::Usage => "usage",
+            ReadmeSection::Architecture => "architecture",
+        }
+    }
+
+    /// Heading of the section in a README Dredger creates
+    pub fn title(&self) -> &'static str {
+        match self {
+            ReadmeSection::Overview => "Overview",
+            ReadmeSection::Installation => "Installation",
+            ReadmeSection::Usage => "Usage",
+            ReadmeSection::Architecture => "Architecture",
+        }
+    }
+}
+
+/// README sections written from what the run learned about the repo.
+/// Only text between Dredger's markers is ever replaced.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ReadmeConfig {
+    pub generate: bool,
+    /// Sections to write, in the order a new README gets them
+    pub sections: Vec<ReadmeSection>,
+}
+
+impl Default for ReadmeConfig {
+    fn default() -> Self {
+        ReadmeConfig {
+            generate: false,
+            sections: ReadmeSection::ALL.to_vec(),
+        }
+    }
+}
+
+/// How doc coverage gets reported on the dredged commit
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CoverageReport {
+    /// A commit status, which works with any token
+    Status,
+    /// A check run with annotations on the undocumented items. Falls
+    /// back to a commit status when the token can't create check runs.
+    CheckRun,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct CoverageConfig {
+    /// Coverage isn't reported at all when unset
+    pub report: Option<CoverageReport>,
+    /// Coverage below this percentage fails the status or check run
+    pub min_percent: Option<f64>,
+}
+
+/// Settings that can differ from one dredged repo to the next
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct RepoConfig {
+    pub output: OutputMode,
+    pub pull_request: PullRequestConfig,
+    pub patch: PatchConfig,
+    pub local: LocalConfig,
+    /// Organization to fork into when the token can't push to the repo.
+    /// Forks go to the authenticated account when unset.
+    pub fork_organization: Option<String>,
+    pub coverage: CoverageConfig,
+    pub existing_docs: ExistingDocs,
+    pub examples: ExamplesConfig,
+    pub python: PythonConfig,
+    pub readme: ReadmeConfig,
+}
+
+/// Contents of `dredger.json`
+///
+/// Top-level settings apply to every repo. An entry under `repos`, keyed
+/// by "owner/name", replaces them entirely for that repo.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct DredgerConfig {
+    #[serde(flatten)]
+    pub defaults: RepoConfig,
+    pub repos: HashMap<String, RepoConfig>,
+}
+
+impl DredgerConfig {
+    /// Loads the config file, falling back to defaults if there isn't one
+    pub fn load() -> Result<Self, DredgerError> {
+        let path = env::var("DREDGER_CONFIG").unwrap_or_else(|_| DEFAULT_CONFIG_PATH.to_string());
+
+        if !Path::new(&path).exists() {
+            return Ok(DredgerConfig::default());
+        }
+
+        let content = fs::read_to_string(&path).map_err(DredgerError::IoError)?;
+        serde_json::from_str(&content).map_err(DredgerError::JsonError)
+    }
+
+    pub fn for_repo(&self, repo_owner: &str, repo_name: &str) -> RepoConfig {
+        self.repos
+            .get(&format!("{}/{}", repo_owner, repo_name))
+            .cloned()
+            .unwrap_or_else(|| self.defaults.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_for_repo_prefers_repo_entry() {
+        let config: DredgerConfig = serde_json::from_str(
+            r#"{
+                "pull_request": { "labels": ["documentation"] },
+                "repos": {
+                    "owner/special": { "pull_request": { "draft": true, "milestone": "Docs" } }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let default_repo = config.for_repo("owner", "other");
+        assert_eq!(default_repo.pull_request.labels, vec!["documentation"]);
+        assert!(!default_repo.pull_request.draft);
+
+        let special_repo = config.for_repo("owner", "special");
+        assert!(special_repo.pull_request.draft);
+        assert!(special_repo.pull_request.labels.is_empty());
+        assert_eq!(special_repo.pull_request.milestone.as_deref(), Some("Docs"));
+    }
+}
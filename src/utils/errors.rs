@@ -34,3 +34,8 @@ impl fmt::Display for DredgerError {
 
 // Implement the std::error::Error trait for the general DredgerError
 impl std::error::Error for DredgerError {}
+
+/// Wraps an error from the GitHub client, for `map_err`
+pub fn github_error(e: Box<dyn std::error::Error>) -> Box<DredgerError> {
+    Box::new(DredgerError::GithubClientError(e.to_string()))
+}
@@ -1,5 +1,6 @@
 use std::error::Error;
 use std::fmt;
+use std::path::Path;
 use tokenizers::Tokenizer;
 
 #[derive(Debug)]
@@ -35,3 +36,32 @@ pub fn count_tokens(content: &str, tokenizer: &Tokenizer) -> Result<usize, Token
 
     Ok(encoding.get_ids().len())
 }
+
+/// Loads the tokenizer.json at `tokenizer_path`
+pub fn load_tokenizer(tokenizer_path: &str) -> Result<Tokenizer, TokenizerError> {
+    if !Path::new(tokenizer_path).exists() {
+        return Err(TokenizerError::FileNotFound(tokenizer_path.to_string()));
+    }
+
+    Tokenizer::from_file(tokenizer_path).map_err(|e| TokenizerError::LoadError(e.to_string()))
+}
+
+/// Cuts `content` down to at most `budget` tokens, at a char boundary
+pub fn truncate_to_tokens(
+    content: &str,
+    budget: usize,
+    tokenizer: &Tokenizer,
+) -> Result<String, TokenizerError> {
+    let mut truncated = content.to_string();
+    let mut token_count = count_tokens(&truncated, tokenizer)?;
+    while token_count > budget {
+        // Cut in proportion to the overshoot, and by at least a tenth so
+        // this ends quickly even where tokens are uneven
+        let chars = truncated.chars().count();
+        let keep = (chars * budget / token_count).min(chars * 9 / 10);
+        truncated = truncated.chars().take(keep).collect();
+        token_count = count_tokens(&truncated, tokenizer)?;
+    }
+
+    Ok(truncated)
+}